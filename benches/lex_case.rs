@@ -0,0 +1,78 @@
+//! `lex_case!` 与手写 `match` 的字节分类性能对比
+//!
+//! 运行：`cargo bench --bench lex_case`
+//!
+//! 两种写法应当生成相同的代码，因此同一组中两项的耗时应在测量误差范围内一致；
+//! criterion 的报告（`target/criterion/lex_case/report`）会把两者放在同一张图中比较。
+
+use std::hint::black_box;
+
+use criterion::{Criterion, Throughput, criterion_group, criterion_main};
+use hgm::lex_case;
+
+const INPUT_LEN: usize = 1 << 16;
+
+#[derive(Clone, Copy)]
+enum Class {
+    Digit,
+    Alpha,
+    Space,
+    Punct,
+    Other,
+}
+
+#[inline(never)]
+fn classify_lex_case(byte: u8) -> Class {
+    lex_case!(byte =>
+        | b'0'..=b'9' => Class::Digit,
+        | b'a'..=b'z' | b'A'..=b'Z' | b'_' => Class::Alpha,
+        | b' ' | b'\t' | b'\n' | b'\r' => Class::Space,
+        | b'!'..=b'/' | b':'..=b'@' | b'['..=b'`' | b'{'..=b'~' => Class::Punct,
+        | _ => Class::Other,
+    )
+}
+
+#[inline(never)]
+fn classify_match(byte: u8) -> Class {
+    match byte {
+        b'0'..=b'9' => Class::Digit,
+        b'a'..=b'z' | b'A'..=b'Z' | b'_' => Class::Alpha,
+        b' ' | b'\t' | b'\n' | b'\r' => Class::Space,
+        b'!'..=b'/' | b':'..=b'@' | b'['..=b'`' | b'{'..=b'~' => Class::Punct,
+        _ => Class::Other,
+    }
+}
+
+/// 简单的线性同余生成器，保证每次运行的输入相同
+fn input() -> Vec<u8> {
+    let mut state: u32 = 0x2545_f491;
+    (0..INPUT_LEN)
+        .map(|_| {
+            state = state.wrapping_mul(1_103_515_245).wrapping_add(12_345);
+            (state >> 16) as u8 % 128
+        })
+        .collect()
+}
+
+fn count(bytes: &[u8], classify: fn(u8) -> Class) -> [usize; 5] {
+    let mut counts = [0; 5];
+    for &byte in bytes {
+        counts[classify(black_box(byte)) as usize] += 1;
+    }
+    counts
+}
+
+fn lex_case_vs_match(c: &mut Criterion) {
+    let bytes = input();
+    // 两种写法必须给出相同的分类结果，比较耗时才有意义
+    assert_eq!(count(&bytes, classify_lex_case), count(&bytes, classify_match));
+
+    let mut group = c.benchmark_group("lex_case");
+    group.throughput(Throughput::Bytes(INPUT_LEN as u64));
+    group.bench_function("lex_case!", |b| b.iter(|| count(black_box(&bytes), classify_lex_case)));
+    group.bench_function("match", |b| b.iter(|| count(black_box(&bytes), classify_match)));
+    group.finish();
+}
+
+criterion_group!(benches, lex_case_vs_match);
+criterion_main!(benches);
@@ -1,14 +1,85 @@
 //
 //! # Haskell 风格语法糖宏
 //!
-//! 本模块提供了三个宏，用于在 Rust 中模拟 Haskell 的 Guards 和模式匹配语法。
+//! 本模块提供了一组宏，用于在 Rust 中模拟 Haskell 的 Guards 和模式匹配语法。
 //!
 //! - [`guard!`] - Haskell 风格的 guard 表达式
 //! - [`fn_guard!`] - 带 guard 语法的函数定义
+//! - [`named_guard!`] - 带标签的 guard 函数，每个条件另外生成可单独调用的谓词
 //! - [`case!`] - Haskell 风格的 case 表达式
-#[allow(dead_code)]
+//! - [`case_result!`] - 返回 `Result` 的 case 表达式，未命中时错误中携带被匹配的值
+//! - [`case_iter!`] - 各分支返回不同迭代器类型的 case 表达式，结果统一为 [`Either`]
+//! - [`case_all!`] - 对多个值分别执行 case 匹配并返回元组
+//! - [`compute_then_case!`] - 先计算共享的 where 绑定，再执行 case 匹配
+//! - [`case_cow!`] - 结果统一为 `Cow<str>` 的 case 表达式，借用的分支不分配内存
+//! - [`case_labeled!`] - 同时返回命中分支标签的 case 表达式
+//! - [`guard_into!`] - 分支结果统一经 `Into` 转换为目标类型的 guard 表达式
+//! - [`guard_do!`] - 分支带副作用的 guard 表达式，保证只执行命中的分支
+//! - [`guard_result!`] - 返回 `Result` 的 guard 表达式
+//! - [`guard_opt!`] - 返回 `Option` 的 guard 表达式，没有分支命中时为 `None`
+//! - [`guard_chain!`] - 组合多组 guard 规则，前一组都不命中时才求值下一组
+//! - [`guard_flow!`] - 返回 `ControlFlow` 的 guard 表达式
+//! - [`first_ok!`] - 依次尝试多个可失败的计算，返回第一个 `Ok` / `Some`
+//! - [`guard_ref!`] - 返回引用的 guard 表达式，分支在共同的引用类型下检查
+//! - [`guard_weighted!`] - 在条件成立的分支中按权重随机选择，随机数由调用者提供
+//! - [`guard_first_ok!`] - 依次尝试条件成立的分支，返回第一个成功结果
+//! - [`guard_each!`] - 依次执行所有条件成立的分支，值为 `()`
+//! - [`guard_iter!`] - 惰性产出所有条件成立分支结果的迭代器
+//! - [`guard_debug!`] - 启用 `debug` 特性时同时返回结果与 where 绑定值的 guard 表达式
+//! - [`guard_debug_arms!`] - 不求值，只收集 guard 各分支条件的文本
+//! - [`guard_with_reason!`] - 连同命中分支的条件文本一起返回结果
+//! - [`transitions!`] - 基于 case 的状态机转移表
+//! - [`lex_case!`] - 面向 `u8` / `char` 词法分析的 case 表达式
+//! - [`case_discriminant!`] - 按枚举判别值的整数区间分派的 case 表达式
+//! - [`case_matches!`] - 只判断是否有分支命中、返回 `bool` 的 case 表达式
+//! - [`cmp_case!`] - 对 `Ordering` 三路比较结果的 case 表达式，省略 `Ordering::` 前缀
+//! - [`use_variants!`] - 把枚举的变体导入当前作用域，case 分支可以省略枚举前缀
+//! - [`case_arms!`] - 定义可在多个 case 表达式中复用的分支片段
+//! - [`case_len!`] - 按长度分派的 case 表达式，分支中仍可使用被匹配的切片
+//! - [`map_case!`] / [`filter_case!`] - 用于迭代器适配器的 case 闭包
+//! - [`dispatch!`] - 按键分派到处理函数的 case 闭包，用于命令路由
+//! - [`names!`] - 列出枚举变体，生成返回变体名的 case 闭包
+//!
+//! 另外提供运行时函数 [`first_matching`]，用于规则以数据形式给出的场景；
+//! 以及统一两种迭代器类型的 [`Either`]。
+//!
+//! 默认分支写作 `| otherwise => ...`，与 Haskell 一致；常量 [`otherwise`]
+//! 也从 crate 根导出，供需要把它当作普通表达式使用的场景。
+//!
+//! # Features
+//!
+//! - `std`（默认启用）：依赖标准库的扩展，例如 [`guard_result!`] 的 `err_box` 字符串错误；包含 `alloc`
+//! - `alloc`：只需要 `alloc` 的扩展，例如 [`case_cow!`] 与 `fn_guard!` 的 `#[boxed]`，可用于 `no_std` + `alloc` 的 crate
+//! - `strict`：为 [`case!`] 与 [`guard!`] 启用额外的编译期检查
+//! - `profile`：统计 `guard!(instrument; ...)` 每个分支的求值与命中次数，见 `hgm::profile` 模块
+//! - `tracing`：`guard!(instrument; ...)` 的分支命中时发出 `tracing::trace!` 事件，字段为调用位置 `site`、分支序号 `arm` 与条件文本 `condition`
+//! - `debug`：[`guard_debug!`] 额外返回 where 绑定的值，用于查看中间结果
+//! - `nightly`：在 nightly 工具链上启用依赖不稳定语言特性的测试与示例（如 `box` 模式）
+//! - `rand`：[`guard_weighted!`] 可以直接接受 `rand::Rng` 作为随机数来源
+#![cfg_attr(not(any(feature = "std", test)), no_std)]
+#![cfg_attr(all(test, feature = "nightly"), feature(box_patterns))]
+
+#[cfg(feature = "alloc")]
+extern crate alloc;
+
+/// Haskell 中的 `otherwise`
+///
+/// Haskell 里 `otherwise` 只是 `True` 的别名，这里同样定义为 `true`。
+///
+/// [`guard!`]、[`fn_guard!`]、[`guard_result!`] 等宏会直接识别字面量 `otherwise` 分支，
+/// 不需要导入它；只有在宏之外把 `otherwise` 当作普通布尔表达式使用时，
+/// 才需要 `use hgm::otherwise;`。
+///
+/// ```
+/// use hgm::{guard_result, otherwise};
+///
+/// let parsed: Result<u8, ()> = guard_result!(| otherwise => 1, err = ());
+/// assert_eq!(parsed, Ok(1));
+/// assert!(otherwise);
+/// ```
 #[allow(non_upper_case_globals)]
-const otherwise: bool = true;
+pub const otherwise: bool = true;
+
 /// Haskell 风格的 Guard 表达式宏
 ///
 /// 模拟 Haskell 中的 guards 语法，允许使用 `| condition => result` 的形式
@@ -29,42 +100,40 @@ const otherwise: bool = true;
 ///     | otherwise => default_result,
 ///     where binding1 = value1, binding2 = value2,  // 可选的 where 子句
 /// )
+///
+/// guard!(
+///     on name = expression;  // 先求值一次并绑定到 name（也可以是解构模式）
+///     | condition(name) => result,
+///     | otherwise => name,
+/// )
 /// ```
 ///
 /// # 特性
 ///
-/// - 支持 `otherwise` 关键字作为默认分支（类似 Haskell）
-/// - 支持 `where` 子句定义局部绑定
+/// - 支持 `otherwise` 关键字作为默认分支（类似 Haskell），字面量 `true` 条件同样视为兜底分支
+/// - 支持 `where` 子句定义局部绑定，可写作 `where a = 1, b = 2` 或 `where { a = 1; b = 2; }`
+/// - `where` 中的可失败模式写作 `Some(v) = opt else { return None }`，展开为 `let ... else`
+/// - 支持 `on` 前缀：被检测的值只求值一次，所有分支（包括 `otherwise`）均可使用
+/// - 条件可以是以 `let` 开头的 let-chain，绑定的变量在分支结果中可用
+/// - 分支逐批累积展开，数百个分支的长链也不会触及默认的宏递归上限
+/// - 条件前可加 `[likely]` / `[unlikely]` 分支预测提示，不改变语义
+/// - 连续的 `| a, | b => r` 共用一个结果（或），同一分支中的 `&&` 表示与
+/// - 支持 `apply value;` 前缀：每个条件都是以 `value` 为参数调用的谓词
+/// - 支持 `map = f;` 前缀：对选中的分支结果统一调用 `f`
+/// - 支持 `: Type;` 前缀：标注结果类型，分支中的字面量据此推断
+/// - 支持 `default d; where? x = opt;` 前缀：任一可选绑定为 `None` 时整体取 `d`，不检查分支
+/// - 分支结果可以是 `panic!`、`unreachable!`、`return` 等发散表达式，与其它分支的类型自然统一
 /// - 如果没有 `otherwise` 且所有条件都不满足，将会 panic
+/// - 总是展开为单个 `{ ... }` 块表达式，可直接用作 `match` 分支体、闭包体、数组元素、
+///   结构体字段与函数参数，或在其后接 `?`、方法调用，无需额外的括号
 ///
 /// # Examples
 ///
 /// ## 基本用法
 ///
 /// ```
-/// # macro_rules! guard {
-/// #     (
-/// #       $(| $cond:expr => $result:expr),+ $(,)?,
-/// #       where $($binding:ident = $val:expr),+ $(,)?
-/// #     ) => {{
-/// #       $(let $binding = $val;)+
-/// #        guard!($(| $cond => $result),+)
-/// #     }};
-/// #     (| otherwise => $result:expr $(,)?) => {
-/// #        $result
-/// #     };
-/// #     (| $cond:expr => $result:expr,$($rest:tt)+) => {
-/// #       if $cond {
-/// #            $result
-/// #        }
-/// #       else{
-/// #            guard!($($rest)+)
-/// #        }
-/// #     };
-/// #     (| $cond:expr => $result:expr $(,)?) => {
-/// #         if $cond {$result} else {panic!("Non-exhaustive guards")}
-/// #     };
-/// # }
+/// use hgm::guard;
+///
 /// let x = 10;
 /// let result = guard!(
 ///     | x < 0 => "negative",
@@ -78,30 +147,8 @@ const otherwise: bool = true;
 /// ## 使用 where 子句
 ///
 /// ```
-/// # const otherwise:bool = true;
-/// # macro_rules! guard {
-/// #     (
-/// #       $(| $cond:expr => $result:expr),+ $(,)?,
-/// #       where $($binding:ident = $val:expr),+ $(,)?
-/// #     ) => {{
-/// #       $(let $binding = $val;)+
-/// #        guard!($(| $cond => $result),+)
-/// #     }};
-/// #     (| otherwise => $result:expr $(,)?) => {
-/// #        $result
-/// #     };
-/// #     (| $cond:expr => $result:expr,$($rest:tt)+) => {
-/// #       if $cond {
-/// #            $result
-/// #        }
-/// #       else{
-/// #            guard!($($rest)+)
-/// #        }
-/// #     };
-/// #     (| $cond:expr => $result:expr $(,)?) => {
-/// #         if $cond {$result} else {panic!("Non-exhaustive guards")}
-/// #     };
-/// # }
+/// use hgm::guard;
+///
 /// let a = 3;
 /// let b = 4;
 /// let result = guard!(
@@ -113,538 +160,8396 @@ const otherwise: bool = true;
 /// assert_eq!(result, "medium");
 /// ```
 ///
-/// # Panics
+/// where 绑定也可以写成花括号包裹、分号分隔的形式，更接近普通的 Rust 代码块。
+/// 这一写法同样适用于 [`case!`] 与 [`fn_guard!`]：
 ///
-/// 当所有条件都不满足且没有 `otherwise` 分支时，会触发 panic：
+/// ```
+/// use hgm::guard;
 ///
-/// ```should_panic
-/// # const otherwise:bool =  true;
-/// # macro_rules! guard {
-/// #     (
-/// #       $(| $cond:expr => $result:expr),+ $(,)?,
-/// #       where $($binding:ident = $val:expr),+ $(,)?
-/// #     ) => {{
-/// #       $(let $binding = $val;)+
-/// #        guard!($(| $cond => $result),+)
-/// #     }};
-/// #     (| otherwise => $result:expr $(,)?) => {
-/// #        $result
-/// #     };
-/// #     (| $cond:expr => $result:expr,$($rest:tt)+) => {
-/// #       if $cond {
-/// #            $result
-/// #        }
-/// #       else{
-/// #            guard!($($rest)+)
-/// #        }
-/// #     };
-/// #     (| $cond:expr => $result:expr $(,)?) => {
-/// #         if $cond {$result} else {panic!("Non-exhaustive guards")}
-/// #     };
-/// # }
-/// let x = 0;
-/// guard!(
-///     | x > 0 => "positive",
-///     | x < 0 => "negative",
-///     // 缺少 otherwise，x == 0 时会 panic
+/// let (w, h) = (3.0_f64, 4.0_f64);
+/// let shape = guard!(
+///     | ratio > 2.0 => "wide",
+///     | ratio < 0.5 => "tall",
+///     | otherwise => "squarish",
+///     where {
+///         ratio = w / h;
+///     }
 /// );
+/// assert_eq!(shape, "squarish");
 /// ```
-#[macro_export]
-macro_rules! guard {
-    (
-      $(| $cond:expr => $result:expr),+ $(,)?,
-      where $($binding:ident = $val:expr),+ $(,)?
-    ) => {{
-      $(let $binding = $val;)+
-       guard!($(| $cond => $result),+)
-    }};
-    (| otherwise => $result:expr $(,)?) => {
-       $result
-    };
-    (| $cond:expr => $result:expr,$($rest:tt)+) => {
-      if $cond {
-           $result
-       }
-      else{
-           guard!($($rest)+)
-       }
-    };
-    (| $cond:expr => $result:expr $(,)?) => {
-        if $cond {$result} else {panic!("Non-exhaustive guards")}
-    };
-}
-
-/// Haskell 风格的 Guard 函数定义宏
 ///
-/// 允许使用 Haskell guards 语法直接定义函数，将函数签名与 guard 条件
-/// 结合在一起，类似于 Haskell 的：
+/// 绑定的值是普通表达式，因此可以是包含多条语句的块：
+/// `where width = { let t = text.trim(); t.len() + 2 }`。
 ///
-/// ```haskell
-/// abs :: Int -> Int
-/// abs n
-///     | n < 0     = -n
-///     | otherwise = n
-/// ```
+/// 绑定左侧也可以是不可反驳的解构模式，例如 `where (q, r) = (n / d, n % d)`；
+/// 模式内的逗号不会被当作绑定之间的分隔符。
 ///
-/// # 语法
+/// 绑定左侧也可以是可失败的模式，后面跟一个必须发散的 `else` 块，
+/// 展开为 `let ... else`，适合“绑定成功才继续，否则提前返回”的场景：
 ///
-/// ```text
-/// fn_guard!(
-///     #[attributes]           // 可选的属性
-///     pub fn name(arg1: Type1, arg2: Type2) -> ReturnType
-///     | condition1 => result1,
-///     | condition2 => result2,
-///     | otherwise => default,
-///     where binding = value,  // 可选的 where 子句
-/// );
 /// ```
+/// use hgm::fn_guard;
 ///
-/// # 特性
+/// fn_guard!(
+///     fn port_class(addr: &str) -> Option<&'static str>
+///     | port < 1024 => Some("system"),
+///     | port < 49152 => Some("registered"),
+///     | otherwise => Some("dynamic"),
+///     where Some((_, raw)) = addr.rsplit_once(':') else { return None },
+///           Ok(port) = raw.parse::<u16>() else { return None }
+/// );
 ///
-/// - 支持可见性修饰符（`pub`, `pub(crate)` 等）
-/// - 支持函数属性（`#[inline]`, `#[must_use]` 等）
-/// - 支持有返回值和无返回值（`-> ()`）的函数
-/// - 支持 `where` 子句定义局部绑定
+/// assert_eq!(port_class("localhost:80"), Some("system"));
+/// assert_eq!(port_class("localhost:8080"), Some("registered"));
+/// assert_eq!(port_class("localhost"), None);
+/// assert_eq!(port_class("localhost:http"), None);
+/// ```
 ///
-/// # Examples
+/// ## 使用 on 绑定被检测的值
 ///
-/// ## 基本函数定义
+/// `on pattern = expr;` 会先执行 `let pattern = expr;`，再进入 guard 链；
+/// `on name;` 是 `on name = name;` 的简写。模式必须是不可反驳的，
+/// 因此可以用来解构结构体或元组，让各分支直接使用字段名。
 ///
 /// ```
-/// # const otherwise:bool = true;
-/// # macro_rules! guard {
-/// #     (
-/// #       $(| $cond:expr => $result:expr),+ $(,)?,
-/// #       where $($binding:ident = $val:expr),+ $(,)?
-/// #     ) => {{
-/// #       $(let $binding = $val;)+
-/// #        guard!($(| $cond => $result),+)
-/// #     }};
-/// #     (| otherwise => $result:expr $(,)?) => {
-/// #        $result
-/// #     };
-/// #     (| $cond:expr => $result:expr,$($rest:tt)+) => {
-/// #       if $cond {
-/// #            $result
-/// #        }
-/// #       else{
-/// #            guard!($($rest)+)
-/// #        }
-/// #     };
-/// #     (| $cond:expr => $result:expr $(,)?) => {
-/// #         if $cond {$result} else {panic!("Non-exhaustive guards")}
-/// #     };
-/// # }
-/// # macro_rules! fn_guard {
-/// #     (
-/// #         $(#[$attr:meta])*
-/// #         $vis:vis fn $name:ident($($param:ident : $ptype:ty),* $(,)?) -> $ret:ty
-/// #         $(| $cond:expr => $result:expr),+ $(,)?,
-/// #         $(where $($binding:ident = $val:expr),+ $(,)?)?
-/// #     ) => {
-/// #         $(#[$attr])*
-/// #         $vis fn $name($($param: $ptype),*) -> $ret {
-/// #             $($(let $binding = $val;)+)?
-/// #             guard!($(| $cond => $result),+)
-/// #         }
-/// #     };
-/// #     (
-/// #         $(#[$attr:meta])*
-/// #         $vis:vis fn $name:ident($($param:ident : $ptype:ty),* $(,)?)
-/// #         $(| $cond:expr => $result:expr),+ $(,)?,
-/// #         $(where $($binding:ident = $val:expr),+ $(,)?)?
-/// #     ) => {
-/// #         $(#[$attr])*
-/// #         $vis fn $name($($param: $ptype),*) {
-/// #             $($(let $binding = $val;)+)?
-/// #             guard!($(| $cond => $result),+)
-/// #         }
-/// #     };
-/// # }
-/// fn_guard!(
-///     fn abs(n: i32) -> i32
-///     | n < 0 => -n,
+/// use hgm::guard;
+///
+/// let input = "-42";
+/// let clamped = guard!(
+///     on n = input.parse::<i32>().unwrap();
+///     | n < 0 => 0,
+///     | n > 100 => 100,
 ///     | otherwise => n,
 /// );
+/// assert_eq!(clamped, 0);
+/// ```
 ///
-/// assert_eq!(abs(-5), 5);
-/// assert_eq!(abs(3), 3);
 /// ```
+/// use hgm::guard;
 ///
-/// ## 带属性和 where 子句
+/// struct Point { x: i32, y: i32 }
 ///
-/// ```
-/// # const otherwise:bool = true;
-/// # macro_rules! guard {
-/// #     (
-/// #       $(| $cond:expr => $result:expr),+ $(,)?,
-/// #       where $($binding:ident = $val:expr),+ $(,)?
-/// #     ) => {{
-/// #       $(let $binding = $val;)+
-/// #        guard!($(| $cond => $result),+)
-/// #     }};
-/// #     (| otherwise => $result:expr $(,)?) => {
-/// #        $result
-/// #     };
-/// #     (| $cond:expr => $result:expr,$($rest:tt)+) => {
-/// #       if $cond {
-/// #            $result
-/// #        }
-/// #       else{
-/// #            guard!($($rest)+)
-/// #        }
-/// #     };
-/// #     (| $cond:expr => $result:expr $(,)?) => {
-/// #         if $cond {$result} else {panic!("Non-exhaustive guards")}
-/// #     };
-/// # }
-/// # macro_rules! fn_guard {
-/// #     (
-/// #         $(#[$attr:meta])*
-/// #         $vis:vis fn $name:ident($($param:ident : $ptype:ty),* $(,)?) -> $ret:ty
-/// #         $(| $cond:expr => $result:expr),+ $(,)?,
-/// #         $(where $($binding:ident = $val:expr),+ $(,)?)?
-/// #     ) => {
-/// #         $(#[$attr])*
-/// #         $vis fn $name($($param: $ptype),*) -> $ret {
-/// #             $($(let $binding = $val;)+)?
-/// #             guard!($(| $cond => $result),+)
-/// #         }
-/// #     };
-/// #     (
-/// #         $(#[$attr:meta])*
-/// #         $vis:vis fn $name:ident($($param:ident : $ptype:ty),* $(,)?)
-/// #         $(| $cond:expr => $result:expr),+ $(,)?,
-/// #         $(where $($binding:ident = $val:expr),+ $(,)?)?
-/// #     ) => {
-/// #         $(#[$attr])*
-/// #         $vis fn $name($($param: $ptype),*) {
-/// #             $($(let $binding = $val;)+)?
-/// #             guard!($(| $cond => $result),+)
-/// #         }
-/// #     };
-/// # }
-/// fn_guard!(
-///     #[inline]
-///     pub fn bmi_category(weight: f64, height: f64) -> &'static str
-///     | bmi < 18.5 => "Underweight",
-///     | bmi < 25.0 => "Normal",
-///     | bmi < 30.0 => "Overweight",
-///     | otherwise => "Obese",
-///     where bmi = weight / (height * height),
+/// let p = Point { x: 3, y: -3 };
+/// let quadrant = guard!(
+///     on Point { x, y } = p;
+///     | x == 0 || y == 0 => "axis",
+///     | x > 0 && y > 0 => "I",
+///     | x > 0 => "IV",
+///     | y > 0 => "II",
+///     | otherwise => "III",
 /// );
+/// assert_eq!(quadrant, "IV");
+/// ```
+///
+/// ## let-chain 条件
+///
+/// 以 `let` 开头的条件按 let-chain 展开为 `if let ... && ...`，
+/// 其中绑定的变量在该分支的结果中可用。条件必须以 `let` 开头；
+/// 需要先做布尔判断时，把它写在 `let` 之后的 `&&` 链中。
 ///
-/// assert_eq!(bmi_category(70.0, 1.75), "Normal");
 /// ```
+/// use hgm::guard;
 ///
-/// ## 无返回值函数
+/// let input: Option<&str> = Some("42");
+/// let value = guard!(
+///     | let Some(text) = input && let Ok(n) = text.parse::<i32>() && n > 0 => n,
+///     | let Some(text) = input => -(text.len() as i32),
+///     | otherwise => 0,
+/// );
+/// assert_eq!(value, 42);
+/// ```
+///
+/// ## 以同一个值调用谓词
+///
+/// 以 `apply 值;` 开头时，值只求值一次，之后每个条件都是一个谓词（函数或闭包），
+/// 展开为以该值为参数的调用：`| is_even => ...` 相当于 `| is_even(值) => ...`。
+/// 值按值传给每个谓词，因此应为 `Copy` 类型，或写成引用 `apply &value;`。
 ///
 /// ```
-/// # const otherwise:bool = true;
-/// # macro_rules! guard {
-/// #     (
-/// #       $(| $cond:expr => $result:expr),+ $(,)?,
-/// #       where $($binding:ident = $val:expr),+ $(,)?
-/// #     ) => {{
-/// #       $(let $binding = $val;)+
-/// #        guard!($(| $cond => $result),+)
-/// #     }};
-/// #     (| otherwise => $result:expr $(,)?) => {
-/// #        $result
-/// #     };
-/// #     (| $cond:expr => $result:expr,$($rest:tt)+) => {
-/// #       if $cond {
-/// #            $result
-/// #        }
-/// #       else{
-/// #            guard!($($rest)+)
-/// #        }
-/// #     };
-/// #     (| $cond:expr => $result:expr $(,)?) => {
-/// #         if $cond {$result} else {panic!("Non-exhaustive guards")}
-/// #     };
-/// # }
-/// # macro_rules! fn_guard {
-/// #     (
-/// #         $(#[$attr:meta])*
-/// #         $vis:vis fn $name:ident($($param:ident : $ptype:ty),* $(,)?) -> $ret:ty
-/// #         $(| $cond:expr => $result:expr),+ $(,)?,
-/// #         $(where $($binding:ident = $val:expr),+ $(,)?)?
-/// #     ) => {
-/// #         $(#[$attr])*
-/// #         $vis fn $name($($param: $ptype),*) -> $ret {
-/// #             $($(let $binding = $val;)+)?
-/// #             guard!($(| $cond => $result),+)
-/// #         }
-/// #     };
-/// #     (
-/// #         $(#[$attr:meta])*
-/// #         $vis:vis fn $name:ident($($param:ident : $ptype:ty),* $(,)?)
-/// #         $(| $cond:expr => $result:expr),+ $(,)?,
-/// #         $(where $($binding:ident = $val:expr),+ $(,)?)?
-/// #     ) => {
-/// #         $(#[$attr])*
-/// #         $vis fn $name($($param: $ptype),*) {
-/// #             $($(let $binding = $val;)+)?
-/// #             guard!($(| $cond => $result),+)
-/// #         }
-/// #     };
-/// # }
-/// fn_guard!(
-///     fn log_level(level: u8)
-///     | level == 0 => println!("DEBUG"),
-///     | level == 1 => println!("INFO"),
-///     | level == 2 => println!("WARN"),
-///     | otherwise => println!("ERROR"),
+/// use hgm::guard;
+///
+/// fn is_negative(n: i32) -> bool { n < 0 }
+/// fn is_even(n: i32) -> bool { n % 2 == 0 }
+///
+/// let describe = |n: i32| guard!(
+///     apply n;
+///     | is_negative => "negative",
+///     | is_even => "even",
+///     | |n| n > 100 => "large odd",
+///     | otherwise => "odd",
 /// );
+/// assert_eq!(describe(-3), "negative");
+/// assert_eq!(describe(8), "even");
+/// assert_eq!(describe(101), "large odd");
+/// assert_eq!(describe(7), "odd");
 /// ```
-#[macro_export]
-macro_rules! fn_guard {
-    // 带 where 子句
-    (
-        $(#[$attr:meta])*
-        $vis:vis fn $name:ident($($param:ident : $ptype:ty),* $(,)?) -> $ret:ty
-        $(| $cond:expr => $result:expr),+ $(,)?,
-        $(where $($binding:ident = $val:expr),+ $(,)?)?
-    ) => {
-        $(#[$attr])*
-        $vis fn $name($($param: $ptype),*) -> $ret {
-            $($(let $binding = $val;)+)?
-            guard!($(| $cond => $result),+)
-        }
-    };
-
-    // 无返回值 (-> ())
-    (
-        $(#[$attr:meta])*
-        $vis:vis fn $name:ident($($param:ident : $ptype:ty),* $(,)?)
-        $(| $cond:expr => $result:expr),+ $(,)?,
-        $(where $($binding:ident = $val:expr),+ $(,)?)?
-    ) => {
-        $(#[$attr])*
-        $vis fn $name($($param: $ptype),*) {
-            $($(let $binding = $val;)+)?
-            guard!($(| $cond => $result),+)
-        }
-    };
-}
-
-/// Haskell 风格的 Case 表达式宏
 ///
-/// 模拟 Haskell 中的 case 表达式语法，使用 `| pattern => result` 的形式
-/// 进行模式匹配，类似于 Haskell 的：
+/// ## 多个条件共用一个结果
+///
+/// 连续写出几个只有条件、没有 `=>` 的分支时，它们会一直累积到下一个 `=>`，
+/// 共用这个结果：`| a, | b, | c => r` 等价于 `| a || b || c => r`，条件按书写顺序
+/// 短路求值。与 `match` 中的 `|` 一样，每个 `|` 开始的都是一个**可选项**（或）；
+/// 需要多个条件同时成立（与）时，在同一个分支里用 `&&` 连接。
+/// `otherwise` 不能与其它条件组合，带 `let` 的条件也不能参与组合。
 ///
-/// ```haskell
-/// case x of
-///     pattern1 -> result1
-///     pattern2 | guard -> result2
-///     _        -> defaultResult
 /// ```
+/// use hgm::guard;
 ///
-/// # 语法
+/// let weekday = |day: &str| guard!(
+///     | day == "sat",
+///     | day == "sun" => "weekend",
+///     | day.len() == 3 && day.is_ascii() => "weekday",
+///     | otherwise => "unknown",
+/// );
+/// assert_eq!(weekday("sun"), "weekend");
+/// assert_eq!(weekday("tue"), "weekday");
+/// assert_eq!(weekday("tuesday"), "unknown");
+/// ```
+///
+/// ## 字面量 `true` 作为兜底分支
+///
+/// 与 Haskell 中的 `| True = ...` 一样，条件是字面量 `true` 的分支等价于 `otherwise`。
+/// 它之后的分支永远不会被检查，但仍然保留在展开结果中，照常参与名称解析与类型检查；
+/// 调用处会得到一条 `unused_must_use` 警告，提示把兜底分支移到最后。写在中间的
+/// `| otherwise =>` 受到同样的处理。`guard_debug_arms!`、`iter` 与 `each` 模式中
+/// 这些分支照常列出或执行。条件是字面量 `false` 的分支
+/// 保持原样，编译器会把这样的 `if` 整体优化掉，其结果表达式永远不会求值。
 ///
-/// ```text
-/// case!(expression =>
-///     | pattern1 => result1,
-///     | pattern2 if guard => result2,  // 支持 guard 条件
-///     | _ => default,
-/// )
 /// ```
+/// use hgm::guard;
 ///
-/// # 特性
+/// let n = 7;
+/// let parity = guard!(
+///     | n % 2 == 0 => "even",
+///     | false => "never",
+///     | true => "odd",
+/// );
+/// assert_eq!(parity, "odd");
+/// ```
 ///
-/// - 使用 `|` 前缀使语法更接近 Haskell
-/// - 支持模式守卫（pattern guards）：`| pattern if condition => result`
-/// - 底层编译为标准 Rust `match` 表达式，保持完整的模式匹配能力
-/// - 支持所有 Rust 模式语法（结构体解构、元组、枚举等）
+/// ## 分支预测提示
 ///
-/// # Examples
+/// 条件前可以写 `[likely]` 或 `[unlikely]`，向编译器提示该分支命中的可能性。
+/// 标记不改变条件的求值顺序与结果；在稳定版 Rust 上通过调用 `#[cold]`
+/// 函数实现，效果取决于优化器，只在确有测量依据的热点 guard 链中使用。
 ///
-/// ## 基本模式匹配
+/// ```
+/// use hgm::guard;
 ///
+/// let byte = b'x';
+/// let class = guard!(
+///     | [likely] byte.is_ascii_lowercase() => "lower",
+///     | [unlikely] byte == 0 => "nul",
+///     | otherwise => "other",
+/// );
+/// assert_eq!(class, "lower");
 /// ```
-/// # macro_rules! case {
-/// #     (@acc $x:expr => [$($arms:tt)*]) => {
-/// #         match $x {$($arms)*}
-/// #     };
-/// #     (@acc $x:expr => [$($arms:tt)*] | $pat:pat if $guard:expr => $result:expr $(,$($rest:tt)*)?) => {
-/// #         case!(@acc $x => [$($arms)* $pat if $guard => $result,] $($($rest)*)?)
-/// #     };
-/// #     (@acc $x:expr => [$($arms:tt)*] | $pat:pat => $result:expr $(,$($rest:tt)*)?) => {
-/// #         case!(@acc $x => [$($arms)* $pat => $result,] $($($rest)*)?)
-/// #     };
-/// #     ($x:expr=>$($rest:tt)+) => {
-/// #         case!(@acc $x =>[] $($rest)+)
-/// #     };
-/// # }
-/// let x = Some(42);
-/// let result = case!(x =>
-///     | None => "nothing",
-///     | Some(0) => "zero",
-///     | Some(_) => "something",
+///
+/// ## 对选中的结果统一变换
+///
+/// 以 `map = 函数;` 开头时，该函数（通常是闭包）只作用于最终选中的分支结果，
+/// 无需在每个分支里重复 `.to_string()`、`Some(...)` 之类的包装。
+/// 可以与 `on` 前缀和 `where` 子句同时使用（`map` 写在最前面）。
+///
+/// ```
+/// use hgm::guard;
+///
+/// let n = 7;
+/// let label = guard!(
+///     map = |s: &str| s.to_uppercase();
+///     | n % 2 == 0 => "even",
+///     | otherwise => kind,
+///     where kind = "odd"
 /// );
-/// assert_eq!(result, "something");
+/// assert_eq!(label, "ODD");
 /// ```
 ///
-/// ## 使用模式守卫（Pattern Guards）
+/// ## 可选绑定缺失时直接取默认值
+///
+/// 以 `default 值;` 开头，随后写一个或多个 `where? 名字 = 可选值;` 时，
+/// 每个可选值按书写顺序求值；只要有一个是 `None`，整个 `guard!` 立即取默认值，
+/// 不再检查任何分支，相当于 guard 级别的提前返回。全部为 `Some` 时，
+/// 内部的值绑定到对应的名字上，之后的绑定与所有分支都可以使用。
+/// 默认值只在需要时求值。
 ///
 /// ```
-/// # macro_rules! case {
-/// #     (@acc $x:expr => [$($arms:tt)*]) => {
-/// #         match $x {$($arms)*}
-/// #     };
-/// #     (@acc $x:expr => [$($arms:tt)*] | $pat:pat if $guard:expr => $result:expr $(,$($rest:tt)*)?) => {
-/// #         case!(@acc $x => [$($arms)* $pat if $guard => $result,] $($($rest)*)?)
-/// #     };
-/// #     (@acc $x:expr => [$($arms:tt)*] | $pat:pat => $result:expr $(,$($rest:tt)*)?) => {
-/// #         case!(@acc $x => [$($arms)* $pat => $result,] $($($rest)*)?)
-/// #     };
-/// #     ($x:expr=>$($rest:tt)+) => {
-/// #         case!(@acc $x =>[] $($rest)+)
-/// #     };
-/// # }
-/// let pair = (3, 5);
-/// let result = case!(pair =>
-///     | (x, y) if x == y => "equal",
-///     | (x, y) if x > y => "first is larger",
-///     | (_, _) => "second is larger",
+/// use hgm::guard;
+///
+/// let describe = |input: &str| guard!(
+///     default "not a number";
+///     where? n = input.parse::<i32>().ok();
+///     | n < 0 => "negative",
+///     | n == 0 => "zero",
+///     | otherwise => "positive",
 /// );
-/// assert_eq!(result, "second is larger");
+/// assert_eq!(describe("-4"), "negative");
+/// assert_eq!(describe("12"), "positive");
+/// assert_eq!(describe("twelve"), "not a number");
 /// ```
 ///
-/// ## 枚举匹配
+/// ## 标注结果类型
+///
+/// 以 `: 类型;` 开头时，整个 guard 的结果被标注为该类型，各分支中的整数、浮点数字面量
+/// 按它推断，无需在每个分支里写 `1u64` 这样的后缀。可以与 `on`、`map` 等前缀组合，
+/// `:` 写在最前面时标注的是最终结果的类型。
 ///
 /// ```
-/// # macro_rules! case {
-/// #     (@acc $x:expr => [$($arms:tt)*]) => {
-/// #         match $x {$($arms)*}
-/// #     };
-/// #     (@acc $x:expr => [$($arms:tt)*] | $pat:pat if $guard:expr => $result:expr $(,$($rest:tt)*)?) => {
-/// #         case!(@acc $x => [$($arms)* $pat if $guard => $result,] $($($rest)*)?)
-/// #     };
-/// #     (@acc $x:expr => [$($arms:tt)*] | $pat:pat => $result:expr $(,$($rest:tt)*)?) => {
-/// #         case!(@acc $x => [$($arms)* $pat => $result,] $($($rest)*)?)
-/// #     };
-/// #     ($x:expr=>$($rest:tt)+) => {
-/// #         case!(@acc $x =>[] $($rest)+)
-/// #     };
-/// # }
-/// enum Color { Red, Green, Blue, Rgb(u8, u8, u8) }
+/// use hgm::guard;
 ///
-/// let color = Color::Rgb(255, 128, 0);
-/// let name = case!(color =>
-///     | Color::Red => "red",
-///     | Color::Green => "green",
-///     | Color::Blue => "blue",
-///     | Color::Rgb(r, _, _) if r > 200 => "reddish",
-///     | Color::Rgb(_, _, _) => "custom",
+/// let size = 3_000_000_000u64;
+/// let shift = guard!(: u32;
+///     | size >= 1 << 30 => 30,
+///     | size >= 1 << 20 => 20,
+///     | otherwise => 0,
 /// );
-/// assert_eq!(name, "reddish");
+/// assert_eq!(size >> shift, 2);
 /// ```
 ///
-/// ## 与标准 match 对比
+/// ## 在 const 上下文中使用
+///
+/// 展开结果只包含 `if` / `else` 与 `panic!`，可以直接用在 `const fn` 中。
+/// 常量求值命中缺省的 panic 时，编译失败并给出 `Non-exhaustive guards` 信息，
+/// 指向触发求值的常量。
+///
+/// 启用 `profile` 或 `tracing` 特性不会改变普通 `guard!` 的展开，因此无论依赖图中
+/// 哪个 crate 打开了这些特性，`const fn` 与常量项中的 `guard!` 都照常编译。
+/// 需要统计或事件的调用写作 `guard!(instrument; ...)`（插入的调用不是 `const`）；
+/// [`fn_guard!`] 以 guard 链为函数体的非 `const` 函数会自动这样做。
+///
+/// ```compile_fail,E0080
+/// use hgm::guard;
 ///
+/// const fn sign(n: i32) -> i32 {
+///     guard!(
+///         | n > 0 => 1,
+///         | n < 0 => -1,
+///     )
+/// }
+///
+/// const POSITIVE: i32 = sign(5);
+/// const ZERO: i32 = sign(0); // error: evaluation panicked: Non-exhaustive guards
+/// # let _ = (POSITIVE, ZERO);
 /// ```
-/// # macro_rules! case {
-/// #     (@acc $x:expr => [$($arms:tt)*]) => {
-/// #         match $x {$($arms)*}
-/// #     };
-/// #     (@acc $x:expr => [$($arms:tt)*] | $pat:pat if $guard:expr => $result:expr $(,$($rest:tt)*)?) => {
-/// #         case!(@acc $x => [$($arms)* $pat if $guard => $result,] $($($rest)*)?)
-/// #     };
-/// #     (@acc $x:expr => [$($arms:tt)*] | $pat:pat => $result:expr $(,$($rest:tt)*)?) => {
-/// #         case!(@acc $x => [$($arms)* $pat => $result,] $($($rest)*)?)
-/// #     };
-/// #     ($x:expr=>$($rest:tt)+) => {
-/// #         case!(@acc $x =>[] $($rest)+)
-/// #     };
-/// # }
-/// let n = 5;
 ///
-/// // 使用 case! 宏 (Haskell 风格)
-/// let a = case!(n =>
-///     | 0 => "zero",
-///     | x if x < 0 => "negative",
-///     | _ => "positive",
+/// ## 数值 guard 的写法
+///
+/// Haskell 风格的 `|n| < 5` 绝对值写法会与 guard 前缀的 `|` 产生歧义，
+/// 因此推荐用 `on` 绑定一次 `abs()` / `signum()` 的结果，再在各分支中使用：
+///
+/// ```
+/// use hgm::guard;
+///
+/// fn describe(delta: i32) -> &'static str {
+///     guard!(
+///         on magnitude = delta.abs();
+///         | magnitude < 5 => "steady",
+///         | delta.signum() > 0 => "rising",
+///         | otherwise => "falling",
+///     )
+/// }
+///
+/// assert_eq!(describe(-3), "steady");
+/// assert_eq!(describe(12), "rising");
+/// assert_eq!(describe(-40), "falling");
+/// ```
+///
+/// # 严格模式检查
+///
+/// 启用 `strict` 特性时，`guard!` 会检查整数区间比较：若某个分支形如
+/// `变量 比较符 整数字面量`，且前面已有同一变量、同一方向的比较完全覆盖了它，
+/// 该分支永远不会命中，编译失败。
+///
+/// 能够识别的情况（`<` / `<=` 与 `>` / `>=` 先统一为闭区间边界再比较）：
+///
+/// - `x < 10` 之后的 `x < 5`、`x <= 9`、`x < 10`
+/// - `x >= 0` 之后的 `x > 0`、`x >= 3`
+/// - 负数与带后缀的字面量，例如 `x < -1`、`x <= 10u8`
+///
+/// 不会检查的情况：不同变量或方向相反的比较（`x < 10` 之后的 `x > 5`）、
+/// 浮点数、常量或表达式作为边界、`&&` / `||` 组合条件、字段与方法调用（`p.x < 5`）。
+/// 这些条件按原样编译，不会报错。
+///
+#[cfg_attr(feature = "strict", doc = "```compile_fail,E0080")]
+#[cfg_attr(not(feature = "strict"), doc = "```ignore")]
+/// use hgm::guard;
+///
+/// let x = 3;
+/// let size = guard!(
+///     | x < 10 => "small",
+///     | x < 5 => "tiny", // 永远不会命中：x < 5 时上一个分支已经成立
+///     | otherwise => "large",
 /// );
+/// ```
 ///
-/// // 等价的标准 Rust match
-/// let b = match n {
-///     0 => "zero",
-///     x if x < 0 => "negative",
-///     _ => "positive",
-/// };
+/// # Panics
 ///
-/// assert_eq!(a, b);
+/// 当所有条件都不满足且没有 `otherwise` 分支时，会触发 panic。
+/// panic 由展开在调用处的 `panic!` 产生，报告的位置就是 `guard!` 所在的代码行：
+///
+/// ```should_panic
+/// use hgm::guard;
+///
+/// let x = 0;
+/// guard!(
+///     | x > 0 => "positive",
+///     | x < 0 => "negative",
+///     // 缺少 otherwise，x == 0 时会 panic
+/// );
 /// ```
 #[macro_export]
-macro_rules! case {
-    (@acc $x:expr => [$($arms:tt)*]) => {
-        match $x {$($arms)*}
+macro_rules! guard {
+    // 所有分支都展开为 `{ ... }` 块，保证 where 绑定不会泄漏到外部作用域
+    (on $name:ident; $($rest:tt)+) => {{
+        let $name = $name;
+        $crate::guard!($($rest)+)
+    }};
+    // on 绑定可以是任意不可反驳的模式，例如结构体或元组解构
+    (on $pat:pat = $val:expr; $($rest:tt)+) => {{
+        let $pat = $val;
+        $crate::guard!($($rest)+)
+    }};
+    // apply：值只求值一次，每个条件都是以它为参数调用的谓词
+    (apply $val:expr; $($rest:tt)+) => {{
+        let applied = $val;
+        $crate::guard!(@apply [applied] [] $($rest)+)
+    }};
+    (@apply [$value:ident] [$($done:tt)*]) => {
+        $crate::guard!($($done)*)
     };
-    (@acc $x:expr => [$($arms:tt)*] | $pat:pat if $guard:expr => $result:expr $(,$($rest:tt)*)?) => {
-        case!(@acc $x => [$($arms)* $pat if $guard => $result,] $($($rest)*)?)
+    (@apply [$value:ident] [$($done:tt)*] where $($bindings:tt)+) => {
+        $crate::guard!($($done)* where $($bindings)+)
     };
-    (@acc $x:expr => [$($arms:tt)*] | $pat:pat => $result:expr $(,$($rest:tt)*)?) => {
-        case!(@acc $x => [$($arms)* $pat => $result,] $($($rest)*)?)
+    (@apply [$value:ident] [$($done:tt)*] # $attr:tt $($rest:tt)+) => {
+        $crate::guard!(@apply [$value] [$($done)* # $attr] $($rest)+)
     };
-    ($x:expr=>$($rest:tt)+) => {
-        case!(@acc $x =>[] $($rest)+)
+    (@apply [$value:ident] [$($done:tt)*] | otherwise => $result:expr $(, $($rest:tt)*)?) => {
+        $crate::guard!(@apply [$value] [$($done)* | otherwise => $result,] $($($rest)*)?)
     };
-}
-
-#[cfg(test)]
-mod tests {
-    use super::*;
-
-    #[test]
-    fn test_guard() {
-        let x = 10;
-        let result = guard!(
-            | x < 0 => "negative",
-            | x == 0 => "zero",
-            | otherwise => "positive",
-        );
-        assert_eq!(result, "positive");
-    }
-
+    (@apply [$value:ident] [$($done:tt)*] | [$hint:ident] $predicate:expr => $result:expr $(, $($rest:tt)*)?) => {
+        $crate::guard!(@apply [$value] [$($done)* | [$hint] { ($predicate)($value) } => $result,] $($($rest)*)?)
+    };
+    (@apply [$value:ident] [$($done:tt)*] | $predicate:expr => $result:expr $(, $($rest:tt)*)?) => {
+        $crate::guard!(@apply [$value] [$($done)* | ($predicate)($value) => $result,] $($($rest)*)?)
+    };
+    // default 与 where? 绑定：按顺序求值，任一为 None 时整个 guard 取默认值，不检查任何分支
+    (default $default:expr; where? $($rest:tt)+) => {
+        $crate::guard!(@default [$default] [] where? $($rest)+)
+    };
+    (@default [$default:expr] [$($bindings:tt)*] where? $binding:ident = $val:expr; $($rest:tt)+) => {
+        $crate::guard!(@default [$default] [$($bindings)* $binding = $val;] $($rest)+)
+    };
+    (@default [$default:expr] [$($binding:ident = $val:expr;)+] $($rest:tt)+) => {
+        'hgm_default: {
+            $(
+                let ::core::option::Option::Some($binding) = ($val) else {
+                    break 'hgm_default $default;
+                };
+            )+
+            $crate::guard!($($rest)+)
+        }
+    };
+    // `instrument;`：启用 profile / tracing 时插入统计与事件调用，不能用于常量求值
+    (instrument; $($rest:tt)+) => {
+        $crate::guard!(@acc [eval instrument] [] $($rest)+)
+    };
+    // `: Type;` 为结果标注类型，各分支中的字面量据此推断
+    (: $ty:ty; $($rest:tt)+) => {{
+        let typed: $ty = $crate::guard!($($rest)+);
+        typed
+    }};
+    // map 作用于最终选中的分支结果，只调用一次
+    (map = $map:expr; $($rest:tt)+) => {
+        ($map)($crate::guard!($($rest)+))
+    };
+    // 逐个分支累积到 [...] 中；字面量 `otherwise` 在这里被改写为 `true`，
+    // 因此无论是否导入常量 `otherwise`、是否带 where 子句都能直接使用。
+    // $mode 为 [eval]（求值；`[eval instrument]` 插桩）、debug（只收集条件文本，见 guard_debug_arms!）
+    // iter（惰性迭代器，见 guard_iter!）或 each（执行所有成立的分支，见 guard_each!），
+    // 每个分支前保存一个 [...] 包裹的标签，用于 debug 与 strict 检查
+    (@acc $mode:tt [$($arms:tt)*] | otherwise => $fallback:expr $(,)?) => {
+        $crate::guard!(@emit $mode [$($arms)*] [] [otherwise] { $fallback })
+    };
+    (@acc $mode:tt [$($arms:tt)*] | otherwise => $fallback:expr, where $($bindings:tt)+) => {
+        $crate::guard!(@emit $mode [$($arms)*] [$($bindings)+] [otherwise] { $fallback })
+    };
+    // 字面量 `true` 与 Haskell 的 `True` 一样是兜底分支，与 `otherwise` 等价；求值模式下
+    // 兜底分支之后的分支永远不会被检查，放入不会执行的 else 中，并通过丢弃一个 #[must_use] 的值在调用处产生警告。
+    // debug、iter 与 each 模式保留这些分支：debug 列出全部条件，iter 与 each 中兜底分支之后的分支仍会执行
+    (@acc [eval $($marker:ident)?] [$($arms:tt)*] | true => $fallback:expr $(,)?) => {
+        $crate::guard!(@emit [eval $($marker)?] [$($arms)*] [] [true] { $fallback })
+    };
+    (@acc [eval $($marker:ident)?] [$($arms:tt)*] | true => $fallback:expr, where $($bindings:tt)+) => {
+        $crate::guard!(@emit [eval $($marker)?] [$($arms)*] [$($bindings)+] [true] { $fallback })
+    };
+    (@acc debug [$($arms:tt)*] | true => $fallback:expr $(, $($rest:tt)*)?) => {
+        $crate::guard!(@emit debug [$($arms)*] [] [true] { $fallback })
+    };
+    (@acc [eval $($marker:ident)?] [$($arms:tt)*] | true => $fallback:expr, | $($rest:tt)+) => {
+        $crate::guard!(@dead [eval $($marker)?] [true] [$($arms)*] [$fallback] [] | $($rest)+)
+    };
+    (@acc [eval $($marker:ident)?] [$($arms:tt)*] | otherwise => $fallback:expr, | $($rest:tt)+) => {
+        $crate::guard!(@dead [eval $($marker)?] [otherwise] [$($arms)*] [$fallback] [] | $($rest)+)
+    };
+    // 8 个一批的累积规则把条件当作不透明的 expr 捕获，无法再识别其中的 `true` / `otherwise`，
+    // 因此先检查接下来的 7 个分支：兜底分支之前的分支照常累积，再由上面的规则处理兜底分支
+    (@acc [eval $($marker:ident)?] [$($arms:tt)*] | $c0:expr => $r0:expr, | true => $fallback:expr, | $($rest:tt)+) => {
+        $crate::guard!(@acc [eval $($marker)?] [$($arms)* [$c0] ($c0) => ($r0)] | true => $fallback, | $($rest)+)
+    };
+    (@acc [eval $($marker:ident)?] [$($arms:tt)*] | $c0:expr => $r0:expr, | $c1:expr => $r1:expr, | true => $fallback:expr, | $($rest:tt)+) => {
+        $crate::guard!(@acc [eval $($marker)?] [$($arms)* [$c0] ($c0) => ($r0) [$c1] ($c1) => ($r1)] | true => $fallback, | $($rest)+)
+    };
+    (@acc [eval $($marker:ident)?] [$($arms:tt)*] | $c0:expr => $r0:expr, | $c1:expr => $r1:expr, | $c2:expr => $r2:expr, | true => $fallback:expr, | $($rest:tt)+) => {
+        $crate::guard!(@acc [eval $($marker)?] [$($arms)* [$c0] ($c0) => ($r0) [$c1] ($c1) => ($r1) [$c2] ($c2) => ($r2)] | true => $fallback, | $($rest)+)
+    };
+    (@acc [eval $($marker:ident)?] [$($arms:tt)*] | $c0:expr => $r0:expr, | $c1:expr => $r1:expr, | $c2:expr => $r2:expr, | $c3:expr => $r3:expr, | true => $fallback:expr, | $($rest:tt)+) => {
+        $crate::guard!(@acc [eval $($marker)?] [$($arms)* [$c0] ($c0) => ($r0) [$c1] ($c1) => ($r1) [$c2] ($c2) => ($r2) [$c3] ($c3) => ($r3)] | true => $fallback, | $($rest)+)
+    };
+    (@acc [eval $($marker:ident)?] [$($arms:tt)*] | $c0:expr => $r0:expr, | $c1:expr => $r1:expr, | $c2:expr => $r2:expr, | $c3:expr => $r3:expr, | $c4:expr => $r4:expr, | true => $fallback:expr, | $($rest:tt)+) => {
+        $crate::guard!(@acc [eval $($marker)?] [$($arms)* [$c0] ($c0) => ($r0) [$c1] ($c1) => ($r1) [$c2] ($c2) => ($r2) [$c3] ($c3) => ($r3) [$c4] ($c4) => ($r4)] | true => $fallback, | $($rest)+)
+    };
+    (@acc [eval $($marker:ident)?] [$($arms:tt)*] | $c0:expr => $r0:expr, | $c1:expr => $r1:expr, | $c2:expr => $r2:expr, | $c3:expr => $r3:expr, | $c4:expr => $r4:expr, | $c5:expr => $r5:expr, | true => $fallback:expr, | $($rest:tt)+) => {
+        $crate::guard!(@acc [eval $($marker)?] [$($arms)* [$c0] ($c0) => ($r0) [$c1] ($c1) => ($r1) [$c2] ($c2) => ($r2) [$c3] ($c3) => ($r3) [$c4] ($c4) => ($r4) [$c5] ($c5) => ($r5)] | true => $fallback, | $($rest)+)
+    };
+    (@acc [eval $($marker:ident)?] [$($arms:tt)*] | $c0:expr => $r0:expr, | $c1:expr => $r1:expr, | $c2:expr => $r2:expr, | $c3:expr => $r3:expr, | $c4:expr => $r4:expr, | $c5:expr => $r5:expr, | $c6:expr => $r6:expr, | true => $fallback:expr, | $($rest:tt)+) => {
+        $crate::guard!(@acc [eval $($marker)?] [$($arms)* [$c0] ($c0) => ($r0) [$c1] ($c1) => ($r1) [$c2] ($c2) => ($r2) [$c3] ($c3) => ($r3) [$c4] ($c4) => ($r4) [$c5] ($c5) => ($r5) [$c6] ($c6) => ($r6)] | true => $fallback, | $($rest)+)
+    };
+    (@acc [eval $($marker:ident)?] [$($arms:tt)*] | $c0:expr => $r0:expr, | otherwise => $fallback:expr, | $($rest:tt)+) => {
+        $crate::guard!(@acc [eval $($marker)?] [$($arms)* [$c0] ($c0) => ($r0)] | otherwise => $fallback, | $($rest)+)
+    };
+    (@acc [eval $($marker:ident)?] [$($arms:tt)*] | $c0:expr => $r0:expr, | $c1:expr => $r1:expr, | otherwise => $fallback:expr, | $($rest:tt)+) => {
+        $crate::guard!(@acc [eval $($marker)?] [$($arms)* [$c0] ($c0) => ($r0) [$c1] ($c1) => ($r1)] | otherwise => $fallback, | $($rest)+)
+    };
+    (@acc [eval $($marker:ident)?] [$($arms:tt)*] | $c0:expr => $r0:expr, | $c1:expr => $r1:expr, | $c2:expr => $r2:expr, | otherwise => $fallback:expr, | $($rest:tt)+) => {
+        $crate::guard!(@acc [eval $($marker)?] [$($arms)* [$c0] ($c0) => ($r0) [$c1] ($c1) => ($r1) [$c2] ($c2) => ($r2)] | otherwise => $fallback, | $($rest)+)
+    };
+    (@acc [eval $($marker:ident)?] [$($arms:tt)*] | $c0:expr => $r0:expr, | $c1:expr => $r1:expr, | $c2:expr => $r2:expr, | $c3:expr => $r3:expr, | otherwise => $fallback:expr, | $($rest:tt)+) => {
+        $crate::guard!(@acc [eval $($marker)?] [$($arms)* [$c0] ($c0) => ($r0) [$c1] ($c1) => ($r1) [$c2] ($c2) => ($r2) [$c3] ($c3) => ($r3)] | otherwise => $fallback, | $($rest)+)
+    };
+    (@acc [eval $($marker:ident)?] [$($arms:tt)*] | $c0:expr => $r0:expr, | $c1:expr => $r1:expr, | $c2:expr => $r2:expr, | $c3:expr => $r3:expr, | $c4:expr => $r4:expr, | otherwise => $fallback:expr, | $($rest:tt)+) => {
+        $crate::guard!(@acc [eval $($marker)?] [$($arms)* [$c0] ($c0) => ($r0) [$c1] ($c1) => ($r1) [$c2] ($c2) => ($r2) [$c3] ($c3) => ($r3) [$c4] ($c4) => ($r4)] | otherwise => $fallback, | $($rest)+)
+    };
+    (@acc [eval $($marker:ident)?] [$($arms:tt)*] | $c0:expr => $r0:expr, | $c1:expr => $r1:expr, | $c2:expr => $r2:expr, | $c3:expr => $r3:expr, | $c4:expr => $r4:expr, | $c5:expr => $r5:expr, | otherwise => $fallback:expr, | $($rest:tt)+) => {
+        $crate::guard!(@acc [eval $($marker)?] [$($arms)* [$c0] ($c0) => ($r0) [$c1] ($c1) => ($r1) [$c2] ($c2) => ($r2) [$c3] ($c3) => ($r3) [$c4] ($c4) => ($r4) [$c5] ($c5) => ($r5)] | otherwise => $fallback, | $($rest)+)
+    };
+    (@acc [eval $($marker:ident)?] [$($arms:tt)*] | $c0:expr => $r0:expr, | $c1:expr => $r1:expr, | $c2:expr => $r2:expr, | $c3:expr => $r3:expr, | $c4:expr => $r4:expr, | $c5:expr => $r5:expr, | $c6:expr => $r6:expr, | otherwise => $fallback:expr, | $($rest:tt)+) => {
+        $crate::guard!(@acc [eval $($marker)?] [$($arms)* [$c0] ($c0) => ($r0) [$c1] ($c1) => ($r1) [$c2] ($c2) => ($r2) [$c3] ($c3) => ($r3) [$c4] ($c4) => ($r4) [$c5] ($c5) => ($r5) [$c6] ($c6) => ($r6)] | otherwise => $fallback, | $($rest)+)
+    };
+    // 收集兜底分支之后的分支，直到 where 子句或结尾；能按 expr 解析的分支整体收集以减少递归深度。
+    // 这些分支放在 `if true { 兜底 } else { ... }` 的 else 中：永远不会执行，但仍然参与名称解析与类型检查
+    (@dead $mode:tt [$label:tt] [$($arms:tt)*] [$fallback:expr] [$($dead:tt)*] where $($bindings:tt)+) => {
+        $crate::guard!(@emit $mode [$($arms)*] [$($bindings)+] [$label] {
+            $crate::guard!(@unreachable [$fallback] $($dead)*)
+        })
+    };
+    (@dead $mode:tt [$label:tt] [$($arms:tt)*] [$fallback:expr] [$($dead:tt)*] | $cond:expr => $result:expr $(, $($rest:tt)*)?) => {
+        $crate::guard!(@dead $mode [$label] [$($arms)*] [$fallback] [$($dead)* | $cond => $result,] $($($rest)*)?)
+    };
+    (@dead $mode:tt [$label:tt] [$($arms:tt)*] [$fallback:expr] [$($dead:tt)*] $next:tt $($rest:tt)*) => {
+        $crate::guard!(@dead $mode [$label] [$($arms)*] [$fallback] [$($dead)* $next] $($rest)*)
+    };
+    (@dead $mode:tt [$label:tt] [$($arms:tt)*] [$fallback:expr] [$($dead:tt)*]) => {
+        $crate::guard!(@emit $mode [$($arms)*] [] [$label] {
+            $crate::guard!(@unreachable [$fallback] $($dead)*)
+        })
+    };
+    (@unreachable [$fallback:expr] $($dead:tt)+) => {{
+        $crate::__private::unreachable_guard_arms();
+        if true { $fallback } else { $crate::guard!($($dead)+) }
+    }};
+    (@acc $mode:tt [$($arms:tt)*] | otherwise => $result:expr, | $($rest:tt)+) => {
+        $crate::guard!(@acc $mode [$($arms)* [otherwise] (true) => ($result)] | $($rest)+)
+    };
+    // 出现带属性（通常是 `#[cfg(...)]`）的分支时，改为逐条语句展开，
+    // 已经累积的分支转换为不带属性的形式
+    (@acc [eval $($marker:ident)?] [$([$($label:tt)+] ($($cond:tt)+) => ($result:expr))*] # $($rest:tt)+) => {
+        $crate::guard!(@cfg [$([] ($($cond)+) => ($result))*] # $($rest)+)
+    };
+    (@acc $mode:tt [$($arms:tt)*] # $($rest:tt)+) => {
+        compile_error!("guard!: attributes on arms are only supported by guard! and fn_guard!")
+    };
+    // `[likely]` / `[unlikely]` 标记只影响分支预测提示，标签仍是原条件
+    (@acc $mode:tt [$($arms:tt)*] | [likely] $cond:expr => $result:expr $(, $($rest:tt)*)?) => {
+        $crate::guard!(@acc $mode [$($arms)* [$cond] ($crate::__private::likely($cond)) => ($result)] $($($rest)*)?)
+    };
+    (@acc $mode:tt [$($arms:tt)*] | [unlikely] $cond:expr => $result:expr $(, $($rest:tt)*)?) => {
+        $crate::guard!(@acc $mode [$($arms)* [$cond] ($crate::__private::unlikely($cond)) => ($result)] $($($rest)*)?)
+    };
+    // 连续的 `| a, | b, | c => r` 共用一个结果，合并为 `(a) || (b) || (c)`，按顺序短路求值
+    (@acc $mode:tt [$($arms:tt)*] | otherwise, | $($rest:tt)+) => {
+        compile_error!("guard!: `otherwise` cannot be grouped with other conditions")
+    };
+    (@acc $mode:tt [$($arms:tt)*] | $first:expr, | $($rest:tt)+) => {
+        $crate::guard!(@or $mode [$($arms)*] [$first] [($first)] $($rest)+)
+    };
+    (@or $mode:tt [$($arms:tt)*] [$($label:tt)+] [$($cond:tt)+] otherwise $($rest:tt)*) => {
+        compile_error!("guard!: `otherwise` cannot be grouped with other conditions")
+    };
+    (@or $mode:tt [$($arms:tt)*] [$($label:tt)+] [$($cond:tt)+] $next:expr, | $($rest:tt)+) => {
+        $crate::guard!(@or $mode [$($arms)*] [$($label)+ || $next] [$($cond)+ || ($next)] $($rest)+)
+    };
+    (@or $mode:tt [$($arms:tt)*] [$($label:tt)+] [$($cond:tt)+] $last:expr => $result:expr $(, $($rest:tt)*)?) => {
+        $crate::guard!(@acc $mode [$($arms)* [$($label)+ || $last] ($($cond)+ || ($last)) => ($result)] $($($rest)*)?)
+    };
+    // 以 `let` 开头的 let-chain 不是 expr 片段，逐个 token 收集到 `=>` 为止
+    (@acc $mode:tt [$($arms:tt)*] | let $($rest:tt)+) => {
+        $crate::guard!(@let $mode [$($arms)*] [let] $($rest)+)
+    };
+    (@let $mode:tt [$($arms:tt)*] [$($cond:tt)+] => $result:expr $(, $($rest:tt)*)?) => {
+        $crate::guard!(@acc $mode [$($arms)* [$($cond)+] ($($cond)+) => ($result)] $($($rest)*)?)
+    };
+    (@let $mode:tt [$($arms:tt)*] [$($cond:tt)+] $next:tt $($rest:tt)+) => {
+        $crate::guard!(@let $mode [$($arms)*] [$($cond)+ $next] $($rest)+)
+    };
+    // 一次累积 8 个普通分支，使递归深度约为分支数的 1/8，长链不会触及递归上限。
+    // 其中的 `otherwise` 会被当作普通条件捕获，由展开时导入的常量 `otherwise` 解析为 `true`
+    (
+        @acc $mode:tt [$($arms:tt)*]
+        | $c0:expr => $r0:expr, | $c1:expr => $r1:expr, | $c2:expr => $r2:expr, | $c3:expr => $r3:expr, | $c4:expr => $r4:expr, | $c5:expr => $r5:expr, | $c6:expr => $r6:expr, | $c7:expr => $r7:expr,
+        | $($rest:tt)+
+    ) => {
+        $crate::guard!(
+            @acc $mode [$($arms)* [$c0] ($c0) => ($r0) [$c1] ($c1) => ($r1) [$c2] ($c2) => ($r2) [$c3] ($c3) => ($r3) [$c4] ($c4) => ($r4) [$c5] ($c5) => ($r5) [$c6] ($c6) => ($r6) [$c7] ($c7) => ($r7)]
+            | $($rest)+
+        )
+    };
+    (@acc $mode:tt [$($arms:tt)*] | $cond:expr => $result:expr $(, $($rest:tt)*)?) => {
+        $crate::guard!(@acc $mode [$($arms)* [$cond] ($cond) => ($result)] $($($rest)*)?)
+    };
+    (@acc $mode:tt [$($arms:tt)*] where $($bindings:tt)+) => {
+        $crate::guard!(@emit $mode [$($arms)*] [$($bindings)+] [] { panic!("Non-exhaustive guards") })
+    };
+    (@acc $mode:tt [$($arms:tt)*]) => {
+        $crate::guard!(@emit $mode [$($arms)*] [] [] { panic!("Non-exhaustive guards") })
+    };
+    // @cfg：每个分支保存 [属性] (条件) => (结果)，不带属性的最后一个 otherwise 作为兜底
+    (@cfg [$($arms:tt)*] | otherwise => $fallback:expr $(,)?) => {
+        $crate::guard!(@emit_cfg [$($arms)*] [] { $fallback })
+    };
+    (@cfg [$($arms:tt)*] | otherwise => $fallback:expr, where $($bindings:tt)+) => {
+        $crate::guard!(@emit_cfg [$($arms)*] [$($bindings)+] { $fallback })
+    };
+    (@cfg [$($arms:tt)*] $(#[$meta:meta])* | otherwise => $result:expr $(, $($rest:tt)*)?) => {
+        $crate::guard!(@cfg [$($arms)* [$(#[$meta])*] (true) => ($result)] $($($rest)*)?)
+    };
+    (@cfg [$($arms:tt)*] $(#[$meta:meta])* | let $($rest:tt)+) => {
+        $crate::guard!(@cfg_let [$($arms)*] [$(#[$meta])*] [let] $($rest)+)
+    };
+    (@cfg_let [$($arms:tt)*] [$($attrs:tt)*] [$($cond:tt)+] => $result:expr $(, $($rest:tt)*)?) => {
+        $crate::guard!(@cfg [$($arms)* [$($attrs)*] ($($cond)+) => ($result)] $($($rest)*)?)
+    };
+    (@cfg_let [$($arms:tt)*] [$($attrs:tt)*] [$($cond:tt)+] $next:tt $($rest:tt)+) => {
+        $crate::guard!(@cfg_let [$($arms)*] [$($attrs)*] [$($cond)+ $next] $($rest)+)
+    };
+    (@cfg [$($arms:tt)*] $(#[$meta:meta])* | $cond:expr => $result:expr $(, $($rest:tt)*)?) => {
+        $crate::guard!(@cfg [$($arms)* [$(#[$meta])*] ($cond) => ($result)] $($($rest)*)?)
+    };
+    (@cfg [$($arms:tt)*] where $($bindings:tt)+) => {
+        $crate::guard!(@emit_cfg [$($arms)*] [$($bindings)+] { panic!("Non-exhaustive guards") })
+    };
+    (@cfg [$($arms:tt)*]) => {
+        $crate::guard!(@emit_cfg [$($arms)*] [] { panic!("Non-exhaustive guards") })
+    };
+    // 属性无法加在 `else if` 上，因此每个分支展开为带属性的独立语句，
+    // 命中时跳出带标签的块；被 cfg 移除的分支不会出现在生成的代码中
+    (@emit_cfg [$([$($attrs:tt)*] ($($cond:tt)+) => ($result:expr))*] [$($bindings:tt)*] $fallback:block) => {{
+        #[allow(unused_imports)]
+        use $crate::otherwise;
+        $crate::__where_lets!($($bindings)*);
+        #[allow(unused_labels)]
+        'hgm_guard: {
+            $(
+                $($attrs)*
+                if $($cond)+ {
+                    break 'hgm_guard $result;
+                }
+            )*
+            $fallback
+        }
+    }};
+    // [eval instrument] 来自 `instrument;` 前缀：启用 profile / tracing 时插入统计与事件调用
+    (
+        @emit [eval instrument] [$([$($label:tt)+] ($($cond:tt)+) => ($result:expr))*]
+        [$($bindings:tt)*]
+        [$($fallback_label:tt)?]
+        $fallback:block
+    ) => {
+        $crate::__guard_instrumented!(
+            [$([$($label)+] ($($cond)+) => ($result))*]
+            [$($bindings)*]
+            [$($fallback_label)?]
+            $fallback
+        )
+    };
+    (
+        @emit [eval] [$([$($label:tt)+] ($($cond:tt)+) => ($result:expr))*]
+        [$($bindings:tt)*]
+        [$($fallback_label:tt)?]
+        $fallback:block
+    ) => {
+        $crate::__guard_eval!(
+            [$([$($label)+] ($($cond)+) => ($result))*]
+            [$($bindings)*]
+            [$($fallback_label)?]
+            $fallback
+        )
+    };
+    (
+        @emit each [$([$($label:tt)+] ($($cond:tt)+) => ($result:expr))*]
+        [$($bindings:tt)*]
+        [$($fallback_label:tt)?]
+        $fallback:block
+    ) => {{
+        #[allow(unused_imports)]
+        use $crate::otherwise;
+        $crate::__where_lets!($($bindings)*);
+        $(if $($cond)+ { $result; })*
+        $crate::guard!(@each_fallback [$($fallback_label)?] $fallback)
+    }};
+    // guard_each! 中 otherwise 分支总会执行；没有 otherwise 时什么也不做，不会 panic
+    (@each_fallback [otherwise] $fallback:block) => {{
+        $fallback;
+    }};
+    (@each_fallback [] $fallback:block) => {
+        ()
+    };
+    (
+        @emit iter [$([$($label:tt)+] ($($cond:tt)+) => ($result:expr))*]
+        []
+        [otherwise]
+        $fallback:block
+    ) => {{
+        #[allow(unused_imports)]
+        use $crate::otherwise;
+        ::core::iter::empty()
+            $(.chain(::core::iter::once_with(|| if $($cond)+ { ::core::option::Option::Some($result) } else { ::core::option::Option::None }).flatten()))*
+            .chain(::core::iter::once_with(|| $fallback))
+    }};
+    // 没有 otherwise 时迭代器在最后一个分支之后结束，不会 panic
+    (
+        @emit iter [$([$($label:tt)+] ($($cond:tt)+) => ($result:expr))*]
+        []
+        []
+        $fallback:block
+    ) => {{
+        #[allow(unused_imports)]
+        use $crate::otherwise;
+        ::core::iter::empty()
+            $(.chain(::core::iter::once_with(|| if $($cond)+ { ::core::option::Option::Some($result) } else { ::core::option::Option::None }).flatten()))*
+    }};
+    (@emit iter [$($arms:tt)*] [$($bindings:tt)+] $($rest:tt)*) => {
+        compile_error!("guard_iter!: where bindings are not supported; bind the values before the macro")
+    };
+    (
+        @emit debug [$([$($label:tt)+] ($($cond:tt)+) => ($result:expr))*]
+        [$($bindings:tt)*]
+        [$($fallback_label:tt)?]
+        $fallback:block
+    ) => {{
+        const ARMS: &[&str] = &[$(stringify!($($label)+),)* $(stringify!($fallback_label),)?];
+        ARMS
+    }};
+    // reason：命中的分支连同其条件文本一起返回，见 guard_with_reason!
+    (
+        @emit reason [$([$($label:tt)+] ($($cond:tt)+) => ($result:expr))*]
+        [$($bindings:tt)*]
+        [$($fallback_label:tt)?]
+        $fallback:block
+    ) => {{
+        #[allow(unused_imports)]
+        use $crate::otherwise;
+        $crate::__guard_check!($([$($label)+]),*);
+        $crate::__where_lets!($($bindings)*);
+        $(if $($cond)+ { (stringify!($($label)+), $result) } else)* {
+            $crate::guard!(@reason_fallback [$($fallback_label)?] $fallback)
+        }
+    }};
+    (@reason_fallback [$fallback_label:tt] $fallback:block) => {
+        (stringify!($fallback_label), $fallback)
+    };
+    // 没有兜底分支时 fallback 是发散的 panic!，无需组成元组
+    (@reason_fallback [] $fallback:block) => {
+        $fallback
+    };
+    (| $($arms:tt)+) => {
+        $crate::guard!(@acc [eval] [] | $($arms)+)
+    };
+    (# $($arms:tt)+) => {
+        $crate::guard!(@acc [eval] [] # $($arms)+)
+    };
+}
+
+/// 把 where 子句展开为 `let` 语句，支持 `a = 1, b = 2` 与 `{ a = 1; b = 2 }` 两种写法，
+/// 以及解构模式 `(a, b) = pair` 和展开为 `let ... else` 的 `Some(v) = opt else { return None }`
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __where_lets {
+    () => {};
+    ({ $($binding:ident = $val:expr);* $(;)? }) => {
+        $(let $binding = $val;)*
+    };
+    ($($binding:ident = $val:expr),+ $(,)?) => {
+        $(let $binding = $val;)+
+    };
+    // 含 let-else 的绑定：普通绑定逐个展开，可失败的模式收集初始化表达式直到 `else { ... }`
+    ($binding:ident = $val:expr $(, $($rest:tt)*)?) => {
+        let $binding = $val;
+        $crate::__where_lets!($($($rest)*)?);
+    };
+    ($pat:pat = $($rest:tt)+) => {
+        $crate::__where_lets!(@else [$pat] [] $($rest)+);
+    };
+    (@else [$pat:pat] [$($init:tt)+] else $diverge:block $(, $($rest:tt)*)?) => {
+        let $pat = ($($init)+) else $diverge;
+        $crate::__where_lets!($($($rest)*)?);
+    };
+    // 没有 `else` 的模式按普通 `let` 解构，例如 `(q, r) = (n / d, n % d)`；
+    // 若模式可失败，由编译器报告需要 `let ... else`
+    (@else [$pat:pat] [$($init:tt)+] , $($rest:tt)*) => {
+        let $pat = ($($init)+);
+        $crate::__where_lets!($($rest)*);
+    };
+    (@else [$pat:pat] [$($init:tt)*] $next:tt $($rest:tt)*) => {
+        $crate::__where_lets!(@else [$pat] [$($init)* $next] $($rest)*);
+    };
+    (@else [$pat:pat] [$($init:tt)+]) => {
+        let $pat = ($($init)+);
+    };
+}
+
+/// 收集 guard 各分支条件的文本
+///
+/// 与 [`guard!`] 使用同一套分支解析，但不求值任何条件或结果，只把每个分支的
+/// 条件经 `stringify!` 转换后组成 `&'static [&'static str]` 返回，
+/// 便于在调试工具、文档生成或管理界面中展示规则集合。
+///
+/// `otherwise` 分支记为 `"otherwise"`；`where` 子句会被接受但不会求值。
+///
+/// # Examples
+///
+/// ```
+/// use hgm::guard_debug_arms;
+///
+/// let arms = guard_debug_arms!(
+///     | temp < 0 => "freezing",
+///     | temp < 25 => "mild",
+///     | otherwise => "hot",
+///     where temp = read_sensor()
+/// );
+/// assert_eq!(arms, ["temp < 0", "temp < 25", "otherwise"]);
+/// ```
+#[macro_export]
+macro_rules! guard_debug_arms {
+    (| $($arms:tt)+) => {
+        $crate::guard!(@acc debug [] | $($arms)+)
+    };
+}
+
+/// 返回命中分支的条件文本与结果
+///
+/// 分支写法与 [`guard!`] 相同，但结果是 `(&'static str, T)`：第一个元素是命中分支的
+/// 条件经 `stringify!` 转换后的源码文本，第二个元素是该分支的结果。
+/// 条件文本直接取自源码，不需要另外维护标签，适合在日志中记录规则链为何走向某个分支。
+///
+/// - 兜底分支的文本为 `"otherwise"`；`| a, | b => r` 形式的分组分支为 `"a || b"`
+/// - 支持 `where` 子句，绑定在所有条件之前求值
+/// - 文本与 [`guard_debug_arms!`] 返回的条目一致，可以用来对照完整的规则列表
+///
+/// # Examples
+///
+/// ```
+/// use hgm::guard_with_reason;
+///
+/// let classify = |age: u32, member: bool| guard_with_reason!(
+///     | age < 12 => 0,
+///     | member && age >= 65 => 5,
+///     | member => 8,
+///     | otherwise => 10,
+/// );
+///
+/// assert_eq!(classify(8, false), ("age < 12", 0));
+/// assert_eq!(classify(70, true), ("member && age >= 65", 5));
+/// assert_eq!(classify(30, false), ("otherwise", 10));
+///
+/// let (reason, price) = classify(30, true);
+/// assert_eq!(format!("price {price} because `{reason}`"), "price 8 because `member`");
+/// ```
+#[macro_export]
+macro_rules! guard_with_reason {
+    (| $($arms:tt)+) => {
+        $crate::guard!(@acc reason [] | $($arms)+)
+    };
+}
+
+/// 同时返回结果与 where 绑定值的 guard 表达式（`debug` 特性）
+///
+/// 启用 `debug` 特性时，`guard_debug!` 的值是 `(结果, (绑定1, 绑定2, ...))`，
+/// 绑定按 where 子句中的书写顺序排列（只有一个绑定时是 `(绑定,)`，没有 where 子句时是 `()`），
+/// 便于在分段计算给出意外结果时查看中间值。未启用时与 [`guard!`] 完全相同，只返回结果。
+///
+/// 注意事项：
+///
+/// - 返回类型随特性改变，只应在调试期间临时使用，或者只在最终的二进制 crate 中启用 `debug`
+/// - 绑定在结果求值之后被移入元组，因此结果不能借用绑定的值
+/// - 只支持 `name = value` 形式的 where 绑定（逗号或花括号写法）
+///
+#[cfg_attr(feature = "debug", doc = "```")]
+#[cfg_attr(not(feature = "debug"), doc = "```ignore")]
+/// use hgm::guard_debug;
+///
+/// let (weight, height) = (95.0_f64, 1.8_f64);
+/// let (category, (bmi,)) = guard_debug!(
+///     | bmi < 18.5 => "underweight",
+///     | bmi < 25.0 => "normal",
+///     | bmi < 30.0 => "overweight",
+///     | otherwise => "obese",
+///     where bmi = weight / (height * height)
+/// );
+/// assert_eq!(category, "overweight");
+/// assert!((bmi - 29.32).abs() < 0.01);
+/// ```
+#[macro_export]
+macro_rules! guard_debug {
+    ($($tokens:tt)+) => {
+        $crate::__guard_debug!(@split [] $($tokens)+)
+    };
+}
+
+#[doc(hidden)]
+#[cfg(feature = "debug")]
+#[macro_export]
+macro_rules! __guard_debug {
+    (@split [$($arms:tt)+]) => {
+        ($crate::guard!($($arms)+), ())
+    };
+    (@split [$($arms:tt)+] where { $($binding:ident = $val:expr);+ $(;)? }) => {
+        $crate::__guard_debug!(@emit [$($arms)+] [$($binding = $val),+])
+    };
+    (@split [$($arms:tt)+] where $($binding:ident = $val:expr),+ $(,)?) => {
+        $crate::__guard_debug!(@emit [$($arms)+] [$($binding = $val),+])
+    };
+    (@split [$($arms:tt)+] where $($bindings:tt)+) => {
+        compile_error!("guard_debug!: only `name = value` where bindings can be captured")
+    };
+    (@split [$($arms:tt)*] $next:tt $($rest:tt)*) => {
+        $crate::__guard_debug!(@split [$($arms)* $next] $($rest)*)
+    };
+    (@emit [$($arms:tt)+] [$($binding:ident = $val:expr),+]) => {{
+        $(let $binding = $val;)+
+        let result = $crate::guard!($($arms)+);
+        (result, ($($binding,)+))
+    }};
+}
+
+#[doc(hidden)]
+#[cfg(not(feature = "debug"))]
+#[macro_export]
+macro_rules! __guard_debug {
+    (@split [] $($tokens:tt)+) => {
+        $crate::guard!($($tokens)+)
+    };
+}
+
+#[doc(hidden)]
+#[cfg(not(any(feature = "profile", feature = "tracing")))]
+#[macro_export]
+macro_rules! __guard_instrumented {
+    ($($tokens:tt)+) => {
+        $crate::__guard_eval!($($tokens)+)
+    };
+}
+
+// 普通的展开：只包含 if / else，可以用于常量求值
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __guard_eval {
+    (
+        [$([$($label:tt)+] ($($cond:tt)+) => ($result:expr))*]
+        [$($bindings:tt)*]
+        [$($fallback_label:tt)?]
+        $fallback:block
+    ) => {{
+        #[allow(unused_imports)]
+        use $crate::otherwise;
+        $crate::__guard_check!($([$($label)+]),*);
+        $crate::__where_lets!($($bindings)*);
+        $(if $($cond)+ { $result } else)* $fallback
+    }};
+}
+
+// profile / tracing：每个条件求值前记一次 evaluated，命中时记一次 matched，
+// 调用位置与条件文本共同作为统计的键；arm 记录已求值的分支数，用于 tracing 事件中的分支序号
+#[doc(hidden)]
+#[cfg(any(feature = "profile", feature = "tracing"))]
+#[macro_export]
+macro_rules! __guard_instrumented {
+    (
+        [$([$($label:tt)+] ($($cond:tt)+) => ($result:expr))*]
+        [$($bindings:tt)*]
+        [$($fallback_label:tt)?]
+        $fallback:block
+    ) => {{
+        #[allow(unused_imports)]
+        use $crate::otherwise;
+        const SITE: &str = concat!(file!(), ":", line!(), ":", column!());
+        let mut arm = 0usize;
+        $crate::__guard_check!($([$($label)+]),*);
+        $crate::__where_lets!($($bindings)*);
+        $(
+            if $crate::__private::arm_evaluated(SITE, &mut arm, stringify!($($label)+)) && $($cond)+ {
+                $crate::__private::arm_matched(SITE, arm, stringify!($($label)+));
+                $result
+            } else
+        )* {
+            $(
+                $crate::__private::arm_evaluated(SITE, &mut arm, stringify!($fallback_label));
+                $crate::__private::arm_matched(SITE, arm, stringify!($fallback_label));
+            )?
+            $fallback
+        }
+    }};
+}
+
+#[doc(hidden)]
+#[cfg(not(feature = "strict"))]
+#[macro_export]
+macro_rules! __guard_check {
+    ($([$($label:tt)+]),*) => {};
+}
+
+#[doc(hidden)]
+#[cfg(feature = "strict")]
+#[macro_export]
+macro_rules! __guard_check {
+    // 用常量项而不是 `const { }` 块：常量项在 `cargo check` 中同样会被求值
+    ($([$($label:tt)+]),*) => {
+        const _: () = $crate::__private::assert_no_dead_ranges(
+            &[$(stringify!($($label)+)),*],
+            &[$(concat!(
+                "guard!: arm `", stringify!($($label)+),
+                "` is unreachable: an earlier arm already covers every value it accepts"
+            )),*],
+        );
+    };
+}
+
+#[doc(hidden)]
+#[cfg(feature = "std")]
+#[macro_export]
+macro_rules! __fn_guard_memoize {
+    ([$($arg:ident : $ty:ty),*] $ret:ty, $body:expr) => {{
+        ::std::thread_local! {
+            static CACHE: ::core::cell::RefCell<::std::collections::HashMap<($($ty,)*), $ret>> =
+                ::core::cell::RefCell::new(::std::collections::HashMap::new());
+        }
+        let key = ($(::core::clone::Clone::clone(&$arg),)*);
+        // 查询与写入缓存时都不持有借用，递归调用可以安全地再次访问缓存
+        if let ::core::option::Option::Some(hit) = CACHE.with(|cache| cache.borrow().get(&key).cloned()) {
+            return hit;
+        }
+        let value: $ret = $body;
+        CACHE.with(|cache| cache.borrow_mut().insert(key, ::core::clone::Clone::clone(&value)));
+        value
+    }};
+}
+
+#[doc(hidden)]
+#[cfg(not(feature = "std"))]
+#[macro_export]
+macro_rules! __fn_guard_memoize {
+    ($($tt:tt)*) => {
+        compile_error!("fn_guard!: #[memoize] requires the `std` feature")
+    };
+}
+
+/// 执行所有条件成立分支的语句形式 Guard 宏
+///
+/// [`guard!`] 的非互斥版本：按书写顺序检查每个条件，**每个**成立的分支都会执行，
+/// 整个表达式的值为 `()`。适合根据互相重叠的条件输出多条日志、累计多个标记等场景。
+///
+/// - `otherwise` 分支总是执行，并且最后执行
+/// - 没有分支成立时什么也不做，不会 panic
+/// - 支持 `where` 子句与 let-chain 条件
+///
+/// # Examples
+///
+/// ```
+/// use hgm::guard_each;
+///
+/// let mut warnings = Vec::new();
+/// let (cpu, mem) = (93, 88);
+/// guard_each!(
+///     | cpu > 90 => warnings.push("cpu high"),
+///     | mem > 80 => warnings.push("memory high"),
+///     | cpu > 90 && mem > 80 => warnings.push("consider scaling out"),
+///     | cpu < 10 => warnings.push("cpu idle"),
+/// );
+/// assert_eq!(warnings, ["cpu high", "memory high", "consider scaling out"]);
+/// ```
+#[macro_export]
+macro_rules! guard_each {
+    (| $($arms:tt)+) => {
+        $crate::guard!(@acc each [] | $($arms)+)
+    };
+}
+
+/// 惰性产出所有条件成立分支结果的 Guard 宏
+///
+/// 与 [`guard!`] 只执行第一个条件成立的分支不同，`guard_iter!` 返回一个迭代器，
+/// 按书写顺序依次产出**每个**条件成立的分支结果。条件与结果都在迭代器被推进时
+/// 才求值，因此只取前几个结果时，后面的分支完全不会被执行。
+///
+/// - `otherwise` 分支总是成立，其结果在最后产出
+/// - 没有分支成立时得到空迭代器，不会 panic
+/// - 不需要分配内存（不使用 `Vec`），可在 `no_std` 中使用
+/// - 迭代器借用周围的变量；不支持 `where` 子句
+///
+/// # Examples
+///
+/// ```
+/// use std::cell::Cell;
+/// use hgm::guard_iter;
+///
+/// let n = 12;
+/// let checked = Cell::new(0);
+/// let check = |ok: bool| {
+///     checked.set(checked.get() + 1);
+///     ok
+/// };
+///
+/// let divisors: Vec<u32> = guard_iter!(
+///     | check(n % 2 == 0) => 2,
+///     | check(n % 3 == 0) => 3,
+///     | check(n % 5 == 0) => 5,
+///     | check(n % 6 == 0) => 6,
+/// )
+/// .take(2)
+/// .collect();
+///
+/// assert_eq!(divisors, [2, 3]);
+/// assert_eq!(checked.get(), 2); // 后两个条件没有被求值
+/// ```
+#[macro_export]
+macro_rules! guard_iter {
+    (| $($arms:tt)+) => {
+        $crate::guard!(@acc iter [] | $($arms)+)
+    };
+}
+
+/// 分支结果统一转换为目标类型的 Guard 表达式宏
+///
+/// 各分支结果的类型不同但都能转换为同一类型时（例如 `&str` 与 `String`），
+/// 普通的 [`guard!`] 无法统一类型。`guard_into!` 以开头的 `-> Type;` 声明目标类型，
+/// 并对每个分支结果调用 [`Into::into`]，省去重复的 `.to_string()` / `.into()`。
+///
+/// 结果为 `panic!`、`unreachable!`、`todo!`、`unimplemented!` 调用或 `return`、`break`、
+/// `continue` 的分支类型为 `!`，不经过 `Into` 直接保留。其它发散表达式
+/// （例如 `std::process::exit(1)`）无法从语法上识别，会因为 `!` 没有实现 `Into`
+/// 而编译失败，此时请改用 [`guard!`] 并手动转换其它分支。
+///
+/// # 语法
+///
+/// ```text
+/// guard_into!(-> Type;
+///     | condition => result,
+///     | otherwise => default,
+///     where name = value
+/// )
+/// ```
+///
+/// # Examples
+///
+/// ```
+/// use hgm::guard_into;
+///
+/// let n = 42;
+/// let text = guard_into!(-> String;
+///     | n == 0 => "zero",
+///     | n < 0 => format!("minus {}", -n),
+///     | otherwise => format!("{n}"),
+/// );
+/// assert_eq!(text, "42");
+///
+/// let wide: i64 = guard_into!(-> i64; | n > 10 => 1u8, | otherwise => -1i32);
+/// assert_eq!(wide, 1);
+/// ```
+#[macro_export]
+macro_rules! guard_into {
+    (-> $target:ty; | $($arms:tt)+) => {
+        $crate::__guard_wrap!(
+            [::core::convert::Into::<$target>::into]
+            [::core::convert::Into::<$target>::into]
+            []
+            | $($arms)+
+        )
+    };
+}
+
+/// 逐个分支用 `$wrap` 包装结果后交给 guard!；`otherwise` 分支使用 `$fallback_wrap`（可以为空）。
+/// 条件原样保留，因此 `otherwise` 与 let-chain 照常识别；类型为 `!` 的分支不能作为
+/// 函数参数，保持原样，由 if/else 链直接强转。
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __guard_wrap {
+    ([$($wrap:tt)+] [$($fallback_wrap:tt)*] [$($done:tt)*]) => {
+        $crate::guard!($($done)*)
+    };
+    ([$($wrap:tt)+] [$($fallback_wrap:tt)*] [$($done:tt)*] where $($bindings:tt)+) => {
+        $crate::guard!($($done)* where $($bindings)+)
+    };
+    ([$($wrap:tt)+] [$($fallback_wrap:tt)*] [$($done:tt)*] # $attr:tt $($rest:tt)+) => {
+        $crate::__guard_wrap!([$($wrap)+] [$($fallback_wrap)*] [$($done)* # $attr] $($rest)+)
+    };
+    ([$($wrap:tt)+] [$($fallback_wrap:tt)*] [$($done:tt)*] | otherwise => $($rest:tt)+) => {
+        $crate::__guard_wrap!(@result [$($fallback_wrap)*] [$($wrap)+] [$($fallback_wrap)*] [$($done)*] [otherwise] $($rest)+)
+    };
+    ([$($wrap:tt)+] [$($fallback_wrap:tt)*] [$($done:tt)*] | let $($rest:tt)+) => {
+        $crate::__guard_wrap!(@let [$($wrap)+] [$($fallback_wrap)*] [$($done)*] [let] $($rest)+)
+    };
+    ([$($wrap:tt)+] [$($fallback_wrap:tt)*] [$($done:tt)*] | [$hint:ident] $cond:expr => $($rest:tt)+) => {
+        $crate::__guard_wrap!(@result [$($wrap)+] [$($wrap)+] [$($fallback_wrap)*] [$($done)*] [[$hint] $cond] $($rest)+)
+    };
+    ([$($wrap:tt)+] [$($fallback_wrap:tt)*] [$($done:tt)*] | $cond:expr => $($rest:tt)+) => {
+        $crate::__guard_wrap!(@result [$($wrap)+] [$($wrap)+] [$($fallback_wrap)*] [$($done)*] [$cond] $($rest)+)
+    };
+    (@let [$($wrap:tt)+] [$($fallback_wrap:tt)*] [$($done:tt)*] [$($cond:tt)+] => $($rest:tt)+) => {
+        $crate::__guard_wrap!(@result [$($wrap)+] [$($wrap)+] [$($fallback_wrap)*] [$($done)*] [$($cond)+] $($rest)+)
+    };
+    (@let [$($wrap:tt)+] [$($fallback_wrap:tt)*] [$($done:tt)*] [$($cond:tt)+] $next:tt $($rest:tt)+) => {
+        $crate::__guard_wrap!(@let [$($wrap)+] [$($fallback_wrap)*] [$($done)*] [$($cond)+ $next] $($rest)+)
+    };
+    // @result [本分支的包装] [wrap] [fallback_wrap] [done] [条件] 结果...
+    (
+        @result [$($this:tt)*] [$($wrap:tt)+] [$($fallback_wrap:tt)*] [$($done:tt)*] [$($cond:tt)+]
+        $mac:ident ! $args:tt $(, $($rest:tt)*)?
+    ) => {
+        $crate::__guard_wrap!(
+            @diverging $mac [$($this)*] [$($wrap)+] [$($fallback_wrap)*] [$($done)*] [$($cond)+]
+            $mac ! $args, $($($rest)*)?
+        )
+    };
+    (
+        @result [$($this:tt)*] [$($wrap:tt)+] [$($fallback_wrap:tt)*] [$($done:tt)*] [$($cond:tt)+]
+        return $($value:expr)? $(, $($rest:tt)*)?
+    ) => {
+        $crate::__guard_wrap!([$($wrap)+] [$($fallback_wrap)*] [$($done)* | $($cond)+ => return $($value)?,] $($($rest)*)?)
+    };
+    (
+        @result [$($this:tt)*] [$($wrap:tt)+] [$($fallback_wrap:tt)*] [$($done:tt)*] [$($cond:tt)+]
+        break $(, $($rest:tt)*)?
+    ) => {
+        $crate::__guard_wrap!([$($wrap)+] [$($fallback_wrap)*] [$($done)* | $($cond)+ => break,] $($($rest)*)?)
+    };
+    (
+        @result [$($this:tt)*] [$($wrap:tt)+] [$($fallback_wrap:tt)*] [$($done:tt)*] [$($cond:tt)+]
+        continue $(, $($rest:tt)*)?
+    ) => {
+        $crate::__guard_wrap!([$($wrap)+] [$($fallback_wrap)*] [$($done)* | $($cond)+ => continue,] $($($rest)*)?)
+    };
+    (
+        @result [$($this:tt)*] [$($wrap:tt)+] [$($fallback_wrap:tt)*] [$($done:tt)*] [$($cond:tt)+]
+        $result:expr $(, $($rest:tt)*)?
+    ) => {
+        $crate::__guard_wrap!([$($wrap)+] [$($fallback_wrap)*] [$($done)* | $($cond)+ => $($this)*($result),] $($($rest)*)?)
+    };
+    // 只有标准库中发散的宏保持原样，其它宏调用（如 `format!`）照常包装
+    (@diverging panic [$($this:tt)*] $($rest:tt)+) => {
+        $crate::__guard_wrap!(@keep $($rest)+)
+    };
+    (@diverging unreachable [$($this:tt)*] $($rest:tt)+) => {
+        $crate::__guard_wrap!(@keep $($rest)+)
+    };
+    (@diverging todo [$($this:tt)*] $($rest:tt)+) => {
+        $crate::__guard_wrap!(@keep $($rest)+)
+    };
+    (@diverging unimplemented [$($this:tt)*] $($rest:tt)+) => {
+        $crate::__guard_wrap!(@keep $($rest)+)
+    };
+    (
+        @diverging $mac:ident [$($this:tt)*] [$($wrap:tt)+] [$($fallback_wrap:tt)*] [$($done:tt)*] [$($cond:tt)+]
+        $result:expr, $($rest:tt)*
+    ) => {
+        $crate::__guard_wrap!([$($wrap)+] [$($fallback_wrap)*] [$($done)* | $($cond)+ => $($this)*($result),] $($rest)*)
+    };
+    (@keep [$($wrap:tt)+] [$($fallback_wrap:tt)*] [$($done:tt)*] [$($cond:tt)+] $result:expr, $($rest:tt)*) => {
+        $crate::__guard_wrap!([$($wrap)+] [$($fallback_wrap)*] [$($done)* | $($cond)+ => $result,] $($rest)*)
+    };
+}
+
+/// 带副作用分支的 Guard 表达式宏
+///
+/// 展开方式与 [`guard!`] 完全相同，用名字明确以下求值保证，方便在分支中
+/// 写日志、计数等副作用：
+///
+/// - 条件按书写顺序求值，遇到第一个为真的条件即停止，之后的条件不会求值；
+/// - 只有命中的那个分支会被执行，并且恰好执行一次；
+/// - 未命中的分支中的副作用永远不会发生。
+///
+/// # 语法
+///
+/// ```text
+/// guard_do!(
+///     | condition1 => { effect1(); value1 },
+///     | otherwise => { effect2(); value2 },
+/// )
+/// ```
+///
+/// # Examples
+///
+/// ```
+/// use std::cell::Cell;
+/// use hgm::guard_do;
+///
+/// let hits = Cell::new(0);
+/// let misses = Cell::new(0);
+/// let cached = true;
+///
+/// let value = guard_do!(
+///     | cached => { hits.set(hits.get() + 1); 42 },
+///     | otherwise => { misses.set(misses.get() + 1); 0 },
+/// );
+/// assert_eq!(value, 42);
+/// assert_eq!((hits.get(), misses.get()), (1, 0));
+/// ```
+#[macro_export]
+macro_rules! guard_do {
+    ($($arms:tt)+) => {
+        $crate::guard!($($arms)+)
+    };
+}
+
+/// 返回 `Result` 的 Guard 表达式宏
+///
+/// 命中的分支结果自动包装为 `Ok(...)`，所有条件都不满足时返回 `err = ...`
+/// 给出的错误，而不是像 [`guard!`] 那样 panic。
+///
+/// # 语法
+///
+/// ```text
+/// guard_result!(
+///     | condition1 => value1,
+///     | condition2 => value2,
+///     err = error_value,
+///     where binding = value
+/// )
+/// ```
+///
+/// # 特性
+///
+/// - 分支写法与 [`guard_opt!`] 相同：支持字面量 `otherwise`、以 `let` 开头的 let-chain
+///   条件与写在 `err` 之后的 `where` 子句；where 绑定只在分支中可见，错误表达式中不可用
+/// - `err = expr` 生成 `Err(expr)`，错误类型由 `expr` 决定；是否启用 `std` 特性不影响其含义
+/// - 启用 `std` 特性时，`err_box = "message"` 生成 `Err("message".into())`，
+///   使字符串成为结果的错误类型（例如 `Box<dyn std::error::Error>`），适合使用 trait object
+///   错误的应用代码；此时结果类型需要能从上下文推断
+///
+/// # Examples
+///
+/// ## 显式错误值
+///
+/// ```
+/// use hgm::guard_result;
+///
+/// #[derive(Debug, PartialEq)]
+/// struct NoTier;
+///
+/// let score = 42;
+/// let tier = guard_result!(
+///     | score >= 90 => 'A',
+///     | score >= 60 => 'B',
+///     err = NoTier,
+/// );
+/// assert_eq!(tier, Err(NoTier));
+/// ```
+///
+/// ## 字符串错误（`std`）
+///
+#[cfg_attr(feature = "std", doc = "```")]
+#[cfg_attr(not(feature = "std"), doc = "```ignore")]
+/// use hgm::guard_result;
+///
+/// fn tier(score: u32) -> Result<char, Box<dyn std::error::Error>> {
+///     guard_result!(
+///         | score >= 90 => 'A',
+///         | score >= 60 => 'B',
+///         err_box = "no tier",
+///     )
+/// }
+///
+/// assert_eq!(tier(75).unwrap(), 'B');
+/// assert_eq!(tier(10).unwrap_err().to_string(), "no tier");
+/// ```
+#[macro_export]
+macro_rules! guard_result {
+    // 收集 `err =` 之前的分支，交给 guard_opt! 求值，因此 `otherwise`、let-chain 与
+    // where 子句的写法与 guard_opt! 相同；`None` 时才求值错误表达式
+    (@split [$($arms:tt)*] err = $err:expr $(, where $($bindings:tt)+)? $(,)?) => {
+        match $crate::guard_opt!($($arms)* $(where $($bindings)+)?) {
+            ::core::option::Option::Some(value) => ::core::result::Result::Ok(value),
+            ::core::option::Option::None => ::core::result::Result::Err($err),
+        }
+    };
+    (@split [$($arms:tt)*] err_box = $err:expr $(, where $($bindings:tt)+)? $(,)?) => {
+        match $crate::guard_opt!($($arms)* $(where $($bindings)+)?) {
+            ::core::option::Option::Some(value) => ::core::result::Result::Ok(value),
+            ::core::option::Option::None => $crate::__guard_result_err_box!($err),
+        }
+    };
+    (@split [$($arms:tt)*] $next:tt $($rest:tt)*) => {
+        $crate::guard_result!(@split [$($arms)* $next] $($rest)*)
+    };
+    (@split [$($arms:tt)*]) => {
+        compile_error!("guard_result!: expected `err = ...` after the arms")
+    };
+    (| $($rest:tt)+) => {
+        $crate::guard_result!(@split [] | $($rest)+)
+    };
+}
+
+#[doc(hidden)]
+#[cfg(feature = "std")]
+#[macro_export]
+macro_rules! __guard_result_err_box {
+    // 经 `Into` 转换，使 `"message"` 可以成为 `Box<dyn Error>`
+    ($err:expr) => {
+        ::core::result::Result::Err(::core::convert::Into::into($err))
+    };
+}
+
+#[doc(hidden)]
+#[cfg(not(feature = "std"))]
+#[macro_export]
+macro_rules! __guard_result_err_box {
+    ($err:expr) => {
+        compile_error!("guard_result!: `err_box = ...` requires the `std` feature")
+    };
+}
+
+/// 返回 `Option` 的 Guard 表达式宏
+///
+/// 命中的分支结果包装为 `Some(...)`；所有条件都不满足时返回 `None`，
+/// 而不是像 [`guard!`] 那样 panic。
+/// 与 [`guard!`] 一样支持 `let` 条件与 `where` 子句；写了 `otherwise` 时，
+/// 其结果同样包装为 `Some(...)`。
+///
+/// # 语法
+///
+/// ```text
+/// guard_opt!(
+///     | condition1 => value1,
+///     | condition2 => value2,
+/// )
+/// ```
+///
+/// # Examples
+///
+/// ```
+/// use hgm::guard_opt;
+///
+/// let grade = |score: u32| guard_opt!(
+///     | score >= 90 => 'A',
+///     | score >= 60 => 'B',
+/// );
+/// assert_eq!(grade(95), Some('A'));
+/// assert_eq!(grade(30), None);
+/// ```
+#[macro_export]
+macro_rules! guard_opt {
+    (@emit [$(($($cond:tt)+) => ($result:expr))*] [$($bindings:tt)*] $fallback:block) => {{
+        #[allow(unused_imports)]
+        use $crate::otherwise;
+        $crate::__where_lets!($($bindings)*);
+        $(if $($cond)+ { ::core::option::Option::Some($result) } else)* $fallback
+    }};
+    (@acc [$($arms:tt)*] | otherwise => $result:expr $(, $($bindings:tt)*)?) => {
+        $crate::guard_opt!(
+            @emit [$($arms)*] [$($($bindings)*)?] { ::core::option::Option::Some($result) }
+        )
+    };
+    (@acc [$($arms:tt)*] | let $($rest:tt)+) => {
+        $crate::guard_opt!(@let [$($arms)*] [let] $($rest)+)
+    };
+    (@acc [$($arms:tt)*] | [likely] $cond:expr => $result:expr $(, $($rest:tt)*)?) => {
+        $crate::guard_opt!(@acc [$($arms)* ($crate::__private::likely($cond)) => ($result)] $($($rest)*)?)
+    };
+    (@acc [$($arms:tt)*] | [unlikely] $cond:expr => $result:expr $(, $($rest:tt)*)?) => {
+        $crate::guard_opt!(@acc [$($arms)* ($crate::__private::unlikely($cond)) => ($result)] $($($rest)*)?)
+    };
+    (@acc [$($arms:tt)*] | $cond:expr => $result:expr $(, $($rest:tt)*)?) => {
+        $crate::guard_opt!(@acc [$($arms)* ($cond) => ($result)] $($($rest)*)?)
+    };
+    (@acc [$($arms:tt)*] $(where $($bindings:tt)+)?) => {
+        $crate::guard_opt!(@emit [$($arms)*] [$($($bindings)+)?] { ::core::option::Option::None })
+    };
+    (@let [$($arms:tt)*] [$($cond:tt)+] => $result:expr $(, $($rest:tt)*)?) => {
+        $crate::guard_opt!(@acc [$($arms)* ($($cond)+) => ($result)] $($($rest)*)?)
+    };
+    (@let [$($arms:tt)*] [$($cond:tt)+] $next:tt $($rest:tt)+) => {
+        $crate::guard_opt!(@let [$($arms)*] [$($cond)+ $next] $($rest)+)
+    };
+    ($($arms:tt)+) => {
+        $crate::guard_opt!(@acc [] $($arms)+)
+    };
+}
+
+/// 组合多组规则的 Guard 表达式宏
+///
+/// 各组分支以 `;` 分隔。前面的每一组都按 [`guard_opt!`] 求值，有分支命中时
+/// 直接得到其结果；没有命中时才求值下一组。最后一组按 [`guard!`] 求值，
+/// 通常以 `otherwise` 结尾。`where` 子句只能写在最后一组之后，其绑定只对最后一组可见。
+///
+/// # 语法
+///
+/// ```text
+/// guard_chain!(
+///     | primary_condition => value1,
+///     | primary_condition2 => value2;
+///     | fallback_condition => value3,
+///     | otherwise => default,
+/// )
+/// ```
+///
+/// # Examples
+///
+/// ```
+/// use hgm::guard_chain;
+///
+/// // 项目配置优先，其次是全局默认规则
+/// let threads = |configured: Option<u32>, cores: u32| guard_chain!(
+///     | configured == Some(0) => 1,
+///     | let Some(n) = configured => n;
+///     | cores > 64 => 64,
+///     | otherwise => cores,
+/// );
+/// assert_eq!(threads(Some(4), 8), 4);
+/// assert_eq!(threads(None, 128), 64);
+/// assert_eq!(threads(None, 8), 8);
+/// ```
+#[macro_export]
+macro_rules! guard_chain {
+    // 遇到 `;`：当前组按 guard_opt! 求值，未命中时才求值后续各组
+    (@split [$($group:tt)*] ; $($rest:tt)+) => {
+        match $crate::guard_opt!($($group)*) {
+            ::core::option::Option::Some(value) => value,
+            ::core::option::Option::None => $crate::guard_chain!(@split [] $($rest)+),
+        }
+    };
+    (@split [$($group:tt)*]) => {
+        $crate::guard!($($group)*)
+    };
+    (@split [$($group:tt)*] where $($bindings:tt)+) => {
+        $crate::guard!($($group)* where $($bindings)+)
+    };
+    (@split [$($group:tt)*] | otherwise => $($rest:tt)+) => {
+        $crate::guard_chain!(@result [$($group)*] [otherwise] $($rest)+)
+    };
+    (@split [$($group:tt)*] | let $($rest:tt)+) => {
+        $crate::guard_chain!(@let [$($group)*] [let] $($rest)+)
+    };
+    (@split [$($group:tt)*] | [$hint:ident] $cond:expr => $($rest:tt)+) => {
+        $crate::guard_chain!(@result [$($group)*] [[$hint] $cond] $($rest)+)
+    };
+    (@split [$($group:tt)*] | $cond:expr => $($rest:tt)+) => {
+        $crate::guard_chain!(@result [$($group)*] [$cond] $($rest)+)
+    };
+    (@let [$($group:tt)*] [$($cond:tt)+] => $($rest:tt)+) => {
+        $crate::guard_chain!(@result [$($group)*] [$($cond)+] $($rest)+)
+    };
+    (@let [$($group:tt)*] [$($cond:tt)+] $next:tt $($rest:tt)+) => {
+        $crate::guard_chain!(@let [$($group)*] [$($cond)+ $next] $($rest)+)
+    };
+    (@result [$($group:tt)*] [$($cond:tt)+] $result:expr ; $($rest:tt)+) => {
+        $crate::guard_chain!(@split [$($group)* | $($cond)+ => $result] ; $($rest)+)
+    };
+    (@result [$($group:tt)*] [$($cond:tt)+] $result:expr $(, $($rest:tt)*)?) => {
+        $crate::guard_chain!(@split [$($group)* | $($cond)+ => $result,] $($($rest)*)?)
+    };
+    ($($arms:tt)+) => {
+        $crate::guard_chain!(@split [] $($arms)+)
+    };
+}
+
+/// 返回 `ControlFlow` 的 Guard 表达式宏
+///
+/// 与 [`guard!`] 语法完全相同，但结果类型固定为 [`core::ops::ControlFlow`]，
+/// 用于在遍历、`try_fold` 等场景中清楚地表达 “继续” 或 “提前结束”。
+///
+/// # 语法
+///
+/// ```text
+/// guard_flow!(
+///     | condition => ControlFlow::Break(value),
+///     | otherwise => ControlFlow::Continue(state),
+/// )
+/// ```
+///
+/// # Examples
+///
+/// ```
+/// use std::ops::ControlFlow;
+/// use hgm::guard_flow;
+///
+/// // 累加直到总和超过 10，返回越界时的元素
+/// let flow = [3, 4, 5, 6].iter().try_fold(0, |sum, &n| {
+///     guard_flow!(
+///         | sum + n > 10 => ControlFlow::Break(n),
+///         | otherwise => ControlFlow::Continue(sum + n),
+///     )
+/// });
+/// assert_eq!(flow, ControlFlow::Break(5));
+/// ```
+#[macro_export]
+macro_rules! guard_flow {
+    ($($arms:tt)+) => {{
+        let flow: ::core::ops::ControlFlow<_, _> = $crate::guard!($($arms)+);
+        flow
+    }};
+}
+
+/// 依次尝试多个可失败的计算，返回第一个成功结果
+///
+/// 相当于 Haskell 中 `Alternative` 的 `asum`：按书写顺序求值每个表达式，
+/// 得到 `Ok` / `Some` 时立即返回，之后的表达式不会求值；全部失败时返回最后
+/// 一个表达式的结果（`Err` 或 `None`）。与 [`guard_first_ok!`] 不同，这里没有条件，
+/// 每个尝试都会被执行，直到成功为止。
+///
+/// # 语法
+///
+/// ```text
+/// first_ok!(attempt1, attempt2, attempt3)
+/// ```
+///
+/// # Examples
+///
+/// ```
+/// use hgm::first_ok;
+///
+/// fn parse_decimal(s: &str) -> Result<u32, String> {
+///     s.parse().map_err(|_| format!("`{s}` is not decimal"))
+/// }
+///
+/// fn parse_hex(s: &str) -> Result<u32, String> {
+///     let digits = s.strip_prefix("0x").ok_or_else(|| format!("`{s}` has no 0x prefix"))?;
+///     u32::from_str_radix(digits, 16).map_err(|_| format!("`{s}` is not hex"))
+/// }
+///
+/// let parse = |s: &str| first_ok!(parse_decimal(s), parse_hex(s));
+/// assert_eq!(parse("42"), Ok(42));
+/// assert_eq!(parse("0xff"), Ok(255));
+/// // 全部失败时返回最后一次尝试的错误
+/// assert_eq!(parse("zz"), Err(String::from("`zz` has no 0x prefix")));
+/// ```
+#[macro_export]
+macro_rules! first_ok {
+    ($last:expr $(,)?) => {
+        $last
+    };
+    ($first:expr, $($rest:expr),+ $(,)?) => {{
+        let attempt = $first;
+        if $crate::__private::Fallible::is_success(&attempt) {
+            attempt
+        } else {
+            $crate::first_ok!($($rest),+)
+        }
+    }};
+}
+
+/// 返回引用的 Guard 表达式宏
+///
+/// 与 [`guard!`] 语法相同，但结果在带类型标注的 `let` 中接收：默认标注为 `&_`，
+/// 也可以用 `-> &T;` 前缀给出具体的引用类型。各分支因此在同一个期望类型下检查，
+/// 指向不同借用数据的引用会统一到共同的生命周期，`&String`、`&Vec<T>` 等也会
+/// 经解引用强制转换为 `&str`、`&[T]`。
+///
+/// 展开中没有中间的临时值，只要被引用的数据活得足够久，结果就可以从访问器
+/// 方法中直接返回。
+///
+/// # 语法
+///
+/// ```text
+/// guard_ref!(
+///     | condition => &data.a,
+///     | otherwise => &data.b,
+/// )
+///
+/// guard_ref!(-> &str;
+///     | condition => &owned_string,
+///     | otherwise => "literal",
+/// )
+/// ```
+///
+/// # Examples
+///
+/// ```
+/// use hgm::guard_ref;
+///
+/// struct Profile { nickname: String, full_name: String, prefer_nickname: bool }
+///
+/// impl Profile {
+///     fn display_name(&self) -> &str {
+///         guard_ref!(-> &str;
+///             | self.prefer_nickname && !self.nickname.is_empty() => &self.nickname,
+///             | self.full_name.is_empty() => "anonymous",
+///             | otherwise => &self.full_name,
+///         )
+///     }
+/// }
+///
+/// let profile = Profile {
+///     nickname: String::new(),
+///     full_name: String::from("Ada Lovelace"),
+///     prefer_nickname: true,
+/// };
+/// assert_eq!(profile.display_name(), "Ada Lovelace");
+/// ```
+#[macro_export]
+macro_rules! guard_ref {
+    (-> $target:ty; $($arms:tt)+) => {{
+        let selected: $target = $crate::guard!($($arms)+);
+        selected
+    }};
+    ($($arms:tt)+) => {{
+        let selected: &_ = $crate::guard!($($arms)+);
+        selected
+    }};
+}
+
+/// 依次尝试条件成立的分支，返回第一个成功结果的 Guard 宏
+///
+/// 与 [`guard!`] 只执行第一个条件成立的分支不同，`guard_first_ok!` 会把
+/// 分支结果（`Option` 或 `Result`）视为一次尝试：
+///
+/// 1. 按书写顺序检查条件，跳过条件不成立的分支；
+/// 2. 条件成立时求值该分支，若得到 `Some` / `Ok` 则立即返回；
+/// 3. 若得到 `None` / `Err`，继续尝试后面条件成立的分支；
+/// 4. 所有被尝试的分支都失败时，返回最后一次尝试的结果；
+/// 5. 一个条件成立的分支都没有时 panic。
+///
+/// 每个条件最多求值一次，且只有条件成立的分支才会被求值。
+///
+/// # 语法
+///
+/// ```text
+/// guard_first_ok!(
+///     | condition1 => attempt1,
+///     | condition2 => attempt2,
+///     | otherwise => fallback,
+/// )
+/// ```
+///
+/// # Examples
+///
+/// ```
+/// use std::collections::HashMap;
+/// use hgm::guard_first_ok;
+///
+/// let cache = HashMap::from([("b", 2)]);
+/// let defaults = HashMap::from([("a", 10), ("b", 20)]);
+/// let use_cache = true;
+///
+/// let lookup = |key| guard_first_ok!(
+///     | use_cache => cache.get(key).copied(),
+///     | otherwise => defaults.get(key).copied(),
+/// );
+/// assert_eq!(lookup("b"), Some(2));   // 命中缓存
+/// assert_eq!(lookup("a"), Some(10));  // 缓存未命中，回退到默认值
+/// assert_eq!(lookup("c"), None);      // 全部失败，返回最后一次尝试的结果
+/// ```
+#[macro_export]
+macro_rules! guard_first_ok {
+    (@acc [$(($cond:expr) => ($result:expr))*]) => {
+        'hgm_first_ok: {
+            let mut last = ::core::option::Option::None;
+            $(
+                if $cond {
+                    let attempt = $result;
+                    if $crate::__private::Fallible::is_success(&attempt) {
+                        break 'hgm_first_ok attempt;
+                    }
+                    last = ::core::option::Option::Some(attempt);
+                }
+            )*
+            match last {
+                ::core::option::Option::Some(attempt) => attempt,
+                ::core::option::Option::None => panic!("Non-exhaustive guards"),
+            }
+        }
+    };
+    (@acc [$($arms:tt)*] | otherwise => $result:expr $(, $($rest:tt)*)?) => {
+        $crate::guard_first_ok!(@acc [$($arms)* (true) => ($result)] $($($rest)*)?)
+    };
+    (@acc [$($arms:tt)*] | $cond:expr => $result:expr $(, $($rest:tt)*)?) => {
+        $crate::guard_first_ok!(@acc [$($arms)* ($cond) => ($result)] $($($rest)*)?)
+    };
+    ($($arms:tt)+) => {
+        $crate::guard_first_ok!(@acc [] $($arms)+)
+    };
+}
+
+/// 在所有条件成立的分支中按权重随机选择的 Guard 宏
+///
+/// 每个分支的结果写作 `(权重, 结果)`。所有条件按书写顺序各求值一次，条件成立的分支
+/// 才会求值权重（`u64`）；随后以随机数在这些分支中按权重比例选择一个，只求值被选中分支的结果，
+/// 返回 `Some(结果)`。没有条件成立（或权重之和为 0）时返回 `None`。
+///
+/// 随机数由调用者提供：第一个参数是 `FnMut(u64) -> u64` 闭包，接收权重之和 `total`，
+/// 必须返回 `0..total` 中均匀分布的数，否则 panic；测试中传入固定种子的生成器即可得到确定的结果。
+/// 启用 `rand` 特性后也可以写 `rng = &mut rng`，直接传入任意 `rand::Rng`。
+///
+/// # 语法
+///
+/// ```text
+/// guard_weighted!(random;
+///     | condition1 => (weight1, result1),
+///     | condition2 => (weight2, result2),
+///     | otherwise => (weight3, result3),
+/// )
+///
+/// guard_weighted!(rng = &mut rng; ...)  // 需要 `rand` 特性
+/// ```
+///
+/// # Examples
+///
+/// ```
+/// use hgm::guard_weighted;
+///
+/// // 固定种子的线性同余生成器
+/// let mut state: u64 = 42;
+/// let mut random = |total: u64| {
+///     state = state.wrapping_mul(6_364_136_223_846_793_005).wrapping_add(1);
+///     (state >> 33) % total
+/// };
+///
+/// let hungry = true;
+/// let mut counts = [0; 3];
+/// for _ in 0..1000 {
+///     let action = guard_weighted!(&mut random;
+///         | hungry => (3, 0),
+///         | !hungry => (5, 1),
+///         | otherwise => (1, 2),
+///     );
+///     counts[action.unwrap()] += 1;
+/// }
+/// assert_eq!(counts[1], 0);
+/// assert!(counts[0] > counts[2] * 2);
+///
+/// let none: Option<u8> = guard_weighted!(&mut random; | false => (1, 0), | hungry => (0, 1));
+/// assert_eq!(none, None);
+/// ```
+///
+/// 使用 `rand`：
+///
+#[cfg_attr(feature = "rand", doc = "```")]
+#[cfg_attr(not(feature = "rand"), doc = "```ignore")]
+/// use hgm::guard_weighted;
+/// use rand::SeedableRng;
+/// use rand::rngs::StdRng;
+///
+/// let mut rng = StdRng::seed_from_u64(7);
+/// let loot = guard_weighted!(rng = &mut rng;
+///     | true => (9, "common"),
+///     | true => (1, "rare"),
+/// );
+/// assert!(loot.is_some());
+/// ```
+#[macro_export]
+macro_rules! guard_weighted {
+    (rng = $rng:expr; $($arms:tt)+) => {
+        $crate::guard_weighted!(|total| $crate::__guard_weighted_rng!($rng, total); $($arms)+)
+    };
+    ($random:expr; $(| $cond:expr => ($weight:expr, $result:expr)),+ $(,)?) => {{
+        #[allow(unused_imports)]
+        use $crate::otherwise;
+        let weights: &[u64] = &[$(if $cond { $weight } else { 0 }),+];
+        let total: u64 = weights.iter().sum();
+        'hgm_weighted: {
+            if total == 0 {
+                break 'hgm_weighted ::core::option::Option::None;
+            }
+            let mut pick = $crate::__private::draw($random, total);
+            let mut chosen = 0;
+            while pick >= weights[chosen] {
+                pick -= weights[chosen];
+                chosen += 1;
+            }
+            let mut arm = 0;
+            $(
+                arm += 1;
+                if arm == chosen + 1 {
+                    break 'hgm_weighted ::core::option::Option::Some($result);
+                }
+            )+
+            unreachable!()
+        }
+    }};
+}
+
+#[doc(hidden)]
+#[cfg(feature = "rand")]
+#[macro_export]
+macro_rules! __guard_weighted_rng {
+    ($rng:expr, $total:expr) => {
+        $crate::__private::draw_rng($rng, $total)
+    };
+}
+
+#[doc(hidden)]
+#[cfg(not(feature = "rand"))]
+#[macro_export]
+macro_rules! __guard_weighted_rng {
+    ($rng:expr, $total:expr) => {
+        compile_error!("guard_weighted!: `rng = ...` requires the `rand` feature")
+    };
+}
+
+// fn_guard! 的 #[boxed]：每个分支（包括 otherwise）的结果都装箱，在声明的 `Box<dyn Trait>` 处统一；
+// 返回类型写作 `impl Trait` 时，装箱后的 `Box<dyn Trait>` 作为不透明类型返回
+#[doc(hidden)]
+#[cfg(feature = "alloc")]
+#[macro_export]
+macro_rules! __fn_guard_boxed {
+    // `impl` 之后的约束可以有多个（`Display + Send + 'a`），逐个收集直到第一个分支
+    (@impl [$($attrs:tt)*] [$vis:vis fn $name:ident ($($args:tt)*)] [$($bound:tt)+] | $($arms:tt)+) => {
+        $($attrs)*
+        #[track_caller]
+        $vis fn $name($($args)*) -> impl $($bound)+ {
+            let boxed: $crate::__private::Box<dyn $($bound)+> = $crate::__guard_wrap!(
+                [$crate::__private::Box::new] [$crate::__private::Box::new] [instrument;] | $($arms)+
+            );
+            boxed
+        }
+    };
+    (@impl [$($attrs:tt)*] [$($sig:tt)+] [$($bound:tt)*] $next:tt $($rest:tt)+) => {
+        $crate::__fn_guard_boxed!(@impl [$($attrs)*] [$($sig)+] [$($bound)* $next] $($rest)+);
+    };
+    ($(#[$attr:meta])* $vis:vis fn $name:ident ($($args:tt)*) -> impl $($rest:tt)+) => {
+        $crate::__fn_guard_boxed!(@impl [$(#[$attr])*] [$vis fn $name ($($args)*)] [] $($rest)+);
+    };
+    ($(#[$attr:meta])* $vis:vis fn $name:ident ($($args:tt)*) -> Box<$inner:ty> | $($arms:tt)+) => {
+        $(#[$attr])*
+        #[track_caller]
+        $vis fn $name($($args)*) -> $crate::__private::Box<$inner> {
+            $crate::__guard_wrap!(
+                [$crate::__private::Box::new] [$crate::__private::Box::new] [instrument;] | $($arms)+
+            )
+        }
+    };
+    ($($item:tt)+) => {
+        compile_error!("fn_guard!: #[boxed] functions must return `Box<dyn Trait>` or `impl Trait`");
+    };
+}
+
+#[doc(hidden)]
+#[cfg(not(feature = "alloc"))]
+#[macro_export]
+macro_rules! __fn_guard_boxed {
+    ($($item:tt)+) => {
+        compile_error!("fn_guard!: #[boxed] requires the `alloc` (or `std`) feature");
+    };
+}
+
+/// Haskell 风格的 Guard 函数定义宏
+///
+/// 允许使用 Haskell guards 语法直接定义函数，将函数签名与 guard 条件
+/// 结合在一起，类似于 Haskell 的：
+///
+/// ```haskell
+/// abs :: Int -> Int
+/// abs n
+///     | n < 0     = -n
+///     | otherwise = n
+/// ```
+///
+/// # 语法
+///
+/// ```text
+/// fn_guard!(
+///     #[attributes]           // 可选的属性
+///     pub fn name(arg1: Type1, arg2: Type2) -> ReturnType
+///     | condition1 => result1,
+///     | condition2 => result2,
+///     | otherwise => default,
+///     where binding = value,  // 可选的 where 子句
+/// );
+/// ```
+///
+/// # 特性
+///
+/// - 支持可见性修饰符（`pub`, `pub(crate)` 等）
+/// - 支持函数属性（`#[inline]`, `#[must_use]` 等）
+/// - 支持泛型参数，包括生命周期与 `const N: usize` 这样的 const 泛型
+/// - 返回类型之后可以写 trait 约束的 `where` 子句（如 `where Self: PartialOrd`），与分支后的绑定 `where` 互不冲突
+/// - 支持有返回值和无返回值（`-> ()`）的函数，参数列表可以为空
+/// - 支持 `const fn`，生成的函数可以在常量求值中调用
+/// - 支持 `where` 子句定义局部绑定（逗号分隔，或 `{ a = 1; b = 2; }` 形式）
+/// - 函数体即 `guard!` 表达式，返回 `Option` / `Result` 时分支、条件与 where 绑定中都可以使用 `?`
+/// - where 绑定可以与参数同名（`where x = x.abs()`），之后的绑定与分支看到遮蔽后的值
+/// - 支持在 `impl` 块中定义带 `&self` / `&mut self` / `self` 接收者的方法，包括 `impl Add` 等运算符 trait 的方法
+/// - 方法中的 where 绑定可以引用 `self` 及其字段（`where bmi = self.weight / self.height`）
+/// - 支持在 trait 实现中定义返回 `Self` 的关联函数
+/// - 生成的非 `async` 函数带有 `#[track_caller]`：没有 `otherwise` 且所有条件都不满足时，
+///   panic 报告的位置是调用该函数的代码，而不是函数定义处
+/// - 第一个属性写 `#[document_arms]` 时，会在生成函数的文档末尾追加
+///   `# Cases` 小节，逐条列出每个分支的条件与结果
+/// - 第一个属性写 `#[result]` 时，除 `otherwise` 以外的分支结果自动包装为 `Ok(...)`，
+///   `otherwise` 分支原样作为失败时的返回值（通常写作 `Err(...)`）
+/// - 分支前可以写 `#[cfg(...)]` 等属性，只保留启用的分支
+/// - 第一个属性写 `#[boxed]` 时，各分支的结果装箱为 `Box<dyn Trait>`，允许分支返回不同的类型
+/// - 第一个属性写 `#[table(NAME: lo..=hi)]` 时，额外生成预先计算好区间内结果的常量数组
+/// - 第一个属性写 `#[verify_over(SAMPLES)]` 时，额外生成对样本逐一调用函数、断言不 panic 的测试
+/// - 第一个属性写 `#[equations]` 时，各分支按参数模式匹配，类似 Haskell 的多条方程
+/// - 第一个属性写 `#[statements]` 时，各分支按顺序展开为互不排斥的
+///   `if condition { statement; }`，适合写多个提前 `return` 的函数
+///
+/// # Examples
+///
+/// ## 基本函数定义
+///
+/// ```
+/// use hgm::fn_guard;
+///
+/// fn_guard!(
+///     fn abs(n: i32) -> i32
+///     | n < 0 => -n,
+///     | otherwise => n,
+/// );
+///
+/// assert_eq!(abs(-5), 5);
+/// assert_eq!(abs(3), 3);
+/// ```
+///
+/// ## 带属性和 where 子句
+///
+/// ```
+/// use hgm::fn_guard;
+///
+/// fn_guard!(
+///     #[inline]
+///     pub fn bmi_category(weight: f64, height: f64) -> &'static str
+///     | bmi < 18.5 => "Underweight",
+///     | bmi < 25.0 => "Normal",
+///     | bmi < 30.0 => "Overweight",
+///     | otherwise => "Obese",
+///     where bmi = weight / (height * height),
+/// );
+///
+/// assert_eq!(bmi_category(70.0, 1.75), "Normal");
+/// ```
+///
+/// ## 在 where 中遮蔽参数
+///
+/// where 绑定展开为函数体开头按顺序执行的 `let` 语句，因此可以与参数同名：
+/// `where x = x.abs()` 中右侧的 `x` 是参数，之后的 where 绑定与所有分支
+/// （包括条件、结果与 `otherwise`）看到的都是遮蔽后的新值，原参数不再可见。
+///
+/// ```
+/// use hgm::fn_guard;
+///
+/// fn_guard!(
+///     fn magnitude(x: i32) -> &'static str
+///     | x == 0 => "zero",
+///     | x < 10 => "small",
+///     | otherwise => "large",
+///     where x = x.abs()
+/// );
+///
+/// assert_eq!(magnitude(-3), "small");
+/// assert_eq!(magnitude(-30), "large");
+/// ```
+///
+/// ## 无返回值函数
+///
+/// ```
+/// use hgm::fn_guard;
+///
+/// fn_guard!(
+///     fn log_level(level: u8)
+///     | level == 0 => println!("DEBUG"),
+///     | level == 1 => println!("INFO"),
+///     | level == 2 => println!("WARN"),
+///     | otherwise => println!("ERROR"),
+/// );
+/// ```
+///
+/// ## 在 impl 块中实现 `Display`
+///
+/// ```
+/// use std::fmt;
+/// use hgm::fn_guard;
+///
+/// struct Celsius(f64);
+///
+/// impl fmt::Display for Celsius {
+///     fn_guard!(
+///         fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result
+///         | self.0 < 0.0 => write!(f, "{:.1}°C (freezing)", self.0),
+///         | self.0 >= 100.0 => write!(f, "{:.1}°C (boiling)", self.0),
+///         | otherwise => write!(f, "{:.1}°C", self.0),
+///     );
+/// }
+///
+/// assert_eq!(Celsius(-4.0).to_string(), "-4.0°C (freezing)");
+/// assert_eq!(Celsius(21.5).to_string(), "21.5°C");
+/// ```
+///
+/// ## 返回 `Self` 的关联函数
+///
+/// 在 `impl` 块（包括 trait 的实现）中，返回类型与分支都可以使用 `Self`：
+///
+/// ```
+/// use hgm::fn_guard;
+///
+/// #[derive(Debug, PartialEq)]
+/// enum Level { Low, High }
+///
+/// trait FromCode {
+///     fn from_code(code: u8) -> Self;
+/// }
+///
+/// impl FromCode for Level {
+///     fn_guard!(
+///         fn from_code(code: u8) -> Self
+///         | code < 128 => Self::Low,
+///         | otherwise => Self::High,
+///     );
+/// }
+///
+/// assert_eq!(Level::from_code(200), Level::High);
+/// ```
+///
+/// ## trait 约束与默认方法
+///
+/// 返回类型之后可以写 trait 约束的 `where` 子句，它在第一个分支的 `|` 处结束；
+/// 分支之后的 `where` 仍是局部绑定。两者可以同时使用，也可以用在 trait 的默认方法中：
+///
+/// ```
+/// use hgm::fn_guard;
+///
+/// trait Clampable {
+///     fn_guard!(
+///         fn clamp_to(self, lo: Self, hi: Self) -> Self
+///         where Self: PartialOrd + Sized
+///         | self < lo => lo,
+///         | self > hi => hi,
+///         | otherwise => self,
+///     );
+/// }
+///
+/// impl Clampable for i32 {}
+///
+/// assert_eq!(15.clamp_to(0, 10), 10);
+/// assert_eq!((-3).clamp_to(0, 10), 0);
+/// ```
+///
+/// ## 泛型与 const 泛型
+///
+/// ```
+/// use hgm::fn_guard;
+///
+/// fn_guard!(
+///     fn first_or_zero<const N: usize>(bytes: [u8; N]) -> u8
+///     | N == 0 => 0,
+///     | otherwise => bytes[0],
+/// );
+///
+/// assert_eq!(first_or_zero([]), 0);
+/// assert_eq!(first_or_zero([7, 8, 9]), 7);
+/// ```
+///
+/// ## 异步函数与 `.await` 绑定
+///
+/// `async fn` 同样受支持。where 绑定展开在异步函数体内部，因此可以直接写
+/// `where data = fetch().await`，绑定在所有条件之前求值一次。
+///
+/// ```
+/// use hgm::fn_guard;
+///
+/// async fn fetch_quota(user: &str) -> u32 {
+///     if user == "admin" { 100 } else { 3 }
+/// }
+///
+/// fn_guard!(
+///     async fn can_upload(user: &str, files: u32) -> bool
+///     | files == 0 => true,
+///     | files <= quota => true,
+///     | otherwise => false,
+///     where quota = fetch_quota(user).await
+/// );
+///
+/// let _pending = can_upload("guest", 5); // 返回 Future，需要由执行器驱动
+/// ```
+///
+/// ## 在分支中使用 `?`
+///
+/// 生成的函数体就是 `guard!` 表达式本身，没有额外的闭包包装，因此返回 `Option`
+/// 或 `Result` 的函数可以在条件、结果与 where 绑定中使用 `?`，提前返回作用于这个函数。
+/// 没有 `otherwise` 时的兜底 `panic!` 是发散表达式，与任何返回类型都能统一。
+///
+/// ```
+/// use std::collections::HashMap;
+/// use hgm::fn_guard;
+///
+/// fn_guard!(
+///     fn port_of(config: &HashMap<&str, &str>, service: &str) -> Option<u16>
+///     | service.is_empty() => None,
+///     | raw.starts_with(':') => raw[1..].parse().ok(),
+///     | otherwise => Some(raw.parse().ok()?),
+///     where raw = *config.get(service)?
+/// );
+///
+/// let config = HashMap::from([("http", "80"), ("admin", ":8443"), ("bad", "x")]);
+/// assert_eq!(port_of(&config, "http"), Some(80));
+/// assert_eq!(port_of(&config, "admin"), Some(8443));
+/// assert_eq!(port_of(&config, "bad"), None);
+/// assert_eq!(port_of(&config, "ftp"), None);
+/// ```
+///
+/// ## 返回 `Result`：成功分支自动包装 `Ok`
+///
+/// ```
+/// use hgm::fn_guard;
+///
+/// #[derive(Debug, PartialEq)]
+/// enum ParseError { Empty, Unknown(String) }
+///
+/// fn_guard!(
+///     #[result]
+///     fn parse_bool(text: &str) -> Result<bool, ParseError>
+///     | text.is_empty() => return Err(ParseError::Empty),
+///     | matches!(lower.as_str(), "true" | "yes") => true,
+///     | matches!(lower.as_str(), "false" | "no") => false,
+///     | otherwise => Err(ParseError::Unknown(text.to_string())),
+///     where lower = text.to_ascii_lowercase()
+/// );
+///
+/// assert_eq!(parse_bool("Yes"), Ok(true));
+/// assert_eq!(parse_bool(""), Err(ParseError::Empty));
+/// assert_eq!(parse_bool("maybe"), Err(ParseError::Unknown("maybe".into())));
+/// ```
+///
+/// ## 分支返回不同的类型：`#[boxed]`
+///
+/// 各分支的结果类型必须相同，即使它们实现了同一个 trait：
+///
+/// ```compile_fail,E0308
+/// use std::fmt::Display;
+/// use hgm::fn_guard;
+///
+/// fn_guard!(
+///     fn label(n: i32) -> impl Display
+///     | n < 0 => "negative",
+///     | otherwise => n, // error: `if` and `else` have incompatible types
+/// );
+/// ```
+///
+/// 类型不同时编译器会报告 `if` 与 `else` 的类型不兼容（E0308）。`fn_guard!` 不会
+/// 给出提示改用 `#[boxed]` 的错误：`macro_rules!` 展开时看不到类型，无法判断分支能否
+/// 统一，这只能由过程宏或编译器本身完成（编译器的提示会建议改用 `Box<dyn Trait>`）。
+/// 这时在第一个属性写 `#[boxed]`：每个分支的结果（包括 `otherwise`）都会被
+/// `Box::new` 包装，并在声明的返回类型处统一为 trait object。
+/// 返回类型可以写作 `Box<dyn Trait>`，也可以写作 `impl Trait`（要求 `Box<dyn Trait>`
+/// 本身实现该 trait，标准库中的 `Display`、`Debug`、`Iterator`、`Error` 等都满足），
+/// `impl` 之后可以写多个约束，如 `impl Display + Send + 'static`。需要 `alloc` 特性
+/// （`std` 包含它，可用于 `no_std` + `alloc` 的 crate）；`panic!`、`return` 等发散的分支保持原样。
+///
+#[cfg_attr(feature = "alloc", doc = "```")]
+#[cfg_attr(not(feature = "alloc"), doc = "```ignore")]
+/// use std::fmt::Display;
+/// use hgm::fn_guard;
+///
+/// fn_guard!(
+///     #[boxed]
+///     fn label(n: i32) -> impl Display
+///     | n < 0 => "negative",
+///     | n > 1000 => format!("{}k", n / 1000),
+///     | otherwise => n,
+/// );
+///
+/// fn_guard!(
+///     #[boxed]
+///     fn evens(limit: u32, reverse: bool) -> Box<dyn Iterator<Item = u32>>
+///     | limit == 0 => std::iter::empty(),
+///     | reverse => (0..limit).rev().filter(|n| n % 2 == 0),
+///     | otherwise => (0..limit).step_by(2),
+/// );
+///
+/// assert_eq!(label(-3).to_string(), "negative");
+/// assert_eq!(label(4200).to_string(), "4k");
+/// assert_eq!(label(7).to_string(), "7");
+/// assert_eq!(evens(7, true).collect::<Vec<_>>(), [6, 4, 2, 0]);
+/// assert_eq!(evens(0, false).count(), 0);
+/// ```
+///
+/// `#[boxed]` 要求返回类型是这两种写法之一，否则得到编译错误：
+///
+/// ```compile_fail
+/// use hgm::fn_guard;
+///
+/// fn_guard!(
+///     #[boxed]
+///     fn count(n: u8) -> u8
+///     | n > 3 => 3,
+///     | otherwise => n,
+/// ); // error: fn_guard!: #[boxed] functions must return `Box<dyn Trait>` or `impl Trait`
+/// ```
+///
+/// ## 语句形式：多个提前返回
+///
+/// `#[statements]` 形式不是 `if/else` 链：每个条件为真的分支都会执行其语句，
+/// 因此分支本身不产生值，通常写作 `return ...`。`otherwise` 分支（如果有）
+/// 放在最后，作为函数体的结尾表达式；没有 `otherwise` 时函数体的值为 `()`。
+/// 写在中间的 `| otherwise =>` 等同于条件为 `true` 的分支，其语句总是执行。
+///
+/// ```
+/// use hgm::fn_guard;
+///
+/// fn_guard!(
+///     #[statements]
+///     fn validate(name: &str, age: i32) -> Result<(), String>
+///     | name.is_empty() => return Err("empty name".to_string()),
+///     | age < 0 => return Err(format!("negative age: {age}")),
+///     | otherwise => Ok(()),
+/// );
+///
+/// assert_eq!(validate("", 3), Err("empty name".to_string()));
+/// assert_eq!(validate("bob", -1), Err("negative age: -1".to_string()));
+/// assert_eq!(validate("bob", 30), Ok(()));
+/// ```
+///
+/// ## 记忆化递归（需要 `std` 特性）
+///
+/// `#[memoize]` 为生成的函数加上线程局部的 `HashMap` 缓存，以参数元组为键，
+/// 命中缓存时直接返回，不再求值 where 绑定与各分支。要求：
+///
+/// - 参数写作 `name: Type`，类型实现 `Hash + Eq + Clone`，且不能有泛型参数
+/// - 必须声明返回类型，返回类型实现 `Clone`
+/// - 缓存按线程分别保存，且不会被清理
+///
+#[cfg_attr(feature = "std", doc = "```")]
+#[cfg_attr(not(feature = "std"), doc = "```ignore")]
+/// use hgm::fn_guard;
+///
+/// fn_guard!(
+///     #[memoize]
+///     fn fib(n: u64) -> u64
+///     | n < 2 => n,
+///     | otherwise => fib(n - 1) + fib(n - 2),
+/// );
+///
+/// // 未记忆化时递归调用次数与 fib(90) 本身同一数量级
+/// assert_eq!(fib(90), 2_880_067_194_370_816_120);
+/// ```
+///
+/// ## 方程式定义
+///
+/// 第一个属性写 `#[equations]` 时，每个分支是参数的模式而不是布尔条件，
+/// 相当于 Haskell 中按参数模式写出的多条方程。只有一个参数时直接匹配该参数，
+/// 多个参数时匹配由全部参数组成的元组；分支写法与 [`case!`] 相同，支持 guard
+/// 与 `where` 子句，并且同样要求穷尽。
+///
+/// ```haskell
+/// fib 0 = 0
+/// fib 1 = 1
+/// fib n = fib (n - 1) + fib (n - 2)
+/// ```
+///
+/// ```
+/// use hgm::fn_guard;
+///
+/// fn_guard!(
+///     #[equations]
+///     fn fib(n: u64) -> u64
+///     | 0 => 0,
+///     | 1 => 1,
+///     | n => fib(n - 1) + fib(n - 2),
+/// );
+///
+/// fn_guard!(
+///     #[equations]
+///     fn gcd(a: u64, b: u64) -> u64
+///     | (a, 0) => a,
+///     | (a, b) => gcd(b, a % b),
+/// );
+///
+/// assert_eq!(fib(10), 55);
+/// assert_eq!(gcd(48, 18), 6);
+/// ```
+///
+/// 参数必须写成 `name: Type` 的形式，不支持接收者、泛型参数与 `async fn`。
+///
+/// ## 预计算查找表
+///
+/// 第一个属性写 `#[table(NAME: lo..=hi)]` 时，除了函数本身，还会生成常量数组
+/// `NAME`，其中 `NAME[i]` 是 `name(lo + i)` 的结果，热点代码可以直接索引数组
+/// 而不必逐个检查条件。
+///
+/// 要求：函数必须是只有一个整数参数的 `const fn`，返回类型实现 `Copy`；
+/// 区间端点写成整数字面量。表名需要显式给出，因为 `macro_rules!` 无法拼接出
+/// `NAME_TABLE` 这样的新标识符。
+///
+/// ```
+/// use hgm::fn_guard;
+///
+/// fn_guard!(
+///     #[table(PRIORITY_TABLE: 0..=9)]
+///     const fn priority(level: u8) -> u8
+///     | level >= 8 => 3,
+///     | level >= 4 => 2,
+///     | otherwise => 1,
+/// );
+///
+/// assert_eq!(PRIORITY_TABLE, [1, 1, 1, 1, 2, 2, 2, 2, 3, 3]);
+/// assert_eq!(PRIORITY_TABLE[5], priority(5));
+/// ```
+///
+/// ## 用样本验证分支覆盖（需要 `std` 特性）
+///
+/// 第一个属性写 `#[verify_over(SAMPLES)]` 时，除了函数本身，还会生成一个与函数同名的
+/// `#[cfg(test)]` 模块，其中的测试 `verify_over` 对 `SAMPLES` 中的每个样本调用函数，
+/// 任何一个样本触发 panic（例如缺少 `otherwise` 时没有分支命中）都会让测试失败，
+/// 失败信息列出所有出问题的样本。这是一种轻量的性质检查，适合分段定义的函数。
+///
+/// 要求：
+///
+/// - 只用于模块级的普通函数，参数写成 `name: Type` 的形式
+/// - `SAMPLES` 可以按引用转换为切片（数组、`Vec` 等），元素实现 `Debug`；
+///   只有一个参数时元素就是参数的类型，多个参数时是参数类型组成的元组
+/// - 参数类型实现 `Clone`，每个样本克隆后传给函数
+///
+/// ```
+/// use hgm::fn_guard;
+///
+/// const TEMPERATURES: [i32; 6] = [-40, -1, 0, 15, 30, 45];
+///
+/// fn_guard!(
+///     #[verify_over(TEMPERATURES)]
+///     fn weather(celsius: i32) -> &'static str
+///     | celsius < 0 => "freezing",
+///     | celsius < 20 => "mild",
+///     | celsius >= 20 => "hot",
+/// );
+///
+/// # fn main() {
+/// assert_eq!(weather(15), "mild");
+/// # }
+/// ```
+///
+/// ## 按 `cfg` 选择分支
+///
+/// 分支前可以写 `#[cfg(...)]` 等属性，只有启用的分支会出现在生成的函数中，
+/// 不同平台或特性下的 guard 逻辑因此可以写在同一个函数里。带属性的分支也可以
+/// 是 `otherwise`，但只有最后一个不带属性的 `otherwise` 会作为兜底分支。
+///
+/// ```
+/// use hgm::fn_guard;
+///
+/// fn_guard!(
+///     fn path_separator(windows_style: bool) -> char
+///     #[cfg(windows)]
+///     | otherwise => '\\',
+///     | windows_style => '\\',
+///     | otherwise => '/',
+/// );
+///
+/// assert_eq!(path_separator(true), '\\');
+/// # #[cfg(not(windows))]
+/// assert_eq!(path_separator(false), '/');
+/// ```
+///
+/// 存在带属性的分支时，`guard!` 改为把每个分支展开为独立的 `if` 语句，命中时
+/// 跳出外层带标签的块；这种形式不参与 `strict` 与 `profile` 特性的检查与统计。
+///
+/// ## 自动生成分支文档
+///
+/// ```
+/// use hgm::fn_guard;
+///
+/// fn_guard!(
+///     #[document_arms]
+///     /// 根据 HTTP 状态码判断响应类别。
+///     pub fn status_class(code: u16) -> &'static str
+///     | code < 200 => "informational",
+///     | code < 300 => "success",
+///     | code < 400 => "redirection",
+///     | otherwise => "error",
+/// );
+///
+/// // 生成的文档末尾包含：
+/// //
+/// // # Cases
+/// //
+/// // - `code < 200` => `"informational"`
+/// // - ...
+/// assert_eq!(status_class(204), "success");
+/// ```
+#[macro_export]
+macro_rules! fn_guard {
+    // #[document_arms]：把每个分支的条件与结果写入生成函数的文档
+    (#[document_arms] $($item:tt)+) => {
+        $crate::fn_guard!(@parse [document] $($item)+);
+    };
+    // #[memoize]：以参数元组为键缓存结果，参数必须是 `name: Type` 形式
+    (
+        #[memoize] $(#[$attr:meta])* $vis:vis fn $name:ident ($($arg:ident : $ty:ty),* $(,)?) -> $ret:ty
+        | $($arms:tt)+
+    ) => {
+        $(#[$attr])*
+        #[track_caller]
+        $vis fn $name($($arg: $ty),*) -> $ret {
+            $crate::__fn_guard_memoize!([$($arg: $ty),*] $ret, $crate::guard!(| $($arms)+))
+        }
+    };
+    // #[equations]：各分支是参数的模式，展开为对参数（多个时为元组）的 case!
+    (
+        #[equations] $(#[$attr:meta])* $vis:vis fn $name:ident ($($arg:ident : $ty:ty),* $(,)?) $(-> $ret:ty)?
+        | $($arms:tt)+
+    ) => {
+        $(#[$attr])*
+        $vis fn $name($($arg: $ty),*) $(-> $ret)? {
+            $crate::case!(($($arg),*) => | $($arms)+)
+        }
+    };
+    // #[table(NAME: lo..=hi)]：额外生成 const 数组，预先计算区间内每个参数的结果
+    (
+        #[table($table:ident : $lo:literal ..= $hi:literal)]
+        $(#[$attr:meta])* $vis:vis const fn $name:ident ($arg:ident : $ty:ty) -> $ret:ty
+        | $($arms:tt)+
+    ) => {
+        $crate::fn_guard!($(#[$attr])* $vis const fn $name($arg: $ty) -> $ret | $($arms)+);
+
+        $vis const $table: [$ret; ($hi - $lo + 1) as usize] = {
+            let mut table = [$name($lo); ($hi - $lo + 1) as usize];
+            let mut offset = 0;
+            while offset < table.len() {
+                table[offset] = $name($lo + offset as $ty);
+                offset += 1;
+            }
+            table
+        };
+    };
+    // #[verify_over(SAMPLES)]：函数本身照常生成，另外生成与函数同名的 #[cfg(test)] 模块
+    // （模块与函数位于不同的命名空间），其中的测试对每个样本调用函数并断言没有 panic
+    (#[verify_over($samples:expr)] $($item:tt)+) => {
+        $crate::fn_guard!($($item)+);
+        $crate::fn_guard!(@verify [$samples] $($item)+);
+    };
+    (@verify [$samples:expr] fn $name:ident ($($arg:ident : $ty:ty),* $(,)?) $($rest:tt)*) => {
+        #[cfg(test)]
+        mod $name {
+            use super::*;
+
+            #[test]
+            fn verify_over() {
+                $crate::__fn_guard_verify!($name, $samples, |($($arg),*): &($($ty),*)| {
+                    $name($(::core::clone::Clone::clone($arg)),*);
+                });
+            }
+        }
+    };
+    (@verify [$samples:expr] $skip:tt $($rest:tt)*) => {
+        $crate::fn_guard!(@verify [$samples] $($rest)*);
+    };
+    (@verify [$samples:expr]) => {
+        compile_error!("fn_guard!: #[verify_over] needs a free function with `name: Type` arguments");
+    };
+    // #[boxed]：见 __fn_guard_boxed!
+    (#[boxed] $($item:tt)+) => {
+        $crate::__fn_guard_boxed!($($item)+);
+    };
+    // #[result]：除 otherwise 外的分支结果自动包装为 `Ok(...)`
+    (#[result] $($item:tt)+) => {
+        $crate::fn_guard!(@parse [result] $($item)+);
+    };
+    // #[statements]：每个分支展开为独立的 `if cond { stmt; }`，按顺序执行
+    (#[statements] $($item:tt)+) => {
+        $crate::fn_guard!(@parse [statements] $($item)+);
+    };
+
+    // 解析签名：属性、可见性与函数名之后，可选的泛型参数列表逐个 token 收集
+    // 普通 fn 带上 #[track_caller]，没有 otherwise 时的 panic 位置指向调用者
+    (@parse [$mode:ident] $(#[$attr:meta])* $vis:vis fn $name:ident < $($rest:tt)+) => {
+        $crate::fn_guard!(@generics [$mode] [$(#[$attr])* #[track_caller]] [$vis fn $name] [<] $($rest)+);
+    };
+    (@parse [$mode:ident] $(#[$attr:meta])* $vis:vis fn $name:ident ($($args:tt)*) $($rest:tt)+) => {
+        $crate::fn_guard!(@signature [$mode] [$(#[$attr])* #[track_caller]] [$vis fn $name ($($args)*)] $($rest)+);
+    };
+    // const fn：函数体可以在常量求值中调用，@emit 为其选择不插桩的 guard! 展开
+    (@parse [$mode:ident] $(#[$attr:meta])* $vis:vis const fn $name:ident < $($rest:tt)+) => {
+        $crate::fn_guard!(@generics [$mode] [$(#[$attr])* #[track_caller]] [$vis const fn $name] [<] $($rest)+);
+    };
+    (@parse [$mode:ident] $(#[$attr:meta])* $vis:vis const fn $name:ident ($($args:tt)*) $($rest:tt)+) => {
+        $crate::fn_guard!(@signature [$mode] [$(#[$attr])* #[track_caller]] [$vis const fn $name ($($args)*)] $($rest)+);
+    };
+    // async fn（不支持 #[track_caller]）：`async` 保留在签名中，函数体（包括 where 绑定）位于异步上下文，可以使用 `.await`
+    (@parse [$mode:ident] $(#[$attr:meta])* $vis:vis async fn $name:ident < $($rest:tt)+) => {
+        $crate::fn_guard!(@generics [$mode] [$(#[$attr])*] [$vis async fn $name] [<] $($rest)+);
+    };
+    (@parse [$mode:ident] $(#[$attr:meta])* $vis:vis async fn $name:ident ($($args:tt)*) $($rest:tt)+) => {
+        $crate::fn_guard!(@signature [$mode] [$(#[$attr])*] [$vis async fn $name ($($args)*)] $($rest)+);
+    };
+    // 泛型列表在紧跟参数列表的 `>` 处结束
+    (@generics [$mode:ident] [$($attrs:tt)*] [$($head:tt)+] [$($gen:tt)+] > ($($args:tt)*) $($rest:tt)+) => {
+        $crate::fn_guard!(@signature [$mode] [$($attrs)*] [$($head)+ $($gen)+ > ($($args)*)] $($rest)+);
+    };
+    (@generics [$mode:ident] [$($attrs:tt)*] [$($head:tt)+] [$($gen:tt)+] $next:tt $($rest:tt)+) => {
+        $crate::fn_guard!(@generics [$mode] [$($attrs)*] [$($head)+] [$($gen)+ $next] $($rest)+);
+    };
+
+    // 参数列表原样转发，因此支持 `&self` 等接收者；分支与 where 子句交给 guard!
+    (@signature [$mode:ident] [$($attrs:tt)*] [$($sig:tt)+] -> $ret:ty | $($arms:tt)+) => {
+        $crate::fn_guard!(@emit [$mode] [$($attrs)*] [$($sig)+ -> $ret] | $($arms)+);
+    };
+    // 返回类型之后的 `where` 是 trait 约束，逐个 token 收集到第一个分支的 `|` 为止
+    (@signature [$mode:ident] [$($attrs:tt)*] [$($sig:tt)+] -> $ret:ty where $($rest:tt)+) => {
+        $crate::fn_guard!(@bounds [$mode] [$($attrs)*] [$($sig)+ -> $ret] [] $($rest)+);
+    };
+    (@bounds [$mode:ident] [$($attrs:tt)*] [$($sig:tt)+] [$($bounds:tt)+] | $($arms:tt)+) => {
+        $crate::fn_guard!(@emit [$mode] [$($attrs)*] [$($sig)+ where $($bounds)+] | $($arms)+);
+    };
+    (@bounds [$mode:ident] [$($attrs:tt)*] [$($sig:tt)+] [$($bounds:tt)*] $next:tt $($rest:tt)+) => {
+        $crate::fn_guard!(@bounds [$mode] [$($attrs)*] [$($sig)+] [$($bounds)* $next] $($rest)+);
+    };
+    // 第一个分支带属性时，`#` 不能跟在 ty 片段之后，改为逐个 token 收集返回类型
+    (@signature [$mode:ident] [$($attrs:tt)*] [$($sig:tt)+] -> $($rest:tt)+) => {
+        $crate::fn_guard!(@return [$mode] [$($attrs)*] [$($sig)+] [] $($rest)+);
+    };
+    (@return [$mode:ident] [$($attrs:tt)*] [$($sig:tt)+] [$($ret:tt)+] # $($arms:tt)+) => {
+        $crate::fn_guard!(@emit [$mode] [$($attrs)*] [$($sig)+ -> $($ret)+] # $($arms)+);
+    };
+    (@return [$mode:ident] [$($attrs:tt)*] [$($sig:tt)+] [$($ret:tt)*] $next:tt $($rest:tt)+) => {
+        $crate::fn_guard!(@return [$mode] [$($attrs)*] [$($sig)+] [$($ret)* $next] $($rest)+);
+    };
+    // 无返回值 (-> ())
+    (@signature [$mode:ident] [$($attrs:tt)*] [$($sig:tt)+] | $($arms:tt)+) => {
+        $crate::fn_guard!(@emit [$mode] [$($attrs)*] [$($sig)+] | $($arms)+);
+    };
+    (@signature [$mode:ident] [$($attrs:tt)*] [$($sig:tt)+] # $($arms:tt)+) => {
+        $crate::fn_guard!(@emit [$mode] [$($attrs)*] [$($sig)+] # $($arms)+);
+    };
+    (@signature [$mode:ident] [$($attrs:tt)*] [$($sig:tt)+] where $($rest:tt)+) => {
+        $crate::fn_guard!(@bounds [$mode] [$($attrs)*] [$($sig)+] [] $($rest)+);
+    };
+
+    // const fn 使用普通的 guard!；其他函数的函数体插桩，启用 profile / tracing 时出现在统计与事件中
+    (@emit [plain] [$($attrs:tt)*] [$vis:vis const fn $($sig:tt)+] $($arms:tt)+) => {
+        $($attrs)*
+        $vis const fn $($sig)+ {
+            $crate::guard!($($arms)+)
+        }
+    };
+    (@emit [result] [$($attrs:tt)*] [$vis:vis const fn $($sig:tt)+] $($arms:tt)+) => {
+        $($attrs)*
+        $vis const fn $($sig)+ {
+            $crate::__guard_wrap!([::core::result::Result::Ok] [] [] $($arms)+)
+        }
+    };
+    (@emit [plain] [$($attrs:tt)*] [$($sig:tt)+] $($arms:tt)+) => {
+        $($attrs)*
+        $($sig)+ {
+            $crate::guard!(instrument; $($arms)+)
+        }
+    };
+    (@emit [result] [$($attrs:tt)*] [$($sig:tt)+] $($arms:tt)+) => {
+        $($attrs)*
+        $($sig)+ {
+            $crate::__guard_wrap!([::core::result::Result::Ok] [] [instrument;] $($arms)+)
+        }
+    };
+    (@emit [statements] [$($attrs:tt)*] [$($sig:tt)+] $($arms:tt)+) => {
+        $($attrs)*
+        $($sig)+ {
+            $crate::fn_guard!(@statements [] $($arms)+)
+        }
+    };
+    (@statements [$($arms:tt)*] | otherwise => $tail:expr $(,)?) => {
+        $crate::fn_guard!(@sequence [$($arms)*] [] $tail)
+    };
+    (@statements [$($arms:tt)*] | otherwise => $tail:expr, where $($bindings:tt)+) => {
+        $crate::fn_guard!(@sequence [$($arms)*] [$($bindings)+] $tail)
+    };
+    // 写在中间的 otherwise 与 `true` 相同：其语句总是执行
+    (@statements [$($arms:tt)*] $(#[$meta:meta])* | otherwise => $stmt:expr, $($rest:tt)+) => {
+        $crate::fn_guard!(@statements [$($arms)* [$(#[$meta])*] (true) => ($stmt)] $($rest)+)
+    };
+    (@statements [$($arms:tt)*] $(#[$meta:meta])* | $cond:expr => $stmt:expr $(, $($rest:tt)*)?) => {
+        $crate::fn_guard!(@statements [$($arms)* [$(#[$meta])*] ($cond) => ($stmt)] $($($rest)*)?)
+    };
+    (@statements [$($arms:tt)*] where $($bindings:tt)+) => {
+        $crate::fn_guard!(@sequence [$($arms)*] [$($bindings)+] ())
+    };
+    (@statements [$($arms:tt)*]) => {
+        $crate::fn_guard!(@sequence [$($arms)*] [] ())
+    };
+    (
+        @sequence [$([$($attrs:tt)*] ($cond:expr) => ($stmt:expr))*]
+        [$($bindings:tt)*]
+        $tail:expr
+    ) => {{
+        $crate::__where_lets!($($bindings)*);
+        $($($attrs)* if $cond { $stmt; })*
+        $tail
+    }};
+    (@emit [document] [$($attrs:tt)*] [$($sig:tt)+] $($arms:tt)+) => {
+        $crate::fn_guard!(@document [$($attrs)*] [$($sig)+] [$($arms)+] $($arms)+);
+    };
+    (
+        @document [$($attrs:tt)*] [$($sig:tt)+] [$($arms:tt)+]
+        $(| $cond:expr => $result:expr),+ , where $($where:tt)*
+    ) => {
+        $crate::fn_guard!(@document [$($attrs)*] [$($sig)+] [$($arms)+] $(| $cond => $result),+);
+    };
+    (
+        @document [$($attrs:tt)*] [$($sig:tt)+] [$($arms:tt)+]
+        $(| $cond:expr => $result:expr),+ $(,)?
+    ) => {
+        $crate::fn_guard!(
+            @emit [plain]
+            [
+                $($attrs)*
+                #[doc = ""]
+                #[doc = "# Cases"]
+                #[doc = ""]
+                $(#[doc = concat!("- `", stringify!($cond), "` => `", stringify!($result), "`")])+
+            ]
+            [$($sig)+]
+            $($arms)+
+        );
+    };
+
+    ($($item:tt)+) => {
+        $crate::fn_guard!(@parse [plain] $($item)+);
+    };
+}
+
+/// 每个条件都可以单独调用的 guard 函数
+///
+/// 与 [`fn_guard!`] 一样定义一个分段函数，但每个分支带有标签；除了函数本身，
+/// 还会生成一个与函数同名的模块，其中每个标签对应一个返回 `bool` 的谓词函数，
+/// 参数与原函数相同。这样就可以在单元测试中分别检查每个条件，而不必构造恰好
+/// 落到某个分支的输入。
+///
+/// `macro_rules!` 无法拼接出 `classify_is_pos` 这样的新标识符，因此谓词的路径写作
+/// `classify::pos`；模块与函数位于不同的命名空间，同名不会冲突。
+///
+/// # 语法
+///
+/// ```text
+/// named_guard!(
+///     pub fn name(arg: Type, ...) -> Ret {
+///         label1: condition1 => result1,
+///         label2: condition2 => result2,
+///         _ => default,  // 可选；省略时没有分支命中会 panic
+///     }
+/// );
+/// ```
+///
+/// # 要求
+///
+/// - 只用于模块级的普通函数，参数写成 `name: Type` 的形式
+/// - 生成的模块通过 `use super::*` 引用外层的类型与函数，谓词的可见性与原函数相同
+///
+/// # Examples
+///
+/// ```
+/// use hgm::named_guard;
+///
+/// named_guard!(
+///     fn classify(x: i32) -> &'static str {
+///         pos: x > 0 => "pos",
+///         neg: x < 0 => "neg",
+///         _ => "zero",
+///     }
+/// );
+///
+/// # fn main() {
+/// assert_eq!(classify(5), "pos");
+/// assert_eq!(classify(0), "zero");
+/// assert!(classify::pos(5));
+/// assert!(!classify::neg(0));
+/// # }
+/// ```
+#[macro_export]
+macro_rules! named_guard {
+    (
+        $(#[$attr:meta])*
+        $vis:vis fn $name:ident ($($arg:ident : $ty:ty),* $(,)?) -> $ret:ty {
+            $($label:ident : $cond:expr => $result:expr),+
+            $(, _ => $default:expr)? $(,)?
+        }
+    ) => {
+        $(#[$attr])*
+        #[track_caller]
+        $vis fn $name($($arg: $ty),*) -> $ret {
+            $crate::guard!($(| $cond => $result,)+ $(| otherwise => $default)?)
+        }
+
+        // 谓词主要供测试使用，未被调用的谓词与未用到的参数不产生警告
+        #[allow(dead_code, unused_variables)]
+        $vis mod $name {
+            #[allow(unused_imports)]
+            use super::*;
+
+            $crate::named_guard!(@predicates ($($arg: $ty),*) $($label: $cond),+);
+        }
+    };
+    // 参数列表作为一个整体转发，避免与分支的重复次数不同而无法展开
+    (@predicates $params:tt $($label:ident : $cond:expr),+) => {
+        $(
+            #[must_use]
+            pub fn $label $params -> bool {
+                $cond
+            }
+        )+
+    };
+}
+
+/// Haskell 风格的 Case 表达式宏
+///
+/// 模拟 Haskell 中的 case 表达式语法，使用 `| pattern => result` 的形式
+/// 进行模式匹配，类似于 Haskell 的：
+///
+/// ```haskell
+/// case x of
+///     pattern1 -> result1
+///     pattern2 | guard -> result2
+///     _        -> defaultResult
+/// ```
+///
+/// # 语法
+///
+/// ```text
+/// case!(expression =>
+///     | pattern1 => result1,
+///     | pattern2 if guard => result2,  // 支持 guard 条件
+///     | _ => default,
+///     where binding = value,           // 可选的 where 子句
+/// )
+/// ```
+///
+/// # 特性
+///
+/// - 使用 `|` 前缀使语法更接近 Haskell
+/// - 支持模式守卫（pattern guards）：`| pattern if condition => result`
+/// - 底层编译为标准 Rust `match` 表达式，保持完整的模式匹配能力
+/// - 分支结果、guard 条件与 where 绑定中都可以使用 `?`，错误从外层函数直接返回
+/// - 与 `match` 一样获取被匹配表达式的所有权：分支可以把按值绑定的 `String` 等载荷移出；
+///   只想借用时匹配 `&value`
+/// - 支持所有 Rust 模式语法（结构体解构、元组、枚举、`1 | 2` 形式的或模式，
+///   嵌套在载荷中的 `Key(Up | Down)`，以及绑定整个或模式的 `e @ (Add(..) | Sub(..))` 等）
+/// - `otherwise` 可作为最后一个兜底分支，等价于 `_`；只在模式位置被识别，
+///   guard 条件中的 `otherwise` 按普通标识符解析
+/// - 与原生 `match` 一样适用默认绑定模式：匹配 `&Option<T>`、`(&a, &b)` 等引用时无需在模式中写 `&`
+/// - 支持 `where` 子句：绑定对所有分支的 guard 与结果可见
+/// - `pattern try => result` 分支在结果 panic 时继续尝试后续分支（需要 `std`）
+/// - `pattern => redispatch value` 分支以新值重新匹配，迭代进行而不增加栈深度
+/// - nightly 上可以使用 `box` 模式（调用方需启用 `#![feature(box_patterns)]`）
+/// - 启用 `strict` 特性后，文本完全相同的重复分支、以及写在裸 `_` 之后的
+///   分支都会导致编译错误
+///
+/// # Examples
+///
+/// ## 基本模式匹配
+///
+/// ```
+/// use hgm::case;
+///
+/// let x = Some(42);
+/// let result = case!(x =>
+///     | None => "nothing",
+///     | Some(0) => "zero",
+///     | Some(_) => "something",
+/// );
+/// assert_eq!(result, "something");
+/// ```
+///
+/// ## 使用模式守卫（Pattern Guards）
+///
+/// ```
+/// use hgm::case;
+///
+/// let pair = (3, 5);
+/// let result = case!(pair =>
+///     | (x, y) if x == y => "equal",
+///     | (x, y) if x > y => "first is larger",
+///     | (_, _) => "second is larger",
+/// );
+/// assert_eq!(result, "second is larger");
+/// ```
+///
+/// ## 或模式与 `otherwise`
+///
+/// 分支前的 `|` 只是分隔符，模式内部仍可以使用 `|` 组合多个模式；
+/// 最后的 `otherwise` 分支兜底其余情况，不需要导入常量 [`otherwise`]。
+///
+/// ```
+/// use hgm::case;
+///
+/// let level = |n: u8| case!(n =>
+///     | 1 | 2 => "low",
+///     | 3 | 4 => "mid",
+///     | otherwise => "high",
+/// );
+/// assert_eq!(level(2), "low");
+/// assert_eq!(level(4), "mid");
+/// assert_eq!(level(9), "high");
+/// ```
+///
+/// ## 无法穷尽的类型与 `otherwise`
+///
+/// 对整数、字符串等无法列举全部取值的类型，最后写一个 `otherwise` 分支兜底即可。
+/// 缺少兜底分支时，编译器的穷尽性检查（E0004）会报错并给出未覆盖的取值，例如
+/// ``patterns `i32::MIN..=0_i32` and `3_i32..=i32::MAX` not covered``。
+/// `case!` 基于 `macro_rules!`，无法在展开时判断分支是否穷尽，因此不会额外生成错误信息，
+/// 该错误直接指向调用处被匹配的表达式。
+///
+/// ```
+/// use hgm::case;
+///
+/// let n = 7;
+/// let size = case!(n =>
+///     | 1 | 2 => "small",
+///     | otherwise => "other",
+/// );
+/// assert_eq!(size, "other");
+/// ```
+///
+/// ```compile_fail,E0004
+/// use hgm::case;
+///
+/// let n = 7;
+/// let size = case!(n =>
+///     | 1 | 2 => "small",
+/// );
+/// ```
+///
+/// ## 枚举匹配
+///
+/// ```
+/// use hgm::case;
+///
+/// enum Color { Red, Green, Blue, Rgb(u8, u8, u8) }
+///
+/// let color = Color::Rgb(255, 128, 0);
+/// let name = case!(color =>
+///     | Color::Red => "red",
+///     | Color::Green => "green",
+///     | Color::Blue => "blue",
+///     | Color::Rgb(r, _, _) if r > 200 => "reddish",
+///     | Color::Rgb(_, _, _) => "custom",
+/// );
+/// assert_eq!(name, "reddish");
+/// ```
+///
+/// ## 通过 `&mut` 原地修改
+///
+/// 被匹配的表达式原样交给 `match`，不会被移动或重新借用，因此可以匹配 `&mut`
+/// 引用并在分支中修改载荷。与原生 `match` 相同，匹配 `&mut` 时绑定默认即为
+/// `&mut`；若要写 `ref mut`，模式本身需要以 `&mut` 开头（或直接匹配值本身）。
+///
+/// ```
+/// use hgm::case;
+///
+/// enum Slot { Count(u32), Name(String), Empty }
+///
+/// let mut slots = [Slot::Count(1), Slot::Name("a".into()), Slot::Empty];
+/// for slot in &mut slots {
+///     case!(slot =>
+///         | Slot::Count(n) => *n += 1,
+///         | Slot::Name(s) => s.push('!'),
+///         | Slot::Empty => {},
+///     );
+/// }
+/// assert!(matches!(&slots[1], Slot::Name(s) if s == "a!"));
+///
+/// case!(&mut slots[0] => | &mut Slot::Count(ref mut n) => *n *= 10, | _ => {});
+/// assert!(matches!(slots[0], Slot::Count(20)));
+/// ```
+///
+/// ## 与标准 match 对比
+///
+/// ```
+/// use hgm::case;
+///
+/// let n = 5;
+///
+/// // 使用 case! 宏 (Haskell 风格)
+/// let a = case!(n =>
+///     | 0 => "zero",
+///     | x if x < 0 => "negative",
+///     | _ => "positive",
+/// );
+///
+/// // 等价的标准 Rust match
+/// let b = match n {
+///     0 => "zero",
+///     x if x < 0 => "negative",
+///     _ => "positive",
+/// };
+///
+/// assert_eq!(a, b);
+/// ```
+///
+/// ## 使用 where 子句
+///
+/// `where` 绑定按书写顺序求值，且都在被匹配的表达式**之前**求值一次，
+/// 因此被匹配的表达式本身也可以使用这些绑定。
+/// 与 [`guard!`] 一样，也可以写成 `where { a = 1; b = 2; }`。
+///
+/// ```
+/// use hgm::case;
+///
+/// let word = "rust";
+/// let score = case!(table.iter().position(|w| *w == word) =>
+///     | Some(i) if i < limit => i + 1,
+///     | Some(_) => limit,
+///     | None => 0,
+///     where table = ["haskell", "ocaml", "rust"], limit = 2,
+/// );
+/// assert_eq!(score, 2);
+/// ```
+///
+/// ## 枚举提示 `#[over Enum]`
+///
+/// 在被匹配的表达式前写 `#[over Enum]`，声明它是对枚举 `Enum` 的匹配。
+/// 这只是一个类型断言：`case!` 在不移动该值的前提下检查它是 `Enum` 或其引用，
+/// 类型不符时报告 ``case!: `#[over Enum]` expects a `Enum` ...``，指出期望的枚举。
+/// `#[over]` 不检查变体是否齐全：`macro_rules!` 无法得知枚举的变体列表，
+/// 遗漏变体时得到的是与不写提示时相同的穷尽性错误（E0004）。
+///
+/// ```
+/// use hgm::case;
+///
+/// enum Light { Red, Yellow, Green }
+///
+/// let light = Light::Yellow;
+/// let wait = case!(#[over Light] &light =>
+///     | Light::Red => 30,
+///     | Light::Yellow => 5,
+///     | Light::Green => 0,
+/// );
+/// assert_eq!(wait, 5);
+/// ```
+///
+/// 遗漏变体时照常由编译器报告 ``patterns `&Light::Green` not covered``：
+///
+/// ```compile_fail,E0004
+/// use hgm::case;
+///
+/// enum Light { Red, Yellow, Green }
+///
+/// let light = Light::Yellow;
+/// let wait = case!(#[over Light] &light =>
+///     | Light::Red => 30,
+///     | Light::Yellow => 5,
+/// );
+/// ```
+///
+/// 被匹配的值不是该枚举时，错误信息点明 `#[over Light]`：
+///
+/// ```compile_fail,E0277
+/// use hgm::case;
+///
+/// enum Light { Red, Yellow, Green }
+///
+/// let wait = case!(#[over Light] Some(3) =>
+///     | Some(_) => 1,
+///     | None => 0,
+/// );
+/// ```
+///
+/// ## 捕获 panic 的分支（需要 `std` 特性）
+///
+/// 写作 `| pattern try => result` 的分支会在 [`std::panic::catch_unwind`] 中求值结果；
+/// 如果结果表达式 panic，该分支视为未命中，继续尝试后续分支。
+///
+/// 注意事项：
+///
+/// - 结果表达式被包装在 [`std::panic::AssertUnwindSafe`] 中，panic 发生前已经产生的
+///   副作用（修改过一半的数据等）不会回滚，调用者需要自行保证其一致性
+/// - panic 仍会经过 panic hook，默认会在标准错误输出中打印信息
+/// - 以 `panic = "abort"` 编译时无法捕获，进程直接终止
+/// - 模式绑定在结果表达式中以引用形式使用，不能从中移出非 `Copy` 的值
+/// - 结果表达式在闭包中求值，其中的 `?` 与 `return` 作用于该闭包而不是外层函数
+/// - 展开为 `if let` 守卫，需要 Rust 1.95 及以上（即 crate 的 `rust-version`）；`try` 分支
+///   不能再附加 `if` 守卫
+///
+#[cfg_attr(feature = "std", doc = "```")]
+#[cfg_attr(not(feature = "std"), doc = "```ignore")]
+/// use hgm::case;
+///
+/// fn parse_strict(s: &str) -> u32 {
+///     s.parse().expect("not a number")
+/// }
+///
+/// let input = Some("12a");
+/// let value = case!(input =>
+///     | Some(s) try => parse_strict(s),
+///     | Some(_) => 0,
+///     | None => u32::MAX,
+/// );
+/// assert_eq!(value, 0);
+/// ```
+///
+/// ## 与严格 lint 共用
+///
+/// 展开结果在 `#![deny(unreachable_patterns, unreachable_code, clippy::all)]` 下不会
+/// 产生额外的警告。模式保留原始的位置信息，因此真正不可达的分支会直接报告在
+/// 用户写下的模式上：
+///
+/// ```compile_fail
+/// #![deny(unreachable_patterns)]
+/// use hgm::case;
+///
+/// let v = Some(3);
+/// let n = case!(v =>
+///     | Some(_) => 1,
+///     | Some(3) => 2, // error: unreachable pattern
+///     | None => 0,
+/// );
+/// ```
+///
+/// ## 重新分派的分支
+///
+/// 写作 `| pattern => redispatch 新值` 的分支以新值替换被匹配的值，再从第一个分支
+/// 重新匹配。出现这种分支时，`case!` 展开为循环中的 `match`，迭代进行，栈深度不随
+/// 重新分派的次数增长。新值的类型必须与被匹配的值相同。
+///
+/// 由于展开在 `loop` 中，其余分支里的 `break` / `continue` 会作用于这个循环，
+/// 而不是外层的循环。
+///
+/// ```
+/// use hgm::case;
+///
+/// let mut steps = 0;
+/// let one = case!(27u64 =>
+///     | 1 => steps,
+///     | n if n.is_multiple_of(2) => redispatch { steps += 1; n / 2 },
+///     | n => redispatch { steps += 1; 3 * n + 1 },
+/// );
+/// assert_eq!(one, 111);
+/// ```
+///
+/// ## `box` 模式（nightly）
+///
+/// 模式按 `pat` 片段原样转发给 `match`，因此在 nightly 上启用 `box_patterns` 后，
+/// 可以直接解构 `Box<T>` 中的值，适合匹配递归的树结构。特性门控作用于展开后的代码，
+/// 需要在调用 `case!` 的 crate 中声明 `#![feature(box_patterns)]`：
+///
+#[cfg_attr(feature = "nightly", doc = "```")]
+#[cfg_attr(not(feature = "nightly"), doc = "```ignore")]
+/// #![feature(box_patterns)]
+/// use hgm::case;
+///
+/// enum Expr {
+///     Num(i64),
+///     Neg(Box<Expr>),
+///     Add(Box<Expr>, Box<Expr>),
+/// }
+///
+/// fn simplify(expr: Expr) -> Expr {
+///     case!(expr =>
+///         | Expr::Neg(box Expr::Neg(box inner)) => simplify(inner),
+///         | Expr::Add(box Expr::Num(0), box rhs) => simplify(rhs),
+///         | other => other,
+///     )
+/// }
+///
+/// let expr = Expr::Add(
+///     Box::new(Expr::Num(0)),
+///     Box::new(Expr::Neg(Box::new(Expr::Neg(Box::new(Expr::Num(7)))))),
+/// );
+/// assert!(matches!(simplify(expr), Expr::Num(7)));
+/// ```
+///
+/// # 严格模式检查
+///
+/// 启用 `strict` 特性时，每个分支的模式（连同 guard 条件）会通过 `stringify!`
+/// 逐一比较，出现完全相同的文本即在编译期报错。这只能发现复制粘贴造成的
+/// 文本重复，无法识别语义上的重叠（例如 `1..=5` 与 `3`）。
+///
+#[cfg_attr(feature = "strict", doc = "```compile_fail,E0080")]
+#[cfg_attr(not(feature = "strict"), doc = "```ignore")]
+/// use hgm::case;
+///
+/// let n = 3;
+/// let name = case!(n =>
+///     | 1 => "one",
+///     | 3 => "three",
+///     | 3 => "three again",
+///     | _ => "other",
+/// );
+/// ```
+///
+/// 同样地，`strict` 特性会拒绝写在裸 `_` 通配分支之后、永远无法命中的分支：
+///
+#[cfg_attr(feature = "strict", doc = "```compile_fail")]
+#[cfg_attr(not(feature = "strict"), doc = "```ignore")]
+/// use hgm::case;
+///
+/// let opt = Some(1);
+/// let name = case!(opt =>
+///     | _ => "x",
+///     | Some(_) => "y",
+/// );
+/// ```
+#[macro_export]
+macro_rules! case {
+    // keys 收集每个分支的 `stringify!` 文本，供 `strict` 特性检查重复模式
+    (@acc $x:expr => $state:tt [$($arms:tt)*] [$($keys:expr,)*]) => {
+        $crate::case!(@emit $x => $state [$($arms)*] [$($keys,)*])
+    };
+    // where 绑定先于被匹配表达式求值
+    (@acc $x:expr => $state:tt [$($arms:tt)*] [$($keys:expr,)*] where $($bindings:tt)+) => {{
+        $crate::__where_lets!($($bindings)+);
+        $crate::case!(@emit $x => $state [$($arms)*] [$($keys,)*])
+    }};
+    // 没有 redispatch 分支时直接展开为 match
+    (@emit $x:expr => [$scrutinee:ident] [$($arms:tt)*] [$($keys:expr,)*]) => {
+        $crate::__case_match!($x => [$($arms)*] [$($keys,)*])
+    };
+    // 有 redispatch 分支时，被匹配的值存入可变绑定，在循环中重新匹配
+    (@emit $x:expr => [$scrutinee:ident loop] [$($arms:tt)*] [$($keys:expr,)*]) => {{
+        let mut $scrutinee = $x;
+        loop {
+            break $crate::__case_match!($scrutinee => [$($arms)*] [$($keys,)*]);
+        }
+    }};
+    // `name!()` 是 case_arms! 定义的分支片段：交给片段宏展开，它把自己的分支放回剩余 token 之前
+    // 再回到这里继续累积；片段末尾补上的逗号由下一条规则吞掉
+    (@acc $x:expr => $state:tt [$($arms:tt)*] [$($keys:expr,)*] $fragment:ident ! () $($rest:tt)*) => {
+        $fragment!(@hgm_case_arms [$x => $state [$($arms)*] [$($keys,)*]] $($rest)*)
+    };
+    (@acc $x:expr => $state:tt [$($arms:tt)*] [$($keys:expr,)*] , $($rest:tt)*) => {
+        $crate::case!(@acc $x => $state [$($arms)*] [$($keys,)*] $($rest)*)
+    };
+    // 裸 `_` 之后仍有分支：keys 只在 `strict` 特性下展开，此时报告不可达分支
+    (@acc $x:expr => $state:tt [$($arms:tt)*] [$($keys:expr,)*] | _ => $result:expr, | $($rest:tt)+) => {
+        $crate::case!(
+            @acc $x => $state [$($arms)* _ => $result,]
+            [$($keys,)* compile_error!("case!: arms after a bare `_` wildcard are unreachable"),]
+            | $($rest)+
+        )
+    };
+    // `otherwise` 与 guard! 一致，作为兜底分支展开为 `_`；同样必须写在最后
+    (@acc $x:expr => $state:tt [$($arms:tt)*] [$($keys:expr,)*] | otherwise => $result:expr, | $($rest:tt)+) => {
+        $crate::case!(@acc $x => $state [$($arms)*] [$($keys,)*] | _ => $result, | $($rest)+)
+    };
+    (@acc $x:expr => $state:tt [$($arms:tt)*] [$($keys:expr,)*] | otherwise => $result:expr $(,$($rest:tt)*)?) => {
+        $crate::case!(@acc $x => $state [$($arms)* _ => $result,] [$($keys,)* $crate::__case_key!(_),] $($($rest)*)?)
+    };
+    // `=> redispatch 新值`：以新值替换被匹配的值并重新匹配
+    (@acc $x:expr => [$scrutinee:ident $($loop:ident)?] [$($arms:tt)*] [$($keys:expr,)*] | $pat:pat if $guard:expr => redispatch $next:expr $(,$($rest:tt)*)?) => {
+        $crate::case!(
+            @acc $x => [$scrutinee loop] [$($arms)* $pat if $guard => { $scrutinee = $next; continue },]
+            [$($keys,)* $crate::__case_key!($pat if $guard),] $($($rest)*)?
+        )
+    };
+    (@acc $x:expr => [$scrutinee:ident $($loop:ident)?] [$($arms:tt)*] [$($keys:expr,)*] | $pat:pat => redispatch $next:expr $(,$($rest:tt)*)?) => {
+        $crate::case!(
+            @acc $x => [$scrutinee loop] [$($arms)* $pat => { $scrutinee = $next; continue },]
+            [$($keys,)* $crate::__case_key!($pat),] $($($rest)*)?
+        )
+    };
+    (@acc $x:expr => $state:tt [$($arms:tt)*] [$($keys:expr,)*] | $pat:pat if $guard:expr => $result:expr $(,$($rest:tt)*)?) => {
+        $crate::case!(@acc $x => $state [$($arms)* $pat if $guard => $result,] [$($keys,)* $crate::__case_key!($pat if $guard),] $($($rest)*)?)
+    };
+    (@acc $x:expr => $state:tt [$($arms:tt)*] [$($keys:expr,)*] | $pat:pat => $result:expr $(,$($rest:tt)*)?) => {
+        $crate::case!(@acc $x => $state [$($arms)* $pat => $result,] [$($keys,)* $crate::__case_key!($pat),] $($($rest)*)?)
+    };
+    // 以上规则都无法解析时，逐个 token 收集模式，寻找 `pat try => result` 形式的分支
+    (@acc $x:expr => $state:tt [$($arms:tt)*] [$($keys:expr,)*] | $($rest:tt)+) => {
+        $crate::case!(@try $x => $state [$($arms)*] [$($keys,)*] [] $($rest)+)
+    };
+    (@try $x:expr => $state:tt [$($arms:tt)*] [$($keys:expr,)*] [$($pat:tt)+] try => $result:expr $(,$($rest:tt)*)?) => {
+        $crate::case!(
+            @acc $x => $state [
+                $($arms)*
+                $($pat)+ if let ::core::result::Result::Ok(__hgm_value) = $crate::__case_catch!($result) => __hgm_value,
+            ]
+            [$($keys,)* $crate::__case_key!($($pat)+ try),]
+            $($($rest)*)?
+        )
+    };
+    (@try $x:expr => $state:tt [$($arms:tt)*] [$($keys:expr,)*] [$($pat:tt)*] $next:tt $($rest:tt)*) => {
+        $crate::case!(@try $x => $state [$($arms)*] [$($keys,)*] [$($pat)* $next] $($rest)*)
+    };
+    (@try $x:expr => $state:tt [$($arms:tt)*] [$($keys:expr,)*] [$($pat:tt)*]) => {
+        compile_error!(concat!("case!: expected `=>` or `try =>` after `", stringify!($($pat)*), "`"))
+    };
+    // #[over Enum]：永不命中的首个分支以引用方式绑定被匹配的值并检查其类型，
+    // 不移动该值，也不影响穷尽性检查
+    (#[over $enum:path] $x:expr => $($rest:tt)+) => {
+        $crate::case!(
+            @acc $x => [scrutinee] [ref __hgm_over if $crate::__private::over::<$enum, _>(__hgm_over) => unreachable!(),]
+            [] $($rest)+
+        )
+    };
+    ($x:expr=>$($rest:tt)+) => {
+        $crate::case!(@acc $x => [scrutinee] [] [] $($rest)+)
+    };
+}
+
+/// 定义可以在多个 [`case!`] 中复用的分支片段
+///
+/// `case_arms! { name { arms } }` 生成一个名为 `name` 的宏，把它写成 `name!()`
+/// 放在 `case!` 的分支列表中，效果等同于在该位置直接写出这些分支。
+/// 大型分派表可以按模块拆成若干片段，再在一处组合：
+/// `case!(x => arms_a!() arms_b!() | _ => default)`。
+///
+/// - 片段内的分支写法与 `case!` 完全相同（guard、`try`、不带 `_` 的 `otherwise` 等）
+/// - 片段可以与普通分支任意交错，按书写顺序参与匹配
+/// - 生成的是普通的 `macro_rules!` 宏，遵循文本作用域：需要在使用之前定义；
+///   写作 `case_arms! { pub(crate) name { ... } }` 时会额外生成 `pub(crate) use name;`，
+///   可以通过模块路径在 crate 内其他位置导入
+///
+/// # Examples
+///
+/// ```
+/// use hgm::{case, case_arms};
+///
+/// case_arms! {
+///     digits {
+///         | '0'..='9' => "digit",
+///     }
+/// }
+/// case_arms! {
+///     brackets {
+///         | '(' | ')' => "paren",
+///         | '[' | ']' | '{' | '}' => "bracket",
+///     }
+/// }
+///
+/// let kind = |c: char| case!(c =>
+///     | ' ' => "space",
+///     digits!()
+///     brackets!()
+///     | c if c.is_alphabetic() => "letter",
+///     | _ => "other",
+/// );
+///
+/// assert_eq!(kind('7'), "digit");
+/// assert_eq!(kind('{'), "bracket");
+/// assert_eq!(kind('x'), "letter");
+/// assert_eq!(kind(' '), "space");
+/// assert_eq!(kind('#'), "other");
+/// ```
+#[macro_export]
+macro_rules! case_arms {
+    ($name:ident { $($arms:tt)* }) => {
+        $crate::case_arms!(@define ($) $name { $($arms)* });
+    };
+    ($vis:vis $name:ident { $($arms:tt)* }) => {
+        $crate::case_arms!(@define ($) $name { $($arms)* });
+        #[allow(unused_imports)]
+        $vis use $name;
+    };
+    // 生成的宏需要自己的元变量，`$` 以 token 的形式从外层传入
+    (@define ($d:tt) $name:ident { $($arms:tt)* }) => {
+        #[allow(unused_macros)]
+        macro_rules! $name {
+            (@hgm_case_arms [$d($d head:tt)*] $d($d rest:tt)*) => {
+                $crate::case!(@acc $d($d head)* $($arms)* , $d($d rest)*)
+            };
+        }
+    };
+}
+
+#[doc(hidden)]
+#[cfg(feature = "std")]
+#[macro_export]
+macro_rules! __case_catch {
+    ($result:expr) => {
+        ::std::panic::catch_unwind(::std::panic::AssertUnwindSafe(|| $result))
+    };
+}
+
+#[doc(hidden)]
+#[cfg(not(feature = "std"))]
+#[macro_export]
+macro_rules! __case_catch {
+    ($result:expr) => {
+        compile_error!("case!: `try` arms require the `std` feature")
+    };
+}
+
+#[doc(hidden)]
+#[cfg(feature = "std")]
+#[macro_export]
+macro_rules! __fn_guard_verify {
+    ($name:ident, $samples:expr, $call:expr) => {
+        let gaps = $crate::__private::verify_samples(&$samples, $call);
+        assert!(
+            gaps.is_empty(),
+            "fn_guard!: `{}` panicked for {} sample(s): {}",
+            stringify!($name),
+            gaps.len(),
+            gaps.join(", "),
+        );
+    };
+}
+
+#[doc(hidden)]
+#[cfg(not(feature = "std"))]
+#[macro_export]
+macro_rules! __fn_guard_verify {
+    ($name:ident, $samples:expr, $call:expr) => {
+        compile_error!("fn_guard!: #[verify_over] requires the `std` feature")
+    };
+}
+
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __case_key {
+    ($($arm:tt)+) => {
+        concat!("duplicate case! pattern: `", stringify!($($arm)+), "`")
+    };
+}
+
+#[doc(hidden)]
+#[cfg(not(feature = "strict"))]
+#[macro_export]
+macro_rules! __case_match {
+    ($x:expr => [$($arms:tt)*] [$($keys:expr,)*]) => {
+        match $x {$($arms)*}
+    };
+}
+
+#[doc(hidden)]
+#[cfg(feature = "strict")]
+#[macro_export]
+macro_rules! __case_match {
+    ($x:expr => [$($arms:tt)*] [$($keys:expr,)*]) => {
+        match $x {
+            // 永不命中的首个分支，仅用于在编译期执行重复模式检查；
+            // 用常量项而不是 `const { }` 块，使 `cargo check` 同样报告错误
+            _ if {
+                const _: () = $crate::__private::assert_distinct_arms(&[$($keys),*]);
+                false
+            } => unreachable!(),
+            $($arms)*
+        }
+    };
+}
+
+/// 先计算 where 绑定、再执行 case 匹配
+///
+/// 等价于在 [`case!`] 外层写一个块先 `let` 出各个辅助值，省去一层嵌套。
+/// 绑定按书写顺序求值，后面的绑定可以使用前面的绑定；
+/// 被匹配的表达式、模式 guard 与分支结果都能使用这些绑定。
+///
+/// # 语法
+///
+/// ```text
+/// compute_then_case!(where a = value1, b = value2;
+///     scrutinee =>
+///     | pattern if guard => result,
+///     | pattern => result,
+/// )
+/// ```
+///
+/// # Examples
+///
+/// ```
+/// use hgm::compute_then_case;
+///
+/// let scores = [72, 95, 64];
+/// let summary = compute_then_case!(
+///     where best = *scores.iter().max().unwrap(), pass = 70;
+///     scores.iter().filter(|&&s| s < pass).count() =>
+///     | 0 => format!("all passed, best {best}"),
+///     | n if best >= 90 => format!("{n} failed, best {best}"),
+///     | n => format!("{n} failed"),
+/// );
+/// assert_eq!(summary, "1 failed, best 95");
+/// ```
+#[macro_export]
+macro_rules! compute_then_case {
+    (where $($binding:ident = $val:expr),+ $(,)?; $x:expr => $($arms:tt)+) => {{
+        $(let $binding = $val;)+
+        $crate::case!($x => $($arms)+)
+    }};
+}
+
+/// 返回 `Cow<str>` 的 case 表达式（需要 `alloc` 特性，`std` 包含它）
+///
+/// 分支与 [`case!`] 相同，每个分支的结果经 `Cow::from` 转换：`&str` 结果成为
+/// `Cow::Borrowed`，`String` 结果成为 `Cow::Owned`。借用的分支不会分配内存，
+/// 避免为了统一类型而把所有分支都转换为 `String`。
+///
+/// # Examples
+///
+#[cfg_attr(feature = "alloc", doc = "```")]
+#[cfg_attr(not(feature = "alloc"), doc = "```ignore")]
+/// use std::borrow::Cow;
+/// use hgm::case_cow;
+///
+/// fn describe(code: u16) -> Cow<'static, str> {
+///     case_cow!(code =>
+///         | 200 => "OK",
+///         | 404 => "Not Found",
+///         | c if c >= 500 => format!("Server Error ({c})"),
+///         | otherwise => format!("Status {code}"),
+///     )
+/// }
+///
+/// assert!(matches!(describe(404), Cow::Borrowed("Not Found")));
+/// assert_eq!(describe(503), "Server Error (503)");
+/// ```
+#[cfg(feature = "alloc")]
+#[macro_export]
+macro_rules! case_cow {
+    ($x:expr => | $($arms:tt)+) => {
+        $crate::case_cow!(@arms $x => [] | $($arms)+)
+    };
+    (@arms $x:expr => [$($done:tt)*]) => {
+        $crate::case!($x => $($done)*)
+    };
+    (@arms $x:expr => [$($done:tt)*] where $($bindings:tt)+) => {
+        $crate::case!($x => $($done)* where $($bindings)+)
+    };
+    // `otherwise` 原样交给 case!，由它识别为兜底分支
+    (@arms $x:expr => [$($done:tt)*] | otherwise => $result:expr $(, $($rest:tt)*)?) => {
+        $crate::case_cow!(
+            @arms $x => [$($done)* | otherwise => $crate::__private::Cow::<str>::from($result),]
+            $($($rest)*)?
+        )
+    };
+    (@arms $x:expr => [$($done:tt)*] | $pat:pat $(if $guard:expr)? => $result:expr $(, $($rest:tt)*)?) => {
+        $crate::case_cow!(
+            @arms $x => [$($done)* | $pat $(if $guard)? => $crate::__private::Cow::<str>::from($result),]
+            $($($rest)*)?
+        )
+    };
+}
+
+/// 带分支标签的 case 表达式
+///
+/// 每个分支在模式（及 guard）之后写一个字符串标签 `# "label"`
+/// （Rust 2024 保留了 `#"` 这一词法形式，因此 `#` 与字符串之间需要空格），
+/// 结果为 `(&'static str, T)`：命中分支的标签与该分支的值。
+/// 适合按命中分支统计指标，而无需手动维护分支编号。
+///
+/// # 语法
+///
+/// ```text
+/// case_labeled!(expr =>
+///     | pattern # "label" => result,
+///     | pattern if guard # "label" => result,
+///     where name = value
+/// )
+/// ```
+///
+/// # Examples
+///
+/// ```
+/// use hgm::case_labeled;
+///
+/// let lookup = |id: u32| if id < 10 { Some(id * 2) } else { None };
+///
+/// let (label, value) = case_labeled!(lookup(4) =>
+///     | Some(v) if v > 5 # "large" => v,
+///     | Some(v) # "found" => v,
+///     | None # "missing" => 0,
+/// );
+/// assert_eq!((label, value), ("large", 8));
+/// ```
+#[macro_export]
+macro_rules! case_labeled {
+    // 分支逐个改写为 `| pattern => (label, result)` 后交给 case!
+    (@acc $x:expr => [$($arms:tt)*] where $($bindings:tt)+) => {
+        $crate::case!($x => $($arms)* where $($bindings)+)
+    };
+    (@acc $x:expr => [$($arms:tt)*]) => {
+        $crate::case!($x => $($arms)*)
+    };
+    (@acc $x:expr => [$($arms:tt)*] | $($rest:tt)+) => {
+        $crate::case_labeled!(@label $x => [$($arms)*] [] $($rest)+)
+    };
+    // 模式后不能直接跟 `#`，因此逐个 token 收集模式直到遇到标签
+    (
+        @label $x:expr => [$($arms:tt)*] [$($pat:tt)+]
+        # $label:literal => $result:expr $(, $($rest:tt)*)?
+    ) => {
+        $crate::case_labeled!(@acc $x => [$($arms)* | $($pat)+ => ($label, $result),] $($($rest)*)?)
+    };
+    (@label $x:expr => [$($arms:tt)*] [$($pat:tt)*] => $($rest:tt)*) => {
+        compile_error!(concat!("case_labeled!: missing `# \"label\"` after `", stringify!($($pat)*), "`"))
+    };
+    (@label $x:expr => [$($arms:tt)*] [$($pat:tt)*] $next:tt $($rest:tt)*) => {
+        $crate::case_labeled!(@label $x => [$($arms)*] [$($pat)* $next] $($rest)*)
+    };
+    ($x:expr => $($rest:tt)+) => {
+        $crate::case_labeled!(@acc $x => [] $($rest)+)
+    };
+}
+
+/// 返回 `Result` 的 case 表达式宏
+///
+/// 命中的分支结果包装为 `Ok(...)`。没有分支命中时，被匹配的值本身作为
+/// `Err(...)` 返回，便于在错误信息中报告是哪个值不被接受；写了 `err = ...`
+/// 时改为返回给定的错误。
+///
+/// 与 [`case!`] 一样，被匹配的表达式会被移动；没有分支命中时其所有权转移到
+/// `Err` 中。只想借用时请匹配 `&value`，此时错误中是该引用。
+///
+/// # 语法
+///
+/// ```text
+/// case_result!(expression =>
+///     | pattern1 => value1,
+///     | pattern2 if guard => value2,
+/// )
+///
+/// case_result!(expression =>
+///     | pattern => value,
+///     err = error_value,
+/// )
+/// ```
+///
+/// 分支不能再写 `_` 或 `otherwise`，否则未匹配的情况永远不会出现。
+///
+/// # Examples
+///
+/// ```
+/// use hgm::case_result;
+///
+/// #[derive(Debug, PartialEq)]
+/// enum Token { Num(i64), Ident(String), Comma }
+///
+/// let number = |token: Token| case_result!(token =>
+///     | Token::Num(n) => n,
+/// );
+/// assert_eq!(number(Token::Num(3)), Ok(3));
+/// assert_eq!(number(Token::Comma), Err(Token::Comma));
+///
+/// fn ident(token: &Token) -> Result<&str, &'static str> {
+///     case_result!(token =>
+///         | Token::Ident(name) if !name.is_empty() => name.as_str(),
+///         err = "expected identifier",
+///     )
+/// }
+/// assert_eq!(ident(&Token::Ident("x".into())), Ok("x"));
+/// assert_eq!(ident(&Token::Num(1)), Err("expected identifier"));
+/// ```
+#[macro_export]
+macro_rules! case_result {
+    ($x:expr => $(| $pat:pat $(if $guard:expr)? => $result:expr),+ , err = $err:expr $(,)?) => {
+        $crate::case!($x =>
+            $(| $pat $(if $guard)? => ::core::result::Result::Ok($result),)+
+            | _ => ::core::result::Result::Err($err),
+        )
+    };
+    ($x:expr => $(| $pat:pat $(if $guard:expr)? => $result:expr),+ $(,)?) => {
+        $crate::case!($x =>
+            $(| $pat $(if $guard)? => ::core::result::Result::Ok($result),)+
+            | unmatched => ::core::result::Result::Err(unmatched),
+        )
+    };
+}
+
+/// 各分支返回不同迭代器类型的 case 表达式宏
+///
+/// 每个分支的结果包装到嵌套的 [`Either`] 中：第一个分支为 `Left`，第二个为
+/// `Right(Left(..))`，依此类推，最后一个分支只有外层的 `Right`。整个表达式因此是
+/// 同一个迭代器类型，各分支的 `Item` 必须相同。
+///
+/// # 语法
+///
+/// ```text
+/// case_iter!(expression =>
+///     | pattern1 => iterator1,
+///     | pattern2 if guard => iterator2,
+///     | _ => iterator3,
+/// )
+/// ```
+///
+/// # Examples
+///
+/// ```
+/// use hgm::case_iter;
+///
+/// enum Mode { Evens, Scaled(u32), Reversed }
+///
+/// let pipeline = |mode: Mode| case_iter!(mode =>
+///     | Mode::Evens => (1..=6).filter(|n| n % 2 == 0),
+///     | Mode::Scaled(k) => (1..=3).map(move |n| n * k),
+///     | Mode::Reversed => (1..=3).rev(),
+/// );
+/// assert_eq!(pipeline(Mode::Evens).collect::<Vec<_>>(), [2, 4, 6]);
+/// assert_eq!(pipeline(Mode::Scaled(10)).collect::<Vec<_>>(), [10, 20, 30]);
+/// assert_eq!(pipeline(Mode::Reversed).sum::<u32>(), 6);
+/// ```
+#[macro_export]
+macro_rules! case_iter {
+    (@acc $x:expr => [$($arms:tt)*] [$($depth:ident)*] | otherwise => $($rest:tt)+) => {
+        $crate::case_iter!(@acc $x => [$($arms)*] [$($depth)*] | _ => $($rest)+)
+    };
+    // 还有后续分支：本分支包装为 depth 层 Right 之内的 Left
+    (
+        @acc $x:expr => [$($arms:tt)*] [$($depth:ident)*]
+        | $pat:pat $(if $guard:expr)? => $result:expr, | $($rest:tt)+
+    ) => {
+        $crate::case_iter!(
+            @acc $x => [$($arms)* | $pat $(if $guard)? => $crate::__case_iter_wrap!([$($depth)*] $crate::Either::Left($result)),]
+            [$($depth)* Right] | $($rest)+
+        )
+    };
+    // 最后一个分支只包装 depth 层 Right
+    (
+        @acc $x:expr => [$($arms:tt)*] [$($depth:ident)*]
+        | $pat:pat $(if $guard:expr)? => $result:expr $(, $($where:tt)*)?
+    ) => {
+        $crate::case!($x =>
+            $($arms)* | $pat $(if $guard)? => $crate::__case_iter_wrap!([$($depth)*] $result),
+            $($($where)*)?
+        )
+    };
+    ($x:expr => $($rest:tt)+) => {
+        $crate::case_iter!(@acc $x => [] [] $($rest)+)
+    };
+}
+
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __case_iter_wrap {
+    ([] $value:expr) => {
+        $value
+    };
+    ([Right $($depth:ident)*] $value:expr) => {
+        $crate::Either::Right($crate::__case_iter_wrap!([$($depth)*] $value))
+    };
+}
+
+/// 对多个互不相关的值分别执行 case 匹配
+///
+/// 每个 `scrutinee => [...]` 都是一张独立的 [`case!`] 分支表，
+/// 结果按书写顺序组成元组返回，避免为无关的匹配层层嵌套。
+///
+/// # 语法
+///
+/// ```text
+/// case_all!(
+///     expr1 => [| pattern => result, ...],
+///     expr2 => [| pattern => result, ...],
+/// )
+/// ```
+///
+/// # Examples
+///
+/// ```
+/// use hgm::case_all;
+///
+/// let level: Option<u8> = None;
+/// let name = "";
+/// let (level, name) = case_all!(
+///     level => [| Some(n) => n, | None => 3],
+///     name => [| "" => "anonymous", | s => s],
+/// );
+/// assert_eq!((level, name), (3, "anonymous"));
+/// ```
+#[macro_export]
+macro_rules! case_all {
+    ($($x:expr => [$($arms:tt)+]),+ $(,)?) => {
+        ($($crate::case!($x => $($arms)+),)+)
+    };
+}
+
+/// 状态机转移表宏
+///
+/// 给定当前状态与事件，按 `(状态, 事件) => 新状态` 的表格查找转移结果，
+/// 底层展开为对元组 `(state, event)` 的 [`case!`]。
+/// 最后一行通常写 `_ => ...` 作为未定义转移时的回退。
+///
+/// # 语法
+///
+/// ```text
+/// transitions!(state, event;
+///     (State1, Event1) => State2,
+///     (State2, Event2) | (State2, Event3) => State1,
+///     _ => fallback,
+/// )
+/// ```
+///
+/// 注意：与 `match` 一样，未加路径的大写名字只有在导入后才会被当作枚举变体，
+/// 否则会被当作绑定变量，请使用 `State::Idle` 或先 `use State::*;`。
+///
+/// # Examples
+///
+/// ```
+/// use hgm::transitions;
+///
+/// #[derive(Debug, Clone, Copy, PartialEq)]
+/// enum Door { Open, Closed, Locked }
+/// #[derive(Debug, Clone, Copy, PartialEq)]
+/// enum Action { Push, Pull, Lock, Unlock }
+///
+/// fn step(door: Door, action: Action) -> Result<Door, (Door, Action)> {
+///     use Action::*;
+///     use Door::*;
+///     transitions!(door, action;
+///         (Closed, Pull) => Ok(Open),
+///         (Open, Push) => Ok(Closed),
+///         (Closed, Lock) => Ok(Locked),
+///         (Locked, Unlock) => Ok(Closed),
+///         _ => Err((door, action)),
+///     )
+/// }
+///
+/// assert_eq!(step(Door::Closed, Action::Pull), Ok(Door::Open));
+/// assert!(step(Door::Locked, Action::Pull).is_err());
+/// ```
+#[macro_export]
+macro_rules! transitions {
+    ($state:expr, $event:expr; $($from:pat => $to:expr),+ $(,)?) => {
+        $crate::case!(($state, $event) => $(| $from => $to),+)
+    };
+}
+
+/// 面向字节 / 字符词法分析的 case 表达式
+///
+/// 专供词法分析器热循环使用：被匹配的值必须是单个 `u8` 或 `char`
+/// （否则编译失败），分支只接受模式，不接受 guard，展开为一个不含任何额外分支的
+/// 普通 `match`，因此与手写的 `match` 完全相同，保留编译器将其降低为跳转表的机会。
+/// `benches/lex_case.rs`（criterion，`cargo bench --bench lex_case`）对比了它与手写 `match` 的性能。
+///
+/// # 语法
+///
+/// ```text
+/// lex_case!(byte =>
+///     | pattern => result,
+///     | _ => fallback,
+/// )
+/// ```
+///
+/// # 使用建议
+///
+/// - 用字面量与区间（`b'0'..=b'9'`）描述字符类，避免 guard 与函数调用判断
+/// - 分支覆盖的取值越稠密、越连续，越容易被降低为跳转表
+/// - 分支结果尽量是简单的值（例如 token 种类的枚举），把具体处理放到 `match` 之外
+/// - 最后用 `_` 兜底；`otherwise` 在这里会被当作普通绑定
+///
+/// # Examples
+///
+/// ```
+/// use hgm::lex_case;
+///
+/// #[derive(Debug, PartialEq)]
+/// enum Class { Digit, Alpha, Space, Punct, Other }
+///
+/// fn classify(byte: u8) -> Class {
+///     lex_case!(byte =>
+///         | b'0'..=b'9' => Class::Digit,
+///         | b'a'..=b'z' | b'A'..=b'Z' | b'_' => Class::Alpha,
+///         | b' ' | b'\t' | b'\n' | b'\r' => Class::Space,
+///         | b'!'..=b'/' | b':'..=b'@' => Class::Punct,
+///         | _ => Class::Other,
+///     )
+/// }
+///
+/// let classes: Vec<Class> = b"x1 ?".iter().map(|&b| classify(b)).collect();
+/// assert_eq!(classes, [Class::Alpha, Class::Digit, Class::Space, Class::Punct]);
+/// ```
+///
+/// 被匹配的值不是 `u8` / `char` 时编译失败：
+///
+/// ```compile_fail
+/// use hgm::lex_case;
+///
+/// let word = "abc";
+/// lex_case!(word => | "abc" => 1, | _ => 0);
+/// ```
+#[macro_export]
+macro_rules! lex_case {
+    ($x:expr => $(| $pat:pat => $result:expr),+ $(,)?) => {
+        match $crate::__private::lex_unit($x) {
+            $($pat => $result,)+
+        }
+    };
+}
+
+/// 按长度分派的 case 表达式
+///
+/// `case_len!(slice => ...)` 等价于对 `slice.len()` 执行 [`case!`]，而 `slice` 本身
+/// 在分支的 guard 与结果中仍然可用，无需先写 `let len = slice.len();`。
+/// 适合解析器中“先看长度、再按位置取元素”的写法。被匹配的可以是任何带 `len()`
+/// 方法的值（切片、`Vec`、`str` 等）；分支、`otherwise` 与 `where` 子句的写法与 `case!` 相同。
+///
+/// 需要同时解构元素时，直接对切片使用 `case!` 与切片模式（`[a, b, ..]`）通常更合适。
+///
+/// # 语法
+///
+/// ```text
+/// case_len!(slice =>
+///     | 0 => empty,
+///     | 1 => one(slice[0]),
+///     | n if n > limit => too_long(n),
+///     | _ => many(slice),
+/// )
+/// ```
+///
+/// # Examples
+///
+/// ```
+/// use hgm::case_len;
+///
+/// fn describe(args: &[&str]) -> String {
+///     case_len!(args =>
+///         | 0 => "no arguments".to_string(),
+///         | 1 => format!("just {}", args[0]),
+///         | 2 => format!("{} and {}", args[0], args[1]),
+///         | n => format!("{} and {} more", args[0], n - 1),
+///     )
+/// }
+///
+/// assert_eq!(describe(&[]), "no arguments");
+/// assert_eq!(describe(&["a"]), "just a");
+/// assert_eq!(describe(&["a", "b"]), "a and b");
+/// assert_eq!(describe(&["a", "b", "c"]), "a and 2 more");
+/// ```
+#[macro_export]
+macro_rules! case_len {
+    ($slice:expr => $($arms:tt)+) => {
+        $crate::case!($slice.len() => $($arms)+)
+    };
+}
+
+/// 按枚举判别值（discriminant）的区间分派的 case 表达式
+///
+/// Rust 不能对枚举变体写区间模式；`case_discriminant!(e => ...)` 把被匹配的值转换为
+/// `isize`（展开为 `case!((e) as isize => ...)`），分支因此可以写 `0..=3` 这样的整数区间。
+/// 适合用显式判别值描述协议编号的 C 风格枚举。分支、guard、`otherwise` 与 `where`
+/// 的写法与 [`case!`] 相同。
+///
+/// 要求：被匹配的值是无字段的枚举，判别值显式给出（通常带 `#[repr(...)]`），
+/// 按值转换要求能移出或复制该值；对引用写 `*e`，此时枚举需要实现 `Copy`。
+///
+/// # Examples
+///
+/// ```
+/// use hgm::case_discriminant;
+///
+/// #[derive(Clone, Copy)]
+/// #[repr(u8)]
+/// enum Opcode {
+///     Nop = 0,
+///     Load = 1,
+///     Store = 2,
+///     Jump = 16,
+///     Call = 17,
+///     Halt = 255,
+/// }
+///
+/// fn group(op: &Opcode) -> &'static str {
+///     case_discriminant!(*op =>
+///         | 0 => "control",
+///         | 1..=15 => "memory",
+///         | 16..=31 => "branch",
+///         | _ => "system",
+///     )
+/// }
+///
+/// assert_eq!(group(&Opcode::Store), "memory");
+/// assert_eq!(group(&Opcode::Call), "branch");
+/// assert_eq!(group(&Opcode::Halt), "system");
+/// # let _ = [Opcode::Nop, Opcode::Load, Opcode::Jump];
+/// ```
+#[macro_export]
+macro_rules! case_discriminant {
+    ($x:expr => $($arms:tt)+) => {
+        $crate::case!(($x) as isize => $($arms)+)
+    };
+}
+
+/// 只判断是否有分支命中的 case 表达式
+///
+/// 与标准库的 [`matches!`] 相同，返回 `bool`，但使用本 crate 以 `|` 开头的分支写法，
+/// 可以列出多个分支，每个分支都可以带自己的 guard 条件。任意一个分支命中即为 `true`。
+///
+/// # 语法
+///
+/// ```text
+/// case_matches!(expression =>
+///     | pattern1,
+///     | pattern2 if guard,
+/// )
+/// ```
+///
+/// # Examples
+///
+/// ```
+/// use hgm::case_matches;
+///
+/// enum Token { Num(i64), Ident(String), Op(char) }
+///
+/// let is_operand = |token: &Token| case_matches!(token =>
+///     | Token::Num(_),
+///     | Token::Ident(name) if !name.is_empty(),
+/// );
+///
+/// assert!(is_operand(&Token::Num(4)));
+/// assert!(is_operand(&Token::Ident("x".to_string())));
+/// assert!(!is_operand(&Token::Ident(String::new())));
+/// assert!(!is_operand(&Token::Op('+')));
+/// ```
+#[macro_export]
+macro_rules! case_matches {
+    ($x:expr => $(| $pat:pat $(if $guard:expr)?),+ $(,)?) => {
+        match $x {
+            $($pat $(if $guard)? => true,)+
+            #[allow(unreachable_patterns)]
+            _ => false,
+        }
+    };
+}
+
+/// 对 [`Ordering`](core::cmp::Ordering) 三路比较结果的 case 表达式
+///
+/// 分支中直接写 `Less` / `Equal` / `Greater`，省略 `Ordering::` 前缀；一个分支可以用 `|`
+/// 列出多个变体。展开为对 `Ordering` 的 `match`，因此三个变体都必须出现，
+/// 漏掉任何一个都会得到编译器的非穷尽匹配错误。
+///
+/// # 语法
+///
+/// ```text
+/// cmp_case!(a.cmp(&b) =>
+///     | Less => result1,
+///     | Equal => result2,
+///     | Greater => result3,
+/// )
+/// ```
+///
+/// # Examples
+///
+/// ```
+/// use hgm::cmp_case;
+///
+/// fn binary_search(items: &[i32], target: i32) -> Option<usize> {
+///     let (mut lo, mut hi) = (0, items.len());
+///     while lo < hi {
+///         let mid = (lo + hi) / 2;
+///         cmp_case!(items[mid].cmp(&target) =>
+///             | Less => lo = mid + 1,
+///             | Greater => hi = mid,
+///             | Equal => return Some(mid),
+///         );
+///     }
+///     None
+/// }
+///
+/// assert_eq!(binary_search(&[1, 3, 5, 7], 5), Some(2));
+/// assert_eq!(binary_search(&[1, 3, 5, 7], 4), None);
+/// ```
+///
+/// 缺少变体时编译失败：
+///
+/// ```compile_fail
+/// use hgm::cmp_case;
+///
+/// let sign = cmp_case!(3.cmp(&4) => | Less => -1, | Greater => 1);
+/// ```
+#[macro_export]
+macro_rules! cmp_case {
+    ($x:expr => $(| $($variant:ident)|+ => $result:expr),+ $(,)?) => {
+        match $x {
+            $($(::core::cmp::Ordering::$variant)|+ => $result,)+
+        }
+    };
+}
+
+/// 把枚举的变体导入当前作用域
+///
+/// 展开为 `use Enum::*;`（可以一次列出多个枚举），之后 [`case!`] 的分支可以直接写
+/// `| Red => ...`，省略 `Color::` 前缀。导入只在调用所在的块或模块内有效。
+///
+/// 注意：与手写的 `use Enum::*` 一样，拼错的变体名会被当作绑定任意值的变量模式，
+/// 编译器会对此给出警告（`non_snake_case` 与 `unreachable_patterns`），不要忽略它们。
+///
+/// # Examples
+///
+/// ```
+/// use hgm::{case, use_variants};
+///
+/// enum Color { Red, Green, Blue }
+/// enum Shape { Circle(f64), Square(f64) }
+///
+/// fn describe(color: Color, shape: Shape) -> String {
+///     use_variants!(Color, Shape);
+///     let color = case!(color => | Red => "red", | Green => "green", | Blue => "blue");
+///     let area = case!(shape =>
+///         | Circle(r) => std::f64::consts::PI * r * r,
+///         | Square(side) => side * side,
+///     );
+///     format!("{color} {area:.1}")
+/// }
+///
+/// assert_eq!(describe(Color::Green, Shape::Square(3.0)), "green 9.0");
+/// ```
+#[macro_export]
+macro_rules! use_variants {
+    ($($($segment:ident)::+),+ $(,)?) => {
+        $(
+            #[allow(unused_imports)]
+            use $($segment)::+::*;
+        )+
+    };
+}
+
+/// 生成用于 [`Iterator::map`] 的 case 闭包
+///
+/// 接收与 [`case!`] 相同的分支，返回一个闭包 `|x| case!(x => ...)`，
+/// 可以直接传给 `.map(...)`、`.flat_map(...)` 等迭代器适配器。
+///
+/// # 语法
+///
+/// ```text
+/// map_case!(
+///     | pattern1 => result1,
+///     | pattern2 if guard => result2,
+///     | _ => default,
+/// )
+/// ```
+///
+/// # Examples
+///
+/// ```
+/// use hgm::map_case;
+///
+/// let words: Vec<_> = [0, 1, 2, 7]
+///     .into_iter()
+///     .map(map_case!(
+///         | 0 => "zero",
+///         | 1 | 2 => "small",
+///         | _ => "large",
+///     ))
+///     .collect();
+/// assert_eq!(words, ["zero", "small", "small", "large"]);
+/// ```
+#[macro_export]
+macro_rules! map_case {
+    ($($arms:tt)+) => {
+        |__hgm_item| $crate::case!(__hgm_item => $($arms)+)
+    };
+}
+
+/// 生成用于 [`Iterator::filter`] 的 case 闭包
+///
+/// 每个分支只写模式（可带 guard 条件），任一分支匹配时闭包返回 `true`，
+/// 否则返回 `false`，类似于 `|x| matches!(x, ...)`。
+///
+/// # 语法
+///
+/// ```text
+/// filter_case!(
+///     | pattern1,
+///     | pattern2 if guard,
+/// )
+/// ```
+///
+/// # Examples
+///
+/// ## 与 `map_case!` 组合
+///
+/// ```
+/// use hgm::{filter_case, map_case};
+///
+/// let readings = [Some(3), None, Some(-1), Some(12)];
+/// let labels: Vec<_> = readings
+///     .iter()
+///     .filter(filter_case!(| Some(n) if *n >= 0))
+///     .map(map_case!(
+///         | Some(n) if *n > 10 => "high",
+///         | Some(_) => "normal",
+///         | None => "missing",
+///     ))
+///     .collect();
+/// assert_eq!(labels, ["normal", "high"]);
+/// ```
+#[macro_export]
+macro_rules! filter_case {
+    (@acc [$($arms:tt)*]) => {
+        |__hgm_item| match __hgm_item {
+            $($arms)*
+            #[allow(unreachable_patterns)]
+            _ => false,
+        }
+    };
+    (@acc [$($arms:tt)*] | $pat:pat if $guard:expr $(,$($rest:tt)*)?) => {
+        $crate::filter_case!(@acc [$($arms)* $pat if $guard => true,] $($($rest)*)?)
+    };
+    (@acc [$($arms:tt)*] | $pat:pat $(,$($rest:tt)*)?) => {
+        $crate::filter_case!(@acc [$($arms)* $pat => true,] $($($rest)*)?)
+    };
+    ($($rest:tt)+) => {
+        $crate::filter_case!(@acc [] $($rest)+)
+    };
+}
+
+/// 按键分派到处理函数的 case 闭包
+///
+/// 适合命令路由这类“按键选择处理函数”的场景：第一个参数是键，其余参数原样传给
+/// 选中的处理函数。每个分支的左侧是键的模式，右侧是处理函数（函数、闭包或返回闭包的表达式），
+/// 以其余参数调用；`otherwise` 分支的处理函数额外在最前面接收未匹配的键。
+/// 展开为 `move |key, args...| case!(key => ...)`，没有 `otherwise` 时键的模式必须穷尽。
+///
+/// 处理函数都是函数或不捕获变量的闭包时，结果可以转换为函数指针 `fn(K, Args...) -> R`，
+/// 便于存入结构体或表中。
+///
+/// # 语法
+///
+/// ```text
+/// dispatch!((key: K, arg1: A1, ...) -> R;
+///     | pattern1 => handler1,     // handler1(arg1, ...)
+///     | pattern2 => handler2,
+///     | otherwise => fallback,    // fallback(key, arg1, ...)
+/// )
+/// ```
+///
+/// # Examples
+///
+/// ```
+/// use hgm::dispatch;
+///
+/// fn echo(args: &[&str]) -> String {
+///     args.join(" ")
+/// }
+///
+/// fn unknown(command: &str, _args: &[&str]) -> String {
+///     format!("unknown command: {command}")
+/// }
+///
+/// let run: fn(&str, &[&str]) -> String = dispatch!((command: &str, args: &[&str]) -> String;
+///     | "echo" => echo,
+///     | "count" | "len" => |args: &[&str]| args.len().to_string(),
+///     | otherwise => unknown,
+/// );
+///
+/// assert_eq!(run("echo", &["hello", "world"]), "hello world");
+/// assert_eq!(run("len", &["a", "b", "c"]), "3");
+/// assert_eq!(run("rm", &[]), "unknown command: rm");
+/// ```
+#[macro_export]
+macro_rules! dispatch {
+    (($key:ident : $key_ty:ty $(, $arg:ident : $arg_ty:ty)* $(,)?) -> $ret:ty; $($arms:tt)+) => {
+        move |$key: $key_ty $(, $arg: $arg_ty)*| -> $ret {
+            $crate::dispatch!(@acc $key [$($arg),*] [] $($arms)+)
+        }
+    };
+    (@acc $key:ident [$($arg:ident),*] [$($done:tt)*] | otherwise => $fallback:expr $(,)?) => {
+        $crate::case!($key => $($done)* | unmatched => ($fallback)(unmatched $(, $arg)*))
+    };
+    (@acc $key:ident [$($arg:ident),*] [$($done:tt)*] | $pat:pat => $handler:expr $(, $($rest:tt)*)?) => {
+        $crate::dispatch!(@acc $key [$($arg),*] [$($done)* | $pat => ($handler)($($arg),*),] $($($rest)*)?)
+    };
+    (@acc $key:ident [$($arg:ident),*] [$($done:tt)*]) => {
+        $crate::case!($key => $($done)*)
+    };
+}
+
+/// 生成返回枚举变体名的 case 闭包
+///
+/// 列出枚举的全部变体，得到闭包 `|value: &Enum| -> &'static str`，
+/// 内部是对每个变体返回 `stringify!(Variant)` 的 [`case!`]。不需要 derive 宏，
+/// 也不需要额外依赖；漏写或写错变体时，与 `match` 一样在编译期报错。
+///
+/// 变体以 `Enum::Variant { .. }` 的形式匹配，因此单元、元组与结构体变体都可以
+/// 只写名字。
+///
+/// # 语法
+///
+/// ```text
+/// names!(Enum => Variant1, Variant2, Variant3)
+/// ```
+///
+/// # Examples
+///
+/// ```
+/// use hgm::names;
+///
+/// enum Shape { Point, Circle(f64), Rect { w: f64, h: f64 } }
+///
+/// let name = names!(Shape => Point, Circle, Rect);
+/// assert_eq!(name(&Shape::Circle(1.0)), "Circle");
+/// assert_eq!(name(&Shape::Rect { w: 1.0, h: 2.0 }), "Rect");
+/// ```
+///
+/// 遗漏变体时编译失败：
+///
+/// ```compile_fail,E0004
+/// use hgm::names;
+///
+/// enum Shape { Point, Circle(f64), Rect { w: f64, h: f64 } }
+///
+/// let name = names!(Shape => Point, Circle);
+/// ```
+#[macro_export]
+macro_rules! names {
+    ($enum:path => $($variant:ident),+ $(,)?) => {
+        |value: &$enum| -> &'static str {
+            type Enum = $enum;
+            $crate::case!(value =>
+                $(| Enum::$variant { .. } => stringify!($variant),)+
+            )
+        }
+    };
+}
+
+/// [`first_matching`] 使用的规则：`(谓词, 结果)`
+pub type Rule<T, R> = (fn(&T) -> bool, R);
+
+/// [`guard!`] 的运行时版本：在一组规则中查找第一个匹配的结果
+///
+/// `guard!` 的分支在编译期写死；当规则来自配置或需要在运行时组装时，
+/// 可以把每条规则表示为 `(谓词, 结果)`，按顺序返回第一个谓词为真的结果。
+/// 没有规则匹配时返回 `None`（相当于没有 `otherwise` 分支）。
+///
+/// # Examples
+///
+/// ```
+/// use hgm::first_matching;
+///
+/// let rules: [(fn(&i32) -> bool, &str); 3] = [
+///     (|n| *n < 0, "negative"),
+///     (|n| *n == 0, "zero"),
+///     (|n| *n < 10, "small"),
+/// ];
+///
+/// assert_eq!(first_matching(&-4, &rules), Some(&"negative"));
+/// assert_eq!(first_matching(&7, &rules), Some(&"small"));
+/// assert_eq!(first_matching(&42, &rules), None);
+/// ```
+pub fn first_matching<'a, T: ?Sized, R>(
+    input: &T,
+    rules: &'a [Rule<T, R>],
+) -> Option<&'a R> {
+    rules
+        .iter()
+        .find(|(predicate, _)| predicate(input))
+        .map(|(_, result)| result)
+}
+
+/// 两种可能类型之一的值
+///
+/// 当两个分支产生不同类型的迭代器时，分别包装为 `Left` 与 `Right` 即可得到
+/// 同一个类型；只要两边的 `Item` 相同，`Either` 本身就是迭代器。
+/// [`case_iter!`] 用嵌套的 `Either` 统一任意多个分支的迭代器类型。
+///
+/// # Examples
+///
+/// ```
+/// use hgm::Either;
+///
+/// let evens_only = true;
+/// let numbers = 1..=6;
+/// let picked = if evens_only {
+///     Either::Left(numbers.filter(|n| n % 2 == 0))
+/// } else {
+///     Either::Right(numbers.map(|n| n * 10))
+/// };
+/// assert_eq!(picked.collect::<Vec<_>>(), [2, 4, 6]);
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Either<L, R> {
+    Left(L),
+    Right(R),
+}
+
+impl<L, R> Iterator for Either<L, R>
+where
+    L: Iterator,
+    R: Iterator<Item = L::Item>,
+{
+    type Item = L::Item;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        match self {
+            Either::Left(left) => left.next(),
+            Either::Right(right) => right.next(),
+        }
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        match self {
+            Either::Left(left) => left.size_hint(),
+            Either::Right(right) => right.size_hint(),
+        }
+    }
+}
+
+impl<L, R> DoubleEndedIterator for Either<L, R>
+where
+    L: DoubleEndedIterator,
+    R: DoubleEndedIterator<Item = L::Item>,
+{
+    fn next_back(&mut self) -> Option<Self::Item> {
+        match self {
+            Either::Left(left) => left.next_back(),
+            Either::Right(right) => right.next_back(),
+        }
+    }
+}
+
+impl<L, R> ExactSizeIterator for Either<L, R>
+where
+    L: ExactSizeIterator,
+    R: ExactSizeIterator<Item = L::Item>,
+{
+}
+
+impl<L, R> core::iter::FusedIterator for Either<L, R>
+where
+    L: core::iter::FusedIterator,
+    R: core::iter::FusedIterator<Item = L::Item>,
+{
+}
+
+/// `guard!` 分支统计（需要 `profile` 特性）
+///
+/// 启用 `profile` 特性后，写作 `guard!(instrument; ...)` 的 guard 以及 [`fn_guard!`]
+/// 以 guard 链为函数体的非 `const` 函数在每个条件求值前记录一次 `evaluated`，条件成立时再记录
+/// 一次 `matched`；`otherwise` 分支在到达时同时记录两者。统计保存在线程局部
+/// 存储中，只反映当前线程，按每个分支第一次被求值的顺序排列。
+///
+/// 根据统计结果，可以把命中率高、求值便宜的分支移到前面。未启用该特性时
+/// 不生成任何统计代码。
+///
+/// 普通的 `guard!` 与 [`fn_guard!`] 生成的 `const fn` 不插入统计代码：统计调用不是 `const fn`，
+/// 只对显式要求的调用插桩，才能保证打开该特性不会让其他 crate 中的常量求值无法编译。
+///
+#[cfg_attr(feature = "profile", doc = "```")]
+#[cfg_attr(not(feature = "profile"), doc = "```ignore")]
+/// use hgm::{guard, profile};
+///
+/// profile::reset();
+/// for n in 0..10 {
+///     let _ = guard!(instrument; | n % 5 == 0 => "five", | n % 2 == 0 => "even", | otherwise => "odd");
+/// }
+/// let stats = profile::snapshot();
+/// assert_eq!(stats[0].arm, "n % 5 == 0");
+/// assert_eq!((stats[0].evaluated, stats[0].matched), (10, 2));
+/// assert_eq!((stats[1].evaluated, stats[1].matched), (8, 4));
+/// print!("{}", profile::report());
+/// ```
+#[cfg(feature = "profile")]
+pub mod profile {
+    use std::cell::RefCell;
+    use std::fmt::Write;
+    use std::string::String;
+    use std::vec::Vec;
+
+    /// 单个分支的统计
+    #[derive(Debug, Clone, PartialEq, Eq)]
+    pub struct ArmStats {
+        /// `guard!` 调用位置，形如 `src/lib.rs:10:5`
+        pub site: &'static str,
+        /// 分支条件的文本
+        pub arm: &'static str,
+        /// 条件被求值的次数
+        pub evaluated: u64,
+        /// 条件成立的次数
+        pub matched: u64,
+    }
+
+    std::thread_local! {
+        static STATS: RefCell<Vec<ArmStats>> = const { RefCell::new(Vec::new()) };
+    }
+
+    fn update(site: &'static str, arm: &'static str, apply: impl FnOnce(&mut ArmStats)) {
+        STATS.with(|stats| {
+            let mut stats = stats.borrow_mut();
+            // 地址相同即为同一个字符串常量，先比较指针避免逐字节比较
+            let found = stats.iter().position(|s| {
+                (core::ptr::eq(s.site, site) || s.site == site) && (core::ptr::eq(s.arm, arm) || s.arm == arm)
+            });
+            let index = found.unwrap_or_else(|| {
+                stats.push(ArmStats { site, arm, evaluated: 0, matched: 0 });
+                stats.len() - 1
+            });
+            apply(&mut stats[index]);
+        });
+    }
+
+    #[doc(hidden)]
+    pub fn __evaluated(site: &'static str, arm: &'static str) -> bool {
+        update(site, arm, |s| s.evaluated += 1);
+        true
+    }
+
+    #[doc(hidden)]
+    pub fn __matched(site: &'static str, arm: &'static str) {
+        update(site, arm, |s| s.matched += 1);
+    }
+
+    /// 返回当前线程的统计副本
+    pub fn snapshot() -> Vec<ArmStats> {
+        STATS.with(|stats| stats.borrow().clone())
+    }
+
+    /// 清空当前线程的统计
+    pub fn reset() {
+        STATS.with(|stats| stats.borrow_mut().clear());
+    }
+
+    /// 把当前线程的统计格式化为每个分支一行的文本
+    pub fn report() -> String {
+        let mut out = String::new();
+        for s in snapshot() {
+            let rate = if s.evaluated == 0 { 0.0 } else { s.matched as f64 / s.evaluated as f64 * 100.0 };
+            let _ = writeln!(out, "{} `{}`: {}/{} matched ({:.1}%)", s.site, s.arm, s.matched, s.evaluated, rate);
+        }
+        out
+    }
+}
+
+#[doc(hidden)]
+pub mod __private {
+    //! 宏展开时使用的内部辅助函数，不属于公开 API。
+
+    // 展开在调用方的 crate 中进行，调用方可能是没有 `::std` 的 `no_std` + `alloc` crate
+    #[cfg(feature = "alloc")]
+    pub use alloc::{borrow::Cow, boxed::Box};
+
+    /// profile / tracing：记录一次条件求值，并把已求值的分支数加一；总是返回 `true`
+    #[cfg(any(feature = "profile", feature = "tracing"))]
+    #[inline]
+    pub fn arm_evaluated(site: &'static str, arm: &mut usize, label: &'static str) -> bool {
+        #[cfg(feature = "profile")]
+        crate::profile::__evaluated(site, label);
+        #[cfg(not(feature = "profile"))]
+        let _ = (site, label);
+        *arm += 1;
+        true
+    }
+
+    /// profile / tracing：记录一次命中；`arm` 是包括命中分支在内已求值的分支数
+    #[cfg(any(feature = "profile", feature = "tracing"))]
+    #[inline]
+    pub fn arm_matched(site: &'static str, arm: usize, label: &'static str) {
+        #[cfg(feature = "profile")]
+        crate::profile::__matched(site, label);
+        #[cfg(feature = "tracing")]
+        tracing::trace!(site, arm = arm - 1, condition = label, "guard arm matched");
+        #[cfg(not(feature = "tracing"))]
+        let _ = arm;
+    }
+
+    /// 可以判断成功与否的分支结果，供 `guard_first_ok!` 与 `first_ok!` 使用
+    pub trait Fallible {
+        fn is_success(&self) -> bool;
+    }
+
+    impl<T> Fallible for Option<T> {
+        fn is_success(&self) -> bool {
+            self.is_some()
+        }
+    }
+
+    impl<T, E> Fallible for Result<T, E> {
+        fn is_success(&self) -> bool {
+            self.is_ok()
+        }
+    }
+
+    /// 标记冷路径；调用它的分支会被编译器视为不太可能执行
+    #[cold]
+    #[inline(never)]
+    const fn cold_path() {}
+
+    /// `guard!` 中 `[likely]` 分支的条件提示
+    #[inline(always)]
+    pub const fn likely(condition: bool) -> bool {
+        if !condition {
+            cold_path();
+        }
+        condition
+    }
+
+    /// `guard!` 中 `[unlikely]` 分支的条件提示
+    #[inline(always)]
+    pub const fn unlikely(condition: bool) -> bool {
+        if condition {
+            cold_path();
+        }
+        condition
+    }
+
+    /// 对每个样本调用函数，返回调用时发生 panic 的样本（`Debug` 格式），供 `#[verify_over]` 使用
+    #[cfg(feature = "std")]
+    pub fn verify_samples<S: core::fmt::Debug>(samples: &[S], call: impl Fn(&S)) -> Vec<String> {
+        samples
+            .iter()
+            .filter(|sample| std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| call(sample))).is_err())
+            .map(|sample| format!("{sample:?}"))
+            .collect()
+    }
+
+    /// `unreachable_guard_arms` 的返回值，只用于产生 `unused_must_use` 警告
+    pub struct UnreachableGuardArms;
+
+    /// 标记 `| true =>` 或 `| otherwise =>` 之后永远不会被检查的分支；
+    /// 展开中丢弃返回值，调用处因此得到 `unused_must_use` 警告
+    #[must_use = "guard!: arms after `| true => ...` or `| otherwise => ...` are never checked; remove them or move the catch-all to the end"]
+    #[inline(always)]
+    pub const fn unreachable_guard_arms() -> UnreachableGuardArms {
+        UnreachableGuardArms
+    }
+
+    /// 从调用者提供的随机数来源取 `0..total` 中的数，供 `guard_weighted!` 使用
+    #[track_caller]
+    pub fn draw(mut random: impl FnMut(u64) -> u64, total: u64) -> u64 {
+        let pick = random(total);
+        assert!(pick < total, "guard_weighted!: the random source returned {pick} for a total weight of {total}");
+        pick
+    }
+
+    /// 用 `rand::Rng` 取 `0..total` 中的数，供 `guard_weighted!(rng = ...)` 使用
+    #[cfg(feature = "rand")]
+    pub fn draw_rng<R: rand::Rng + ?Sized>(rng: &mut R, total: u64) -> u64 {
+        rand::RngExt::random_range(rng, 0..total)
+    }
+
+    /// `case!(#[over E] ...)` 的类型断言：被匹配的值是 `E` 或 `E` 的引用
+    #[diagnostic::on_unimplemented(
+        message = "case!: `#[over {E}]` expects a `{E}` (or a reference to one), found `{Self}`",
+        label = "not a `{E}`"
+    )]
+    pub trait Over<E: ?Sized> {}
+
+    impl<E: ?Sized> Over<E> for E {}
+    impl<E: ?Sized> Over<E> for &E {}
+    impl<E: ?Sized> Over<E> for &mut E {}
+
+    /// 总是返回 `false`，只用于在 `#[over]` 的检查分支中约束类型
+    #[inline(always)]
+    pub const fn over<E: ?Sized, T: Over<E> + ?Sized>(_: &T) -> bool {
+        false
+    }
+
+    /// `lex_case!` 接受的被匹配类型
+    #[diagnostic::on_unimplemented(message = "lex_case! matches a single `u8` or `char`, found `{Self}`")]
+    pub trait LexUnit: Copy {}
+
+    impl LexUnit for u8 {}
+    impl LexUnit for char {}
+
+    #[inline(always)]
+    pub const fn lex_unit<T: LexUnit>(unit: T) -> T {
+        unit
+    }
+
+    /// 逐字节比较两个字符串（const 上下文中不能使用 `==`）
+    const fn str_eq(a: &str, b: &str) -> bool {
+        let (a, b) = (a.as_bytes(), b.as_bytes());
+        if a.len() != b.len() {
+            return false;
+        }
+        let mut i = 0;
+        while i < a.len() {
+            if a[i] != b[i] {
+                return false;
+            }
+            i += 1;
+        }
+        true
+    }
+
+    /// 形如 `var < 10` 的条件归一化后的单侧整数界限
+    #[derive(Clone, Copy)]
+    struct Bound<'a> {
+        var: &'a [u8],
+        /// true 表示 `var <= value`，false 表示 `var >= value`
+        upper: bool,
+        value: i128,
+    }
+
+    const fn skip_spaces(s: &[u8], mut i: usize) -> usize {
+        while i < s.len() && s[i] == b' ' {
+            i += 1;
+        }
+        i
+    }
+
+    const fn is_ident_byte(b: u8) -> bool {
+        b.is_ascii_alphanumeric() || b == b'_'
+    }
+
+    /// 解析 `ident op integer` 形式的条件（op 为 `<`、`<=`、`>`、`>=`），其它形式返回 `None`
+    const fn parse_bound(cond: &str) -> Option<Bound<'_>> {
+        let s = cond.as_bytes();
+        let mut i = skip_spaces(s, 0);
+        let start = i;
+        if i >= s.len() || !(s[i].is_ascii_alphabetic() || s[i] == b'_') {
+            return None;
+        }
+        while i < s.len() && is_ident_byte(s[i]) {
+            i += 1;
+        }
+        let (head, _) = s.split_at(i);
+        let (_, var) = head.split_at(start);
+
+        i = skip_spaces(s, i);
+        if i >= s.len() || (s[i] != b'<' && s[i] != b'>') {
+            return None;
+        }
+        let upper = s[i] == b'<';
+        i += 1;
+        let inclusive = i < s.len() && s[i] == b'=';
+        if inclusive {
+            i += 1;
+        }
+
+        i = skip_spaces(s, i);
+        let negative = i < s.len() && s[i] == b'-';
+        if negative {
+            i = skip_spaces(s, i + 1);
+        }
+        let mut value: i128 = 0;
+        let mut digits = 0;
+        while i < s.len() && (s[i].is_ascii_digit() || s[i] == b'_') {
+            if s[i] != b'_' {
+                if digits == 30 {
+                    return None;
+                }
+                value = value * 10 + (s[i] - b'0') as i128;
+                digits += 1;
+            }
+            i += 1;
+        }
+        if digits == 0 {
+            return None;
+        }
+        // 允许 `10u8` 之类的整数后缀，但拒绝 `1.5`、`10 + n` 等其它内容
+        if i < s.len() && (s[i] == b'i' || s[i] == b'u') {
+            while i < s.len() && is_ident_byte(s[i]) {
+                i += 1;
+            }
+        }
+        if skip_spaces(s, i) != s.len() {
+            return None;
+        }
+
+        if negative {
+            value = -value;
+        }
+        let value = match (upper, inclusive) {
+            (true, false) => value - 1,
+            (false, false) => value + 1,
+            _ => value,
+        };
+        Some(Bound { var, upper, value })
+    }
+
+    const fn bytes_eq(a: &[u8], b: &[u8]) -> bool {
+        if a.len() != b.len() {
+            return false;
+        }
+        let mut i = 0;
+        while i < a.len() {
+            if a[i] != b[i] {
+                return false;
+            }
+            i += 1;
+        }
+        true
+    }
+
+    /// 若某个整数比较分支已被前面同一变量、同一方向的比较完全覆盖，
+    /// 则以 `messages` 中对应的文本在 const 求值时 panic
+    pub const fn assert_no_dead_ranges(conds: &[&str], messages: &[&str]) {
+        let mut j = 0;
+        while j < conds.len() {
+            if let Some(later) = parse_bound(conds[j]) {
+                let mut i = 0;
+                while i < j {
+                    if let Some(earlier) = parse_bound(conds[i])
+                        && earlier.upper == later.upper
+                        && bytes_eq(earlier.var, later.var)
+                        && (later.upper && later.value <= earlier.value
+                            || !later.upper && later.value >= earlier.value)
+                    {
+                        panic!("{}", messages[j]);
+                    }
+                    i += 1;
+                }
+            }
+            j += 1;
+        }
+    }
+
+    /// 若存在两条文本相同的分支，则在 const 求值时 panic
+    pub const fn assert_distinct_arms(keys: &[&str]) {
+        let mut i = 0;
+        while i < keys.len() {
+            let mut j = i + 1;
+            while j < keys.len() {
+                if str_eq(keys[i], keys[j]) {
+                    panic!("{}", keys[i]);
+                }
+                j += 1;
+            }
+            i += 1;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    #[test]
+    fn test_guard() {
+        let x = 10;
+        let result = guard!(
+            | x < 0 => "negative",
+            | x == 0 => "zero",
+            | otherwise => "positive",
+        );
+        assert_eq!(result, "positive");
+    }
+
+    #[test]
+    fn test_guard_dead_range_detection() {
+        use super::__private::assert_no_dead_ranges;
+        use std::panic::catch_unwind;
+
+        let dead = |conds: &'static [&'static str]| {
+            let messages: Vec<&'static str> = conds.iter().map(|_| "dead").collect();
+            catch_unwind(move || assert_no_dead_ranges(conds, &messages)).is_err()
+        };
+        assert!(dead(&["x < 10", "x < 5"]));
+        assert!(dead(&["x < 10", "x <= 9"]));
+        assert!(dead(&["x >= 0", "y == 1", "x > 0"]));
+        assert!(dead(&["n <= 10u8", "n < -1"]));
+
+        assert!(!dead(&["x < 5", "x < 10"]));
+        assert!(!dead(&["x < 10", "x <= 10"]));
+        assert!(!dead(&["x < 10", "x > 5"]));
+        assert!(!dead(&["x < 10", "y < 5"]));
+        assert!(!dead(&["x < 1.5", "x < 0.5"]));
+        assert!(!dead(&["p.x < 10", "p.x < 5"]));
+        assert!(!dead(&["x < LIMIT", "x < 5 && y", "otherwise"]));
+    }
+
+    #[test]
+    fn test_case() {
+        let opt = Some(42);
+        let result = case!(opt =>
+            | None => 0,
+            | Some(x) if x > 50 => 1,
+            | Some(_) => 2,
+        );
+        assert_eq!(result, 2);
+    }
+
+    #[test]
+    fn test_case_distinct_guards() {
+        // 模式相同但 guard 不同的分支不算重复
+        let n = 7;
+        let result = case!(n =>
+            | x if x < 5 => "small",
+            | x if x < 10 => "medium",
+            | _ => "large",
+        );
+        assert_eq!(result, "medium");
+    }
+
+    #[test]
+    fn test_case_where_order() {
+        use std::cell::RefCell;
+
+        let log = RefCell::new(Vec::new());
+        let step = |name: &'static str, value: i32| {
+            log.borrow_mut().push(name);
+            value
+        };
+        let result = case!(step("scrutinee", 4) =>
+            | n if n < low => "below",
+            | n if n > high => "above",
+            | _ => "within",
+            where low = step("low", 1), high = step("high", 3),
+        );
+        assert_eq!(result, "above");
+        assert_eq!(*log.borrow(), ["low", "high", "scrutinee"]);
+    }
+
+    #[test]
+    fn test_case_scrutinee_with_pipes() {
+        const READ: u8 = 0b01;
+        const WRITE: u8 = 0b10;
+        let flags = READ;
+        let result = case!(flags | WRITE =>
+            | 0b11 => "read-write",
+            | _ => "partial",
+        );
+        assert_eq!(result, "read-write");
+
+        let apply = |f: fn(i32) -> i32, n: i32| f(n);
+        let result = case!(apply(|n| n * 2, 4) =>
+            | 8 => "doubled",
+            | _ => "other",
+        );
+        assert_eq!(result, "doubled");
+
+        let result = case!([1, 2].iter().map(|n| n | 4).max() =>
+            | Some(n) if n | 1 == 7 => "bits",
+            | _ => "none",
+        );
+        assert_eq!(result, "bits");
+    }
+
+    #[derive(Clone, Copy)]
+    enum Signal {
+        Stop,
+        Go(u8),
+    }
+
+    struct Limits;
+
+    impl Limits {
+        const MAX: u16 = 1000;
+    }
+
+    const ERROR_CODE: u16 = 500;
+
+    #[test]
+    fn test_case_path_patterns() {
+        let classify = |code: u16| {
+            case!(code =>
+                | ERROR_CODE => "server error",
+                | Limits::MAX => "limit",
+                | u16::MAX => "overflow",
+                | _ => "other",
+            )
+        };
+        assert_eq!(classify(500), "server error");
+        assert_eq!(classify(1000), "limit");
+        assert_eq!(classify(u16::MAX), "overflow");
+        assert_eq!(classify(1), "other");
+
+        let signal = Signal::Go(3);
+        let result = case!(signal =>
+            | crate::tests::Signal::Stop => 0,
+            | self::Signal::Go(n) if n > 5 => 2,
+            | Signal::Go(_) => 1,
+        );
+        assert_eq!(result, 1);
+        assert_eq!(case!(Signal::Stop => | Signal::Stop => 0, | Signal::Go(n) => n), 0);
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn test_case_try_arm() {
+        fn checked(n: i32) -> i32 {
+            assert!(n != 0, "zero is rejected");
+            100 / n
+        }
+
+        let calls = std::cell::Cell::new(0);
+        let classify = |n: i32| {
+            case!(n =>
+                | n if n < 0 => -1,
+                | n try => {
+                    calls.set(calls.get() + 1);
+                    checked(n)
+                },
+                | _ => 0,
+            )
+        };
+        assert_eq!(classify(-5), -1);
+        assert_eq!(classify(4), 25);
+        assert_eq!(classify(0), 0);
+        assert_eq!(calls.get(), 2);
+
+        let pair = (Some(2), "two");
+        let result = case!(pair =>
+            | (Some(n), _) try => [10, 20][n as usize],
+            | (_, name) => name.len(),
+        );
+        assert_eq!(result, 3);
+    }
+
+    #[test]
+    fn test_case_over_hint() {
+        let signal = Signal::Go(3);
+        let by_ref = case!(#[over Signal] &signal =>
+            | Signal::Stop => 0,
+            | Signal::Go(n) => *n,
+        );
+        assert_eq!(by_ref, 3);
+
+        // 提示分支只借用被匹配的值，后续分支仍可按值绑定
+        let owned = case!(#[over Signal] signal =>
+            | Signal::Go(n) if n > 5 => n * 2,
+            | Signal::Go(n) => n,
+            | Signal::Stop => 0,
+        );
+        assert_eq!(owned, 3);
+    }
+
+    #[derive(Debug, PartialEq)]
+    enum Expr {
+        Num(i64),
+        Neg(Box<Expr>),
+        Add(Box<Expr>, Box<Expr>),
+    }
+
+    fn num(n: i64) -> Box<Expr> {
+        Box::new(Expr::Num(n))
+    }
+
+    #[cfg(feature = "nightly")]
+    #[test]
+    fn test_case_box_patterns() {
+        #[derive(Debug, PartialEq)]
+        enum Tree {
+            Leaf(u32),
+            Node(Box<Tree>, Box<Tree>),
+        }
+
+        fn leftmost(tree: &Tree) -> u32 {
+            case!(tree =>
+                | Tree::Leaf(n) => *n,
+                | Tree::Node(box Tree::Leaf(n), _) if *n > 0 => *n,
+                | Tree::Node(box left, _) => leftmost(left),
+            )
+        }
+
+        fn rotate(tree: Tree) -> Tree {
+            case!(tree =>
+                | Tree::Node(box Tree::Node(box a, box b), box c) =>
+                    Tree::Node(Box::new(a), Box::new(Tree::Node(Box::new(b), Box::new(c)))),
+                | other => other,
+            )
+        }
+
+        let leaf = |n| Box::new(Tree::Leaf(n));
+        let tree = Tree::Node(Box::new(Tree::Node(leaf(1), leaf(2))), leaf(3));
+        assert_eq!(leftmost(&tree), 1);
+        assert_eq!(
+            rotate(tree),
+            Tree::Node(leaf(1), Box::new(Tree::Node(leaf(2), leaf(3))))
+        );
+        assert_eq!(leftmost(&Tree::Node(leaf(0), leaf(5))), 0);
+    }
+
+    #[test]
+    fn test_case_binding_over_or_pattern() {
+        #[derive(Debug, Clone, PartialEq)]
+        enum Expr {
+            Num(i64),
+            Add(Box<Expr>, Box<Expr>),
+            Sub(Box<Expr>, Box<Expr>),
+            Mul(Box<Expr>, Box<Expr>),
+            Neg(Box<Expr>),
+        }
+
+        fn num(n: i64) -> Box<Expr> {
+            Box::new(Expr::Num(n))
+        }
+
+        fn fold_binary(e: &Expr) -> i64 {
+            case!(e =>
+                | Expr::Add(l, r) => eval(l) + eval(r),
+                | Expr::Sub(l, r) => eval(l) - eval(r),
+                | Expr::Mul(l, r) => eval(l) * eval(r),
+                | _ => unreachable!("not a binary expression: {e:?}"),
+            )
+        }
+
+        fn eval(e: &Expr) -> i64 {
+            case!(e =>
+                | Expr::Num(n) => *n,
+                // 绑定整个表达式，同时匹配多个带载荷的变体
+                | e @ (Expr::Add(..) | Expr::Sub(..)) => fold_binary(e),
+                // 或模式中的每个变体都绑定同名的载荷，并可附加 guard
+                | whole @ (Expr::Mul(l, _) | Expr::Neg(l)) if **l == Expr::Num(0) => {
+                    assert!(matches!(whole, Expr::Mul(..) | Expr::Neg(..)));
+                    0
+                },
+                | e @ Expr::Mul(..) => fold_binary(e),
+                | Expr::Neg(inner) => -eval(inner),
+            )
+        }
+
+        let expr = Expr::Sub(Box::new(Expr::Add(num(2), num(5))), Box::new(Expr::Neg(num(3))));
+        assert_eq!(eval(&expr), 10);
+        assert_eq!(eval(&Expr::Mul(num(0), Box::new(expr.clone()))), 0);
+        assert_eq!(eval(&Expr::Mul(num(4), Box::new(expr))), 40);
+        assert_eq!(eval(&Expr::Neg(num(0))), 0);
+
+        // 按值匹配时同样可以把整个值移出
+        let moved = case!(Expr::Add(num(1), num(1)) =>
+            | e @ (Expr::Add(..) | Expr::Sub(..)) => Some(e),
+            | _ => None,
+        );
+        assert_eq!(moved, Some(Expr::Add(num(1), num(1))));
+    }
+
+    #[test]
+    fn test_case_guard_uses_several_bindings() {
+        #[derive(Debug, Clone, Copy)]
+        struct Point {
+            x: i32,
+            y: i32,
+        }
+
+        struct Segment {
+            from: Point,
+            to: Point,
+        }
+
+        fn zone(p: Point) -> &'static str {
+            case!(p =>
+                | Point { x: 0, y: 0 } => "origin",
+                | Point { x, y } if x.abs() + y.abs() < 10 => "near",
+                | Point { x, y } if x == y || x == -y => "diagonal",
+                | Point { x, y: ref height } if x > 0 && *height > x => "steep",
+                | _ => "far",
+            )
+        }
+
+        assert_eq!(zone(Point { x: 0, y: 0 }), "origin");
+        assert_eq!(zone(Point { x: 3, y: -6 }), "near");
+        assert_eq!(zone(Point { x: -20, y: 20 }), "diagonal");
+        assert_eq!(zone(Point { x: 5, y: 30 }), "steep");
+        assert_eq!(zone(Point { x: 30, y: 5 }), "far");
+
+        // 嵌套结构体：guard 同时引用四个绑定
+        let length = |s: &Segment| {
+            case!(s =>
+                | Segment { from: Point { x: x1, y: y1 }, to: Point { x: x2, y: y2 } }
+                    if x1 == x2 && y1 == y2 => 0,
+                | Segment { from: Point { x: x1, y: y1 }, to: Point { x: x2, y: y2 } }
+                    if x1 == x2 || y1 == y2 => (x2 - x1).abs() + (y2 - y1).abs(),
+                | _ => -1,
+            )
+        };
+        let p = |x, y| Point { x, y };
+        assert_eq!(length(&Segment { from: p(2, 2), to: p(2, 2) }), 0);
+        assert_eq!(length(&Segment { from: p(1, 4), to: p(1, -3) }), 7);
+        assert_eq!(length(&Segment { from: p(0, 0), to: p(3, 4) }), -1);
+
+        // 元组模式，guard 中混用绑定与方法调用
+        let triangle = |sides: (u32, u32, u32)| {
+            case!(sides =>
+                | (a, b, c) if a + b <= c || a + c <= b || b + c <= a => "degenerate",
+                | (a, b, c) if a == b && b == c => "equilateral",
+                | (a, b, c) if a.pow(2) + b.pow(2) == c.pow(2) => "right",
+                | (a, b, c) if a == b || b == c || a == c => "isosceles",
+                | (_, _, _) => "scalene",
+            )
+        };
+        assert_eq!(triangle((1, 2, 3)), "degenerate");
+        assert_eq!(triangle((5, 5, 5)), "equilateral");
+        assert_eq!(triangle((3, 4, 5)), "right");
+        assert_eq!(triangle((5, 5, 8)), "isosceles");
+        assert_eq!(triangle((4, 5, 6)), "scalene");
+
+        // 切片模式与 `@` 绑定：guard 同时使用首尾元素和整个子切片
+        let shape = |values: &[i32]| {
+            case!(values =>
+                | [first, middle @ .., last] if first == last && middle.iter().all(|m| m > first) => "arch",
+                | [first, .., last] if first < last => "rising",
+                | [_, ..] => "other",
+                | [] => "empty",
+            )
+        };
+        assert_eq!(shape(&[1, 5, 7, 1]), "arch");
+        assert_eq!(shape(&[1, 0, 3]), "rising");
+        assert_eq!(shape(&[4]), "other");
+        assert_eq!(shape(&[]), "empty");
+    }
+
+    #[test]
+    fn test_cmp_case() {
+        let compare = |a: &str, b: &str| cmp_case!(a.len().cmp(&b.len()) =>
+            | Less => "shorter",
+            | Equal => "same length",
+            | Greater => "longer",
+        );
+        assert_eq!(compare("ab", "abc"), "shorter");
+        assert_eq!(compare("ab", "xy"), "same length");
+        assert_eq!(compare("abcd", "x"), "longer");
+
+        // 多个变体共用一个分支，顺序任意
+        let at_least = |a: u8, b: u8| cmp_case!(a.cmp(&b) => | Greater | Equal => true, | Less => false);
+        assert!(at_least(3, 3));
+        assert!(at_least(4, 3));
+        assert!(!at_least(2, 3));
+    }
+
+    #[test]
+    fn test_dispatch() {
+        let mut log = Vec::new();
+        {
+            let prefix = String::from("> ");
+            let route = dispatch!((command: &str, arg: i64, log: &mut Vec<String>) -> Result<i64, String>;
+                | "double" => |n: i64, _: &mut Vec<String>| Ok(n * 2),
+                | "neg" | "negate" => |n: i64, _: &mut Vec<String>| Ok(-n),
+                | "say" => |n: i64, log: &mut Vec<String>| {
+                    log.push(format!("{prefix}{n}"));
+                    Ok(n)
+                },
+                | otherwise => |command: &str, _: i64, _: &mut Vec<String>| Err(format!("no handler for {command}")),
+            );
+
+            assert_eq!(route("double", 21, &mut log), Ok(42));
+            assert_eq!(route("negate", 5, &mut log), Ok(-5));
+            assert_eq!(route("say", 7, &mut log), Ok(7));
+            assert_eq!(route("jump", 1, &mut log), Err("no handler for jump".to_string()));
+        }
+        assert_eq!(log, ["> 7"]);
+
+        // 键是穷尽的枚举时不需要 otherwise；只有键一个参数时处理函数不接收参数
+        #[derive(Clone, Copy)]
+        enum Key {
+            Up,
+            Down,
+        }
+        let delta: fn(Key) -> i32 = dispatch!((key: Key) -> i32; | Key::Up => || 1, | Key::Down => || -1);
+        assert_eq!(delta(Key::Up) + delta(Key::Down) + delta(Key::Up), 1);
+    }
+
+    mod paint {
+        #[derive(Debug, PartialEq)]
+        pub enum Finish {
+            Matte,
+            Gloss(u8),
+        }
+    }
+
+    #[test]
+    fn test_use_variants() {
+        #[derive(Clone, Copy)]
+        enum Color {
+            Red,
+            Green,
+            Blue,
+        }
+
+        use_variants!(Color, paint::Finish);
+
+        let warm = |c: Color| case!(c => | Red => true, | Green | Blue => false);
+        assert!(warm(Red));
+        assert!(!warm(Color::Blue));
+        assert!(!warm(Green));
+
+        let shine = |f: &paint::Finish| case!(f =>
+            | Matte => 0,
+            | Gloss(level) if *level > 5 => 2,
+            | Gloss(_) => 1,
+        );
+        assert_eq!(shine(&Matte), 0);
+        assert_eq!(shine(&Gloss(9)), 2);
+        assert_eq!(Gloss(1), paint::Finish::Gloss(1));
+    }
+
+    #[test]
+    fn test_case_discriminant() {
+        #[derive(Debug, Clone, Copy)]
+        #[repr(i16)]
+        enum Status {
+            Continue = 100,
+            Ok = 200,
+            NoContent = 204,
+            NotFound = 404,
+            Teapot = 418,
+            Unavailable = 503,
+            Internal = -1,
+        }
+
+        let class = |status: Status| case_discriminant!(status =>
+            | 100..=199 => "informational",
+            | 200..=299 => "success",
+            | code @ 400..=499 if code == 418 => "joke",
+            | 400..=499 => "client error",
+            | n if n < 0 => "internal",
+            | otherwise => "server error",
+        );
+        assert_eq!(class(Status::Continue), "informational");
+        assert_eq!(class(Status::Ok), "success");
+        assert_eq!(class(Status::NoContent), "success");
+        assert_eq!(class(Status::NotFound), "client error");
+        assert_eq!(class(Status::Teapot), "joke");
+        assert_eq!(class(Status::Unavailable), "server error");
+        assert_eq!(class(Status::Internal), "internal");
+
+        let statuses = [Status::Ok, Status::Teapot];
+        let codes: Vec<isize> = statuses.iter().map(|s| case_discriminant!(*s => | n => n)).collect();
+        assert_eq!(codes, [200, 418]);
+    }
+
+    #[test]
+    fn test_case_matches() {
+        let is_port = |text: &str| case_matches!(text.parse::<u32>() =>
+            | Ok(0),
+            | Ok(n) if n <= 65535 && n != 0,
+        );
+        assert!(is_port("8080"));
+        assert!(is_port("0"));
+        assert!(!is_port("70000"));
+        assert!(!is_port("http"));
+
+        // 或模式与穷尽的分支同样可用
+        let small = |n: Option<u8>| case_matches!(n => | Some(0..=9) | None, | Some(_) if false);
+        assert!(small(None));
+        assert!(small(Some(3)));
+        assert!(!small(Some(30)));
+        assert!(case_matches!(true => | true, | false));
+    }
+
+    mod opcodes {
+        crate::case_arms! {
+            pub(crate) arithmetic_arms {
+                | ("add", [a, b]) => Some(a + b),
+                | ("sub", [a, b]) => Some(a - b),
+                | ("div", [_, 0]) => None,
+                | ("div", [a, b]) => Some(a / b),
+            }
+        }
+    }
+
+    #[test]
+    fn test_case_arms() {
+        use opcodes::arithmetic_arms;
+
+        crate::case_arms! {
+            unary_arms {
+                | ("neg", [a]) => Some(-a),
+                | ("abs", [a]) if *a < 0 => Some(-a),
+                | ("abs", [a]) => Some(*a)
+            }
+        }
+
+        let eval = |op: &str, args: &[i64]| {
+            case!((op, args) =>
+                | ("nop", []) => Some(0),
+                arithmetic_arms!()
+                unary_arms!()
+                | otherwise => None,
+            )
+        };
+        assert_eq!(eval("nop", &[]), Some(0));
+        assert_eq!(eval("add", &[2, 5]), Some(7));
+        assert_eq!(eval("div", &[9, 0]), None);
+        assert_eq!(eval("div", &[9, 3]), Some(3));
+        assert_eq!(eval("abs", &[-4]), Some(4));
+        assert_eq!(eval("abs", &[4]), Some(4));
+        assert_eq!(eval("neg", &[1, 2]), None);
+
+        // 片段可以单独组成整个 case!，也可以放在最后、由片段之后的 `_` 兜底
+        let binary_only = |op: &str, args: &[i64]| case!((op, args) => arithmetic_arms!() | _ => None);
+        assert_eq!(binary_only("sub", &[2, 5]), Some(-3));
+        assert_eq!(binary_only("neg", &[2]), None);
+    }
+
+    #[test]
+    fn test_case_len() {
+        fn parse_version(text: &str) -> Option<(u32, u32, u32)> {
+            let parts: Vec<u32> = text.split('.').map(str::parse).collect::<Result<_, _>>().ok()?;
+            case_len!(parts =>
+                | 1 => Some((parts[0], 0, 0)),
+                | 2 => Some((parts[0], parts[1], 0)),
+                | 3 => Some((parts[0], parts[1], parts[2])),
+                | otherwise => None,
+            )
+        }
+
+        assert_eq!(parse_version("2"), Some((2, 0, 0)));
+        assert_eq!(parse_version("1.4"), Some((1, 4, 0)));
+        assert_eq!(parse_version("1.4.2"), Some((1, 4, 2)));
+        assert_eq!(parse_version("1.4.2.9"), None);
+        assert_eq!(parse_version("x"), None);
+
+        let bytes: &[u8] = b"GET /";
+        let kind = case_len!(bytes =>
+            | 0 => "empty",
+            | n if n < short => "short",
+            | n if bytes[n - 1] == b'/' => "path",
+            | _ => "other",
+            where short = 3
+        );
+        assert_eq!(kind, "path");
+        assert_eq!(case_len!(&bytes[..2] => | n if n < 3 => n, | _ => 0), 2);
+    }
+
+    #[test]
+    fn test_case_redispatch() {
+        use Expr::*;
+
+        // 化简为常数：每一步只展开最外层，剩余部分交给下一轮匹配
+        let normalize = |expr: Expr| {
+            case!(expr =>
+                | Num(n) => n,
+                | Neg(inner) => redispatch case!(*inner =>
+                    | Num(n) => Num(-n),
+                    | Neg(e) => *e,
+                    | Add(l, r) => Add(Box::new(Neg(l)), Box::new(Neg(r))),
+                ),
+                | Add(l, r) => redispatch case!((*l, *r) =>
+                    | (Num(a), Num(b)) => Num(a + b),
+                    | (Num(a), e) => Add(Box::new(e), num(a)),
+                    | (l, r) => Add(Box::new(normalize_once(l)), Box::new(r)),
+                ),
+            )
+        };
+        fn normalize_once(expr: Expr) -> Expr {
+            match expr {
+                Neg(inner) => match *inner {
+                    Num(n) => Num(-n),
+                    Neg(e) => *e,
+                    Add(l, r) => Add(Box::new(Neg(l)), Box::new(Neg(r))),
+                },
+                Add(l, r) => match (*l, *r) {
+                    (Num(a), Num(b)) => Num(a + b),
+                    (l, r) => Add(Box::new(normalize_once(l)), Box::new(normalize_once(r))),
+                },
+                num => num,
+            }
+        }
+
+        assert_eq!(normalize(Num(4)), 4);
+        assert_eq!(normalize(Neg(Box::new(Neg(num(7))))), 7);
+        assert_eq!(normalize(Neg(Box::new(Add(num(1), num(2))))), -3);
+        assert_eq!(normalize(Add(Box::new(Neg(num(2))), Box::new(Add(num(3), num(4))))), 5);
+
+        // 重新分派不占用栈：百万次迭代也不会溢出
+        let mut rounds = 0u32;
+        let zero = case!(1_000_000u32 =>
+            | 0 => rounds,
+            | n => redispatch { rounds += 1; n - 1 },
+        );
+        assert_eq!(zero, 1_000_000);
+    }
+
+    #[test]
+    fn test_compute_then_case() {
+        let readings = [1.0_f64, 2.0, 15.0];
+        let verdict = |limit: f64| {
+            compute_then_case!(
+                where total = readings.iter().sum::<f64>(),
+                      mean = total / readings.len() as f64,
+                      peak = readings.iter().cloned().fold(f64::MIN, f64::max);
+                (mean > limit, peak > limit * 2.0) =>
+                | (true, _) => ("high", mean),
+                | (false, true) if peak - mean > limit => ("spiky", peak),
+                | (false, _) => ("normal", total),
+            )
+        };
+        assert_eq!(verdict(5.0), ("high", 6.0));
+        assert_eq!(verdict(7.0), ("spiky", 15.0));
+        assert_eq!(verdict(8.0), ("normal", 18.0));
+    }
+
+    #[cfg(feature = "alloc")]
+    #[test]
+    fn test_case_cow() {
+        use std::borrow::Cow;
+
+        let greeting = |name: Option<&str>| {
+            case_cow!(name =>
+                | None => "hello",
+                | Some("") => default,
+                | Some(n) if n.len() > 8 => format!("hello, {}...", &n[..8]),
+                | Some(n) => format!("hello, {n}"),
+                where default = "hello, stranger",
+            )
+        };
+        assert!(matches!(greeting(None), Cow::Borrowed("hello")));
+        assert!(matches!(greeting(Some("")), Cow::Borrowed("hello, stranger")));
+        assert!(matches!(greeting(Some("hgm")), Cow::Owned(ref s) if s == "hello, hgm"));
+        assert_eq!(greeting(Some("haskellers")), "hello, haskelle...");
+
+        let fallback: Cow<str> = case_cow!(3 => | 1 => "one", | otherwise => String::from("many"));
+        assert!(matches!(fallback, Cow::Owned(_)));
+    }
+
+    #[test]
+    fn test_case_labeled() {
+        use std::collections::HashMap;
+
+        let mut hits: HashMap<&str, u32> = HashMap::new();
+        for input in [Some(-3), Some(0), None, Some(7), Some(9)] {
+            let (label, value) = case_labeled!(input =>
+                | Some(n) if n < floor # "below" => floor,
+                | Some(n) # "value" => n,
+                | None # "missing" => floor,
+                where floor = 0,
+            );
+            assert!(value >= 0);
+            *hits.entry(label).or_default() += 1;
+        }
+        assert_eq!(hits["below"], 1);
+        assert_eq!(hits["value"], 3);
+        assert_eq!(hits["missing"], 1);
+    }
+
+    #[test]
+    fn test_case_or_patterns_with_otherwise() {
+        use super::otherwise;
+
+        let bucket = |n: i32| case!(n =>
+            | 1 | 2 => "low",
+            | 3 | 4 => "mid",
+            | otherwise => "high",
+        );
+        assert_eq!(bucket(1), "low");
+        assert_eq!(bucket(4), "mid");
+        assert_eq!(bucket(0), "high");
+
+        // 即使导入了常量 otherwise，也不会被当作常量模式；where 子句照常可用
+        let tier = case!(Some(7) =>
+            | Some(0) | None => "none",
+            | Some(n) if n < cutoff => "some",
+            | otherwise => "many",
+            where cutoff = 5,
+        );
+        assert_eq!(tier, "many");
+        assert_eq!(case!(otherwise => | true => 1, | false => 0), 1);
+    }
+
+    #[test]
+    fn test_case_moves_out_of_bindings() {
+        let name = Some(String::from("hgm"));
+        let owned: String = case!(name =>
+            | Some(s) if s.len() > limit => s,
+            | Some(s) => s + "!",
+            | None => String::new(),
+            where limit = 5
+        );
+        assert_eq!(owned, "hgm!");
+
+        let parsed: Result<String, String> = Err(String::from("bad input"));
+        let message = case!(parsed =>
+            | Ok(value) => value,
+            | Err(e) if e.is_empty() => String::from("unknown"),
+            | Err(e) => e,
+        );
+        assert_eq!(message, "bad input");
+
+        // #[over] 的检查分支只借用，后面的分支仍可移出载荷
+        let wrapped: Option<Vec<String>> = Some(vec![String::from("a"), String::from("b")]);
+        let joined = case!(#[over Option<Vec<String>>] wrapped =>
+            | Some(items) => items.concat(),
+            | None => String::new(),
+        );
+        assert_eq!(joined, "ab");
+
+        // 重新分派时从旧值中移出的部分可以组成新值
+        let nested: Result<Option<String>, String> = Ok(Some(String::from("deep")));
+        let flat = case!(nested =>
+            | Ok(Some(s)) => s,
+            | Ok(None) => redispatch Err(String::from("empty")),
+            | Err(e) => e,
+        );
+        assert_eq!(flat, "deep");
+    }
+
+    #[test]
+    fn test_case_result() {
+        #[derive(Debug, PartialEq)]
+        enum Command {
+            Move(i32, i32),
+            Say(String),
+            Quit,
+        }
+
+        let distance = |cmd: Command| case_result!(cmd =>
+            | Command::Move(dx, dy) if dx == 0 || dy == 0 => dx.abs() + dy.abs(),
+            | Command::Move(dx, dy) => dx.max(dy),
+        );
+        assert_eq!(distance(Command::Move(0, -4)), Ok(4));
+        assert_eq!(distance(Command::Move(2, 5)), Ok(5));
+        // 未命中的值原样出现在错误中，包括其拥有的数据
+        assert_eq!(distance(Command::Say(String::from("hi"))), Err(Command::Say(String::from("hi"))));
+        assert_eq!(distance(Command::Quit), Err(Command::Quit));
+
+        let words = [Command::Quit, Command::Say(String::from("bye"))];
+        let said: Vec<Result<&str, &Command>> = words
+            .iter()
+            .map(|cmd| case_result!(cmd => | Command::Say(s) => s.as_str()))
+            .collect();
+        assert_eq!(said, [Err(&Command::Quit), Ok("bye")]);
+
+        let exit = case_result!(Command::Move(1, 1) =>
+            | Command::Quit | Command::Say(_) => 0,
+            err = "still running",
+        );
+        assert_eq!(exit, Err("still running"));
+    }
+
+    mod palette {
+        #[derive(Clone, Copy)]
+        pub enum Color {
+            Red,
+            Green,
+            Blue,
+        }
+    }
+
+    #[test]
+    fn test_names() {
+        use palette::Color;
+
+        let name = names!(palette::Color => Red, Green, Blue);
+        assert_eq!(name(&Color::Red), "Red");
+        assert_eq!(name(&Color::Green), "Green");
+        assert_eq!(name(&Color::Blue), "Blue");
+
+        let all: Vec<&str> = [Color::Blue, Color::Red].iter().map(name).collect();
+        assert_eq!(all, ["Blue", "Red"]);
+
+        // 闭包不捕获环境，可以转换为函数指针保存
+        let as_fn: fn(&Color) -> &'static str = names!(Color => Red, Green, Blue);
+        assert_eq!(as_fn(&Color::Green), "Green");
+    }
+
+    #[derive(Clone, Copy, PartialEq, Debug)]
+    enum Arrow {
+        Up,
+        Down,
+        Left,
+        Right,
+    }
+
+    #[derive(Clone, Copy, PartialEq, Debug)]
+    enum Msg {
+        Key(Arrow),
+        Click(u8, Option<Arrow>),
+        Idle,
+    }
+
+    #[test]
+    fn test_case_nested_or_patterns() {
+        use Arrow::*;
+
+        let axis = |msg: Msg| case!(msg =>
+            | Msg::Key(Up | Down) => "vertical",
+            | Msg::Key(Left | Right) => "horizontal",
+            | Msg::Click(1 | 2, Some(Up | Down) | None) => "primary click",
+            | Msg::Click(button @ (3 | 4), _) if button > 3 => "side click",
+            | Msg::Click(..) | Msg::Idle => "other",
+        );
+        assert_eq!(axis(Msg::Key(Down)), "vertical");
+        assert_eq!(axis(Msg::Key(Left)), "horizontal");
+        assert_eq!(axis(Msg::Click(2, None)), "primary click");
+        assert_eq!(axis(Msg::Click(1, Some(Right))), "other");
+        assert_eq!(axis(Msg::Click(4, Some(Left))), "side click");
+        assert_eq!(axis(Msg::Idle), "other");
+
+        // 逐 token 收集模式的宏与基于 case! 的其他宏同样保留嵌套或模式
+        let (label, value) = case_labeled!(Msg::Key(Right) =>
+            | Msg::Key(Up | Down) # "vertical" => 1,
+            | Msg::Key(Left | Right) # "horizontal" => 2,
+            | _ # "other" => 0,
+        );
+        assert_eq!((label, value), ("horizontal", 2));
+
+        let vertical: Vec<Msg> = [Msg::Key(Up), Msg::Idle, Msg::Key(Left), Msg::Click(1, Some(Down))]
+            .into_iter()
+            .filter(filter_case!(| Msg::Key(Up | Down) | Msg::Click(_, Some(Up | Down))))
+            .collect();
+        assert_eq!(vertical, [Msg::Key(Up), Msg::Click(1, Some(Down))]);
+
+        let moved = transitions!(Arrow::Left, Msg::Key(Up);
+            (Left | Right, Msg::Key(Up | Down)) => Up,
+            (state, _) => state,
+        );
+        assert_eq!(moved, Up);
+    }
+
+    #[derive(Debug, PartialEq)]
+    enum Field {
+        Number(String),
+        Flag(String),
+        Missing,
+    }
+
+    fn parse_field(field: Field) -> Result<i64, String> {
+        let parsed = case!(field =>
+            | Field::Number(text) if text.starts_with('+') => text[1..].parse::<i64>().map_err(|e| e.to_string())?,
+            | Field::Number(text) => text.parse::<i64>().map_err(|e| e.to_string())? * scale,
+            | Field::Flag(text) if parse_flag(&text)? => 1,
+            | Field::Flag(_) => 0,
+            | Field::Missing => return Err(String::from("missing")),
+            where scale = i64::from(u8::try_from(10u32).map_err(|e| e.to_string())?)
+        );
+        Ok(parsed)
+    }
+
+    fn parse_flag(text: &str) -> Result<bool, String> {
+        text.parse::<bool>().map_err(|_| format!("bad flag `{text}`"))
+    }
+
+    fn sum_fields(fields: Vec<Field>) -> Result<i64, String> {
+        let mut total = 0;
+        for field in fields {
+            total += case!(field =>
+                | Field::Missing => continue,
+                | other => parse_field(other)?,
+            );
+        }
+        Ok(total)
+    }
+
+    #[test]
+    fn test_case_question_mark_in_arms() {
+        assert_eq!(parse_field(Field::Number(String::from("+7"))), Ok(7));
+        assert_eq!(parse_field(Field::Number(String::from("4"))), Ok(40));
+        assert_eq!(parse_field(Field::Flag(String::from("true"))), Ok(1));
+        assert_eq!(parse_field(Field::Flag(String::from("false"))), Ok(0));
+        assert_eq!(parse_field(Field::Flag(String::from("yes"))), Err(String::from("bad flag `yes`")));
+        assert!(parse_field(Field::Number(String::from("x"))).is_err());
+        assert_eq!(parse_field(Field::Missing), Err(String::from("missing")));
+
+        assert_eq!(
+            sum_fields(vec![Field::Number(String::from("2")), Field::Missing, Field::Flag(String::from("true"))]),
+            Ok(21)
+        );
+        assert!(sum_fields(vec![Field::Flag(String::from("?"))]).is_err());
+
+        // case_result! 的分支结果同样可以使用 `?`
+        let checked = || -> Result<Result<i64, Field>, String> {
+            Ok(case_result!(Field::Number(String::from("12")) =>
+                | Field::Number(text) => text.parse::<i64>().map_err(|e| e.to_string())?,
+            ))
+        };
+        assert_eq!(checked(), Ok(Ok(12)));
+    }
+
+    #[test]
+    fn test_case_iter() {
+        let words = ["alpha", "", "beta", "gamma"];
+        let select = |query: Option<&'static str>| case_iter!(query =>
+            | None => words.iter().copied(),
+            | Some("") => words.iter().copied().filter(|w| w.is_empty()),
+            | Some(prefix) if prefix.len() > limit => std::iter::empty(),
+            | Some(prefix) => words.iter().copied().filter(move |w| w.starts_with(prefix)),
+            where limit = 3
+        );
+        assert_eq!(select(None).count(), 4);
+        assert_eq!(select(Some("")).collect::<Vec<_>>(), [""]);
+        assert_eq!(select(Some("toolong")).next(), None);
+        assert_eq!(select(Some("g")).collect::<Vec<_>>(), ["gamma"]);
+
+        // 单个分支时不包装；两个分支时为 Left / Right，并保留双端与精确长度
+        let only = case_iter!(3 => | n => 0..n);
+        assert_eq!(only.len(), 3);
+        let mut two = case_iter!(true =>
+            | true => [1, 2, 3].into_iter(),
+            | otherwise => vec![9].into_iter(),
+        );
+        assert_eq!(two.len(), 3);
+        assert_eq!(two.next_back(), Some(3));
+        assert!(matches!(two, crate::Either::Left(_)));
+    }
+
+    #[test]
+    fn test_case_otherwise_in_guard() {
+        // 只有模式位置的 `otherwise` 是兜底分支；guard 中的 `otherwise` 是普通标识符
+        let otherwise = false;
+        let pick = |n: i32| case!(n =>
+            | 0 if otherwise => "guarded by local otherwise",
+            | 0 => "zero",
+            | _ if !otherwise => "negated local",
+            | otherwise => "fallback",
+        );
+        assert_eq!(pick(0), "zero");
+        assert_eq!(pick(9), "negated local");
+
+        {
+            // 导入 crate 的常量后，guard 中的 otherwise 就是 true
+            use super::otherwise;
+            let pick = case!(0 =>
+                | 0 if otherwise => "const otherwise",
+                | _ => "other",
+            );
+            assert_eq!(pick, "const otherwise");
+        }
+
+        struct Flags {
+            otherwise: bool,
+        }
+        let flags = Flags { otherwise: true };
+        let field = case!(Some(1) =>
+            | Some(n) if flags.otherwise && n > 0 => n,
+            | otherwise => -1,
+        );
+        assert_eq!(field, 1);
+    }
+
+    #[test]
+    fn test_case_reference_ergonomics() {
+        let (a, b) = (0, 5);
+        let pair = case!((&a, &b) =>
+            | (0, 0) => "both zero",
+            | (0, n) if *n > 3 => "zero and big",
+            | (0, _) => "zero and small",
+            | (_, _) => "non-zero",
+        );
+        assert_eq!(pair, "zero and big");
+
+        let name = Some(String::from("hgm"));
+        let len = case!(&name =>
+            | Some(s) if s.is_empty() => 0,
+            | Some(s) => s.len(),
+            | None => 0,
+        );
+        assert_eq!(len, 3);
+        // 默认绑定模式下 s 是 &String，name 没有被移动
+        assert_eq!(name.as_deref(), Some("hgm"));
+
+        let mut counter = Some(1);
+        case!(&mut counter =>
+            | Some(n) => *n += 1,
+            | None => (),
+        );
+        assert_eq!(counter, Some(2));
+    }
+
+    #[derive(Debug, PartialEq)]
+    enum Cell {
+        Counter(u32),
+        Label(String),
+        Pair(i32, i32),
+        Blank,
+    }
+
+    #[test]
+    fn test_case_mut_scrutinee() {
+        let mut cells = vec![
+            Cell::Counter(7),
+            Cell::Label(String::from("x")),
+            Cell::Pair(1, 2),
+            Cell::Blank,
+        ];
+        for cell in cells.iter_mut() {
+            case!(cell =>
+                | Cell::Counter(n) if *n >= limit => *n = 0,
+                | Cell::Counter(n) => *n += 1,
+                | Cell::Label(s) => s.make_ascii_uppercase(),
+                | Cell::Pair(a, b) => std::mem::swap(a, b),
+                | Cell::Blank => {},
+                where limit = 5
+            );
+        }
+        assert_eq!(
+            cells,
+            [Cell::Counter(0), Cell::Label(String::from("X")), Cell::Pair(2, 1), Cell::Blank]
+        );
+
+        // 直接匹配值本身时使用 ref mut，值不会被移动
+        let mut cell = Cell::Counter(3);
+        case!(cell =>
+            | Cell::Counter(ref mut n) => *n *= 2,
+            | _ => {},
+        );
+        assert_eq!(cell, Cell::Counter(6));
+
+        // #[over] 的类型检查分支同样只借用，不影响之后的可变访问
+        let replaced = case!(#[over Cell] &mut cell =>
+            | Cell::Counter(n) => std::mem::replace(n, 1),
+            | Cell::Label(_) | Cell::Pair(..) | Cell::Blank => 0,
+        );
+        assert_eq!((replaced, &cell), (6, &Cell::Counter(1)));
+    }
+
+    #[test]
+    fn test_case_all() {
+        let port: Option<u16> = Some(0);
+        let host = "localhost";
+        let result = case_all!(
+            port => [| Some(0) | None => 8080, | Some(p) => p],
+            host => [| "localhost" => "127.0.0.1", | h => h],
+        );
+        assert_eq!(result, (8080, "127.0.0.1"));
+    }
+
+    #[derive(Debug, Clone, Copy, PartialEq)]
+    enum Player {
+        Idle,
+        Running,
+        Paused,
+    }
+
+    #[derive(Debug, Clone, Copy)]
+    enum Command {
+        Start,
+        Pause,
+        Stop,
+    }
+
+    #[test]
+    fn test_transitions() {
+        use Command::*;
+        use Player::*;
+
+        let next = |state: Player, command: Command| {
+            transitions!(state, command;
+                (Idle, Start) | (Paused, Start) => Running,
+                (Running, Pause) => Paused,
+                (Running | Paused, Stop) => Idle,
+                _ => state,
+            )
+        };
+        assert_eq!(next(Idle, Start), Running);
+        assert_eq!(next(Running, Pause), Paused);
+        assert_eq!(next(Paused, Start), Running);
+        assert_eq!(next(Paused, Stop), Idle);
+        // 未定义的转移保持原状态
+        assert_eq!(next(Idle, Pause), Idle);
+    }
+
+    #[test]
+    fn test_first_matching() {
+        use super::{first_matching, Rule};
+
+        let mut rules: Vec<Rule<str, u8>> = vec![
+            (|s| s.is_empty(), 0),
+            (|s| s.starts_with('#'), 1),
+        ];
+        assert_eq!(first_matching("", &rules), Some(&0));
+        assert_eq!(first_matching("# title", &rules), Some(&1));
+        assert_eq!(first_matching("text", &rules), None);
+
+        // 规则可以在运行时追加，顺序决定优先级
+        rules.push((|_| true, 2));
+        assert_eq!(first_matching("text", &rules), Some(&2));
+        assert_eq!(first_matching::<str, u8>("text", &[]), None);
+    }
+
+    #[test]
+    fn test_lex_case() {
+        let kind = |c: char| {
+            lex_case!(c =>
+                | '0'..='9' => 'd',
+                | 'a'..='z' | 'A'..='Z' => 'w',
+                | ' ' | '\t' => 's',
+                | _ => '?',
+            )
+        };
+        let kinds: String = "a1 \t+Z".chars().map(kind).collect();
+        assert_eq!(kinds, "wdss?w");
+
+        let bytes = b"12;";
+        let digits = bytes
+            .iter()
+            .filter(|&&b| lex_case!(b => | b'0'..=b'9' => true, | _ => false))
+            .count();
+        assert_eq!(digits, 2);
+    }
+
+    #[test]
+    fn test_map_filter_case() {
+        let items = vec![Ok(1), Err("bad"), Ok(20), Ok(-3)];
+        let result: Vec<i32> = items
+            .into_iter()
+            .filter(filter_case!(| Ok(n) if *n > 0, | Err(_)))
+            .map(map_case!(
+                | Ok(n) => n * 2,
+                | Err(_) => 0,
+            ))
+            .collect();
+        assert_eq!(result, vec![2, 0, 40]);
+    }
+}
+
+#[cfg(test)]
+mod hgm {
+    #[test]
+    fn macros_otherwise_const() {
+        let score = 10;
+        let tier: Result<char, ()> = guard_result!(
+            | score > 50 => 'A',
+            | otherwise => 'B',
+            err = (),
+        );
+        assert_eq!(tier, Ok('B'));
+
+        // otherwise 不在最后一个分支时同样按 `true` 处理，其后的分支永远不会被检查并产生警告
+        #[allow(unused_must_use)]
+        let result = guard!(
+            | score < 0 => "negative",
+            | otherwise => "fallback",
+            | score > 0 => "positive",
+        );
+        assert_eq!(result, "fallback");
+    }
+
+    #[test]
+    fn macros_guard() {
+        let weight = 70.0;
+        let height = 1.75;
+        let result = guard! {
+            | bmi <= 18.5 => "Underweight",
+            | otherwise => "Unknown",
+            where bmi = weight / height
+        };
+        assert_eq!(result, "Unknown");
+    }
+    fn_guard! {
+        #[inline]
+        pub fn bmi_tell(weight:f64,height:f64) -> &'static str
+        | bmi <= 18.5 => "Underweight",
+        | otherwise => "Unknown",
+        where bmi = weight / height
+    }
+    struct Reading {
+        value: i32,
+    }
+
+    impl std::fmt::Debug for Reading {
+        fn_guard! {
+            fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result
+            | self.value < 0 => write!(f, "Reading(invalid)"),
+            | otherwise => {
+                write!(f, "Reading({})", self.value)?;
+                Ok(())
+            },
+        }
+    }
+
+    impl Reading {
+        fn_guard! {
+            pub fn bump(&mut self, by: i32)
+            | by > 0 => self.value += by,
+            | otherwise => (),
+        }
+    }
+
+    fn_guard!(
+        fn strictly_positive(n: i32) -> i32
+        | n > 0 => n,
+    );
+
+    /// 捕获当前线程中 panic 的位置（文件与行号）
+    fn panic_location(f: impl FnOnce() + std::panic::UnwindSafe) -> (String, u32) {
+        use std::sync::{Arc, Mutex};
+
+        let location = Arc::new(Mutex::new(None));
+        let thread = std::thread::current().id();
+        let previous = std::panic::take_hook();
+        let recorded = Arc::clone(&location);
+        std::panic::set_hook(Box::new(move |info| {
+            if std::thread::current().id() == thread {
+                let loc = info.location().unwrap();
+                *recorded.lock().unwrap() = Some((loc.file().to_string(), loc.line()));
+            } else {
+                previous(info);
+            }
+        }));
+        let result = std::panic::catch_unwind(f);
+        drop(std::panic::take_hook());
+        assert!(result.is_err());
+        location.lock().unwrap().take().unwrap()
+    }
+
+    #[test]
+    fn macros_guard_panic_location() {
+        let x = 0;
+        let (file, line) = panic_location(|| {
+            guard!(| x > 0 => ());
+        });
+        assert_eq!((file.as_str(), line), (file!(), line!() - 2));
+
+        let (file, line) = panic_location(|| {
+            strictly_positive(-1);
+        });
+        assert_eq!((file.as_str(), line), (file!(), line!() - 2));
+    }
+
+    #[test]
+    fn macros_fn_guard_method() {
+        let mut reading = Reading { value: 1 };
+        reading.bump(2);
+        reading.bump(-5);
+        assert_eq!(format!("{reading:?}"), "Reading(3)");
+        assert_eq!(format!("{:?}", Reading { value: -1 }), "Reading(invalid)");
+    }
+
+    fn_guard! {
+        #[document_arms]
+        #[must_use]
+        fn grade(score: u32) -> char
+        | score >= 90 => 'A',
+        | score >= 60 => 'B',
+        | otherwise => 'C',
+        where score = score.min(100),
+    }
+
+    fn_guard! {
+        #[document_arms]
+        fn record(log: &mut Vec<&'static str>, n: i32)
+        | n < 0 => log.push("negative"),
+        | otherwise => log.push("non-negative"),
+    }
+
+    #[test]
+    fn macros_fn_guard_document_arms() {
+        assert_eq!(grade(95), 'A');
+        assert_eq!(grade(250), 'A');
+        assert_eq!(grade(10), 'C');
+
+        let mut log = Vec::new();
+        record(&mut log, -1);
+        assert_eq!(log, ["negative"]);
+    }
+
+    fn_guard! {
+        fn pick<const N: usize>(a: [u8; N]) -> u8
+        | N == 0 => 0,
+        | otherwise => a[0],
+    }
+
+    fn_guard! {
+        pub(crate) fn longer<'a, T: PartialOrd + Copy>(a: &'a [T], b: &'a [T]) -> &'a [T]
+        | a.len() >= b.len() => a,
+        | otherwise => b,
+    }
+
+    #[test]
+    fn macros_fn_guard_generics() {
+        assert_eq!(pick([]), 0);
+        assert_eq!(pick([4, 5, 6]), 4);
+        assert_eq!(longer(&[1], &[2, 3]), &[2, 3]);
+        assert_eq!(longer(&["a", "b"], &["c"]), &["a", "b"]);
+    }
+
+    fn_guard! {
+        #[statements]
+        fn checked_ratio(a: i32, b: i32) -> Option<i32>
+        | b == 0 => return None,
+        | ratio < 0 => return Some(0),
+        | otherwise => Some(ratio),
+        where ratio = if b == 0 { 0 } else { a / b },
+    }
+
+    fn_guard! {
+        #[statements]
+        fn collect_tags(tags: &mut Vec<&'static str>, n: u32)
+        | n.is_multiple_of(2) => tags.push("even"),
+        | n.is_multiple_of(3) => tags.push("triple"),
+        | n > 100 => return,
+        | n > 10 => tags.push("large"),
+    }
+
+    fn_guard! {
+        #[statements]
+        fn audit(log: &mut Vec<&'static str>, n: u32)
+        | n == 0 => return,
+        | otherwise => log.push("seen"),
+        | n > 10 => log.push("large"),
+    }
+
+    #[derive(Debug, PartialEq)]
+    enum PortError {
+        Reserved(u16),
+        Invalid,
+    }
+
+    fn_guard!(
+        #[result]
+        fn parse_port(text: &str) -> Result<u16, PortError>
+        | let Ok(port) = text.parse::<u16>() && port < 1024 => return Err(PortError::Reserved(port)),
+        | let Ok(port) = text.parse::<u16>() => port,
+        | text == "http" => 80,
+        | otherwise => Err(PortError::Invalid),
+    );
+
+    #[test]
+    fn macros_fn_guard_result() {
+        assert_eq!(parse_port("8080"), Ok(8080));
+        assert_eq!(parse_port("http"), Ok(80));
+        assert_eq!(parse_port("22"), Err(PortError::Reserved(22)));
+        assert_eq!(parse_port("ftp"), Err(PortError::Invalid));
+    }
+
+    #[test]
+    fn macros_fn_guard_statements() {
+        assert_eq!(checked_ratio(1, 0), None);
+        assert_eq!(checked_ratio(-9, 3), Some(0));
+        assert_eq!(checked_ratio(9, 3), Some(3));
+
+        // 分支互不排斥：所有条件为真的分支依次执行，直到遇到 return
+        let mut tags = Vec::new();
+        collect_tags(&mut tags, 12);
+        assert_eq!(tags, ["even", "triple", "large"]);
+        tags.clear();
+        collect_tags(&mut tags, 102);
+        assert_eq!(tags, ["even", "triple"]);
+
+        // 写在中间的 otherwise 总是执行，其后的分支照常检查
+        let mut log = Vec::new();
+        audit(&mut log, 0);
+        audit(&mut log, 3);
+        audit(&mut log, 30);
+        assert_eq!(log, ["seen", "seen", "large"]);
+    }
+
+    async fn load_limit(key: &str) -> u32 {
+        core::future::ready(key.len() as u32 * 10).await
+    }
+
+    fn_guard!(
+        async fn within_limit(key: &str, value: u32) -> &'static str
+        | value == 0 => "empty",
+        | value <= limit => "ok",
+        | otherwise => "over",
+        where limit = load_limit(key).await
+    );
+
+    fn block_on<F: core::future::Future>(future: F) -> F::Output {
+        use core::task::{Context, Poll, Waker};
+
+        let mut future = core::pin::pin!(future);
+        let mut cx = Context::from_waker(Waker::noop());
+        loop {
+            if let Poll::Ready(output) = future.as_mut().poll(&mut cx) {
+                return output;
+            }
+        }
+    }
+
+    #[cfg(feature = "std")]
+    thread_local! {
+        static FIB_EVALS: std::cell::Cell<u32> = const { std::cell::Cell::new(0) };
+    }
+
+    #[cfg(feature = "std")]
+    fn count_fib_eval() {
+        FIB_EVALS.with(|evals| evals.set(evals.get() + 1));
+    }
+
+    #[cfg(feature = "std")]
+    fn_guard!(
+        #[memoize]
+        fn memo_fib(n: u32) -> u64
+        | n < 2 => u64::from(n),
+        | otherwise => memo_fib(n - 1) + memo_fib(n - 2),
+        where _evaluated = count_fib_eval()
+    );
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn macros_fn_guard_memoize() {
+        assert_eq!(memo_fib(40), 102_334_155);
+        // 每个 n 只求值一次
+        assert_eq!(FIB_EVALS.with(|evals| evals.get()), 41);
+
+        assert_eq!(memo_fib(40), 102_334_155);
+        assert_eq!(memo_fib(20), 6_765);
+        assert_eq!(FIB_EVALS.with(|evals| evals.get()), 41);
+    }
+
+    #[test]
+    fn macros_fn_guard_async_where() {
+        assert_eq!(block_on(within_limit("abc", 0)), "empty");
+        assert_eq!(block_on(within_limit("abc", 30)), "ok");
+        assert_eq!(block_on(within_limit("abc", 31)), "over");
+    }
+
+    #[test]
+    fn macros_fn_guard() {
+        let result = bmi_tell(70.0, 1.75);
+        assert_eq!(result, "Unknown");
+    }
+
+    #[test]
+    fn macros_guard_on() {
+        use std::cell::Cell;
+
+        let calls = Cell::new(0);
+        let measure = || {
+            calls.set(calls.get() + 1);
+            -7
+        };
+        let result = guard! {
+            on x = measure();
+            | x > 0 => x,
+            | x == 0 => 0,
+            | otherwise => -x,
+        };
+        assert_eq!(result, 7);
+        assert_eq!(calls.get(), 1);
+
+        let y = 3;
+        let result = guard!(on y; | y > 5 => "big", | otherwise => "small");
+        assert_eq!(result, "small");
+    }
+
+    #[test]
+    fn macros_guard_on_destructure() {
+        struct Size {
+            width: u32,
+            height: u32,
+        }
+
+        let size = Size {
+            width: 1920,
+            height: 1080,
+        };
+        let orientation = guard!(
+            on Size { width, height } = size;
+            | width == height => "square",
+            | width > height => "landscape",
+            | otherwise => "portrait",
+        );
+        assert_eq!(orientation, "landscape");
+
+        let shape = guard!(
+            on (w, h) = (3, 3);
+            | w == h => w * h,
+            | otherwise => 0,
+        );
+        assert_eq!(shape, 9);
+    }
+
+    #[test]
+    #[cfg(feature = "std")]
+    fn macros_guard_result_message() {
+        fn parse_level(level: u8) -> Result<&'static str, Box<dyn std::error::Error>> {
+            guard_result!(
+                | level == 0 => "debug",
+                | level == 1 => "info",
+                err_box = "unknown level",
+            )
+        }
+        assert_eq!(parse_level(1).unwrap(), "info");
+        assert_eq!(parse_level(9).unwrap_err().to_string(), "unknown level");
+    }
+
+    #[test]
+    fn macros_guard_result_infers_without_annotation() {
+        // `err = ...` 的含义与是否启用 std 无关，不标注类型也能推断
+        let n = 3;
+        let message = guard_result!(| n > 5 => 1, err = "small");
+        assert_eq!(message, Err("small"));
+        let code = guard_result!(| n > 5 => 1, err = 42);
+        assert_eq!(code, Err(42));
+    }
+
+    #[test]
+    fn macros_guard_result_otherwise_and_where() {
+        // 不导入 hgm::otherwise 也能使用字面量 otherwise 分支
+        let fallback: Result<i32, ()> = guard_result!(| false => 1, | otherwise => 0, err = ());
+        assert_eq!(fallback, Ok(0));
+
+        let port = |raw: &str| -> Result<u16, String> {
+            guard_result!(
+                | let Some(rest) = digits.strip_prefix(':') && !rest.is_empty() => rest.parse().map_err(|_| raw.to_string())?,
+                | let Ok(port) = digits.parse::<u16>() => port,
+                err = format!("bad port: {raw}"),
+                where digits = raw.trim()
+            )
+        };
+        assert_eq!(port(":8080"), Ok(8080));
+        assert_eq!(port(" 443 "), Ok(443));
+        assert_eq!(port("http"), Err("bad port: http".to_string()));
+        assert_eq!(port(":99999"), Err(":99999".to_string()));
+    }
+
+    #[test]
+    fn macros_guard_result() {
+        let code = 7;
+        let explicit: Result<&str, u16> = guard_result!(
+            | code < 5 => "low",
+            err = 400 + code,
+        );
+        assert_eq!(explicit, Err(407));
+    }
+
+    #[test]
+    fn macros_guard_numeric() {
+        let classify = |n: i64| {
+            guard!(
+                on sign = n.signum();
+                | sign == 0 => "zero",
+                | n.abs() < 10 && sign < 0 => "small negative",
+                | n.abs() < 10 => "small positive",
+                | sign < 0 => "large negative",
+                | otherwise => "large positive",
+            )
+        };
+        assert_eq!(classify(0), "zero");
+        assert_eq!(classify(-3), "small negative");
+        assert_eq!(classify(9), "small positive");
+        assert_eq!(classify(-100), "large negative");
+        assert_eq!(classify(100), "large positive");
+
+        let x = -2.5_f64;
+        let result = guard!(
+            | m < 1.0 => "unit",
+            | otherwise => "scaled",
+            where m = x.abs(),
+        );
+        assert_eq!(result, "scaled");
+    }
+
+    #[test]
+    fn macros_guard_flow() {
+        use std::ops::ControlFlow;
+
+        let find_negative = |items: &[i32]| {
+            items.iter().try_fold(0usize, |seen, &n| {
+                guard_flow!(
+                    | n < 0 => ControlFlow::Break(seen),
+                    | otherwise => ControlFlow::Continue(seen + 1),
+                )
+            })
+        };
+        assert_eq!(find_negative(&[1, 2, -3, 4]), ControlFlow::Break(2));
+        assert_eq!(find_negative(&[1, 2]), ControlFlow::Continue(2));
+    }
+
+    #[test]
+    fn macros_guard_first_ok() {
+        use std::cell::RefCell;
+
+        let tried = RefCell::new(Vec::new());
+        let attempt = |name: &'static str, value: Option<i32>| {
+            tried.borrow_mut().push(name);
+            value
+        };
+        let n = 4;
+        let result = guard_first_ok!(
+            | n > 0 => attempt("first", None),
+            | n > 10 => attempt("skipped", Some(0)),
+            | n % 2 == 0 => attempt("even", Some(n / 2)),
+            | otherwise => attempt("fallback", Some(-1)),
+        );
+        assert_eq!(result, Some(2));
+        assert_eq!(*tried.borrow(), ["first", "even"]);
+
+        let result: Result<i32, &str> = guard_first_ok!(
+            | n > 0 => Err("first"),
+            | n > 1 => Err("second"),
+        );
+        assert_eq!(result, Err("second"));
+    }
+
+    #[test]
+    #[should_panic(expected = "Non-exhaustive guards")]
+    fn macros_guard_first_ok_no_eligible_arm() {
+        let n = 0;
+        let _: Option<i32> = guard_first_ok!(| n > 0 => Some(n));
+    }
+
+    #[test]
+    fn macros_guard_expression_positions() {
+        // match 分支体
+        let describe = |v: Option<i32>| match v {
+            Some(n) => guard!(| n < 0 => "negative", | otherwise => "non-negative"),
+            None => "missing",
+        };
+        assert_eq!(describe(Some(-1)), "negative");
+        assert_eq!(describe(Some(4)), "non-negative");
+        assert_eq!(describe(None), "missing");
+
+        // 闭包体与方法调用
+        let sign = |n: i32| guard!(| n > 0 => 1, | n < 0 => -1, | otherwise => 0);
+        let signs: Vec<i32> = [3, -2, 0].into_iter().map(sign).collect();
+        assert_eq!(signs, [1, -1, 0]);
+        assert_eq!(guard!(| signs.is_empty() => "none", | otherwise => "some").len(), 4);
+
+        // ? 链
+        fn halve(n: u32) -> Option<u32> {
+            let half = guard!(| n.is_multiple_of(2) => Some(n / 2), | otherwise => None)?;
+            Some(half + 1)
+        }
+        assert_eq!(halve(8), Some(5));
+        assert_eq!(halve(7), None);
+    }
+
+    #[test]
+    fn macros_guard_let_chains() {
+        let classify = |a: Option<i32>, b: Result<u8, ()>| {
+            guard!(
+                | let Some(x) = a && x > 0 => x,
+                | let Some(x) = a && let Ok(y) = b => x + i32::from(y),
+                | let Ok(y) = b => i32::from(y) * 100,
+                | otherwise => base,
+                where base = -1,
+            )
+        };
+        assert_eq!(classify(Some(5), Err(())), 5);
+        assert_eq!(classify(Some(-5), Ok(2)), -3);
+        assert_eq!(classify(None, Ok(2)), 200);
+        assert_eq!(classify(Some(-5), Err(())), -1);
+
+        let arms = guard_debug_arms!(| let Some(x) = opt && x > 1 => x, | otherwise => 0);
+        assert_eq!(arms, ["let Some(x) = opt && x > 1", "otherwise"]);
+    }
+
+    #[test]
+    fn macros_guard_each() {
+        use std::cell::RefCell;
+
+        let ran = RefCell::new(Vec::new());
+        let log = |arm: &'static str| ran.borrow_mut().push(arm);
+        let run = |n: i32| {
+            ran.borrow_mut().clear();
+            guard_each!(
+                | n > 0 => log("positive"),
+                | n % 2 == 0 => log("even"),
+                | let Some(half) = n.checked_div(2) && half > limit => log("large"),
+                | otherwise => log("done"),
+                where limit = 10,
+            );
+            ran.borrow().clone()
+        };
+        assert_eq!(run(4), ["positive", "even", "done"]);
+        assert_eq!(run(-3), ["done"]);
+        assert_eq!(run(30), ["positive", "even", "large", "done"]);
+
+        let unit: () = guard_each!(| run(1).is_empty() => log("never"));
+        assert_eq!(unit, ());
+        assert_eq!(*ran.borrow(), ["positive", "done"]);
+    }
+
+    #[test]
+    fn macros_guard_iter() {
+        use std::cell::Cell;
+
+        let evaluated = Cell::new(0);
+        let expensive = |value: u32| {
+            evaluated.set(evaluated.get() + 1);
+            value * 10
+        };
+        let x: u32 = 7;
+        let mut results = guard_iter!(
+            | x > 5 => expensive(1),
+            | x.is_multiple_of(2) => expensive(2),
+            | let Some(y) = x.checked_sub(3) => expensive(y),
+            | otherwise => expensive(5),
+        );
+        assert_eq!(evaluated.get(), 0);
+        assert_eq!(results.by_ref().take(2).collect::<Vec<_>>(), [10, 40]);
+        assert_eq!(evaluated.get(), 2);
+        assert_eq!(results.next(), Some(50));
+        assert_eq!(results.next(), None);
+
+        assert_eq!(guard_iter!(| x == 0 => 1, | x > 100 => 2).count(), 0);
+    }
+
+    #[test]
+    fn macros_guard_long_chain() {
+        let pick = |x: u32| {
+            guard!(
+            | x == 0 => 0, | x == 1 => 2, | x == 2 => 4, | x == 3 => 6, | x == 4 => 8, | x == 5 => 10, | x == 6 => 12, | x == 7 => 14, | x == 8 => 16, | x == 9 => 18,
+            | x == 10 => 20, | x == 11 => 22, | x == 12 => 24, | x == 13 => 26, | x == 14 => 28, | x == 15 => 30, | x == 16 => 32, | x == 17 => 34, | x == 18 => 36, | x == 19 => 38,
+            | x == 20 => 40, | x == 21 => 42, | x == 22 => 44, | x == 23 => 46, | x == 24 => 48, | x == 25 => 50, | x == 26 => 52, | x == 27 => 54, | x == 28 => 56, | x == 29 => 58,
+            | x == 30 => 60, | x == 31 => 62, | x == 32 => 64, | x == 33 => 66, | x == 34 => 68, | x == 35 => 70, | x == 36 => 72, | x == 37 => 74, | x == 38 => 76, | x == 39 => 78,
+            | x == 40 => 80, | x == 41 => 82, | x == 42 => 84, | x == 43 => 86, | x == 44 => 88, | x == 45 => 90, | x == 46 => 92, | x == 47 => 94, | x == 48 => 96, | x == 49 => 98,
+            | x == 50 => 100, | x == 51 => 102, | x == 52 => 104, | x == 53 => 106, | x == 54 => 108, | x == 55 => 110, | x == 56 => 112, | x == 57 => 114, | x == 58 => 116, | x == 59 => 118,
+            | x == 60 => 120, | x == 61 => 122, | x == 62 => 124, | x == 63 => 126, | x == 64 => 128, | x == 65 => 130, | x == 66 => 132, | x == 67 => 134, | x == 68 => 136, | x == 69 => 138,
+            | x == 70 => 140, | x == 71 => 142, | x == 72 => 144, | x == 73 => 146, | x == 74 => 148, | x == 75 => 150, | x == 76 => 152, | x == 77 => 154, | x == 78 => 156, | x == 79 => 158,
+            | x == 80 => 160, | x == 81 => 162, | x == 82 => 164, | x == 83 => 166, | x == 84 => 168, | x == 85 => 170, | x == 86 => 172, | x == 87 => 174, | x == 88 => 176, | x == 89 => 178,
+            | x == 90 => 180, | x == 91 => 182, | x == 92 => 184, | x == 93 => 186, | x == 94 => 188, | x == 95 => 190, | x == 96 => 192, | x == 97 => 194, | x == 98 => 196, | x == 99 => 198,
+            | otherwise => 0,
+            )
+        };
+        assert_eq!(pick(0), 0);
+        assert_eq!(pick(57), 114);
+        assert_eq!(pick(99), 198);
+        assert_eq!(pick(100), 0);
+
+        // 批量累积的分支中出现 otherwise 或 let-chain 时语义不变
+        let y: u32 = 6;
+        #[allow(unused_must_use)]
+        let mixed = guard!(
+            | y == 0 => 0, | y == 1 => 1, | y == 2 => 2, | otherwise => 3,
+            | y == 6 => 6, | let Some(z) = y.checked_sub(1) => z, | y == 7 => 7,
+            | y == 8 => 8, | y == 9 => 9, | y == 10 => 10,
+        );
+        assert_eq!(mixed, 3);
+    }
+
+    fn_guard!(
+        fn exit_code(status: &str) -> &'static str
+        | status == "ok" => "0",
+        | status == "todo" => todo!("status {status}"),
+        | status == "exit" => std::process::exit(2),
+        | otherwise => unreachable!("unknown status {status}"),
+    );
+
+    #[test]
+    fn macros_diverging_arms() {
+        let n = 3;
+        let text = guard!(
+            | n < 0 => panic!("negative"),
+            | n == 0 => unreachable!(),
+            | otherwise => "positive",
+        );
+        assert_eq!(text, "positive");
+
+        let doubled_first = |items: &[u8]| -> u8 {
+            let first = case!(items =>
+                | [] => return 0,
+                | [x, ..] if *x == 0 => unreachable!("zero marker"),
+                | [x, ..] => *x,
+            );
+            first * 2
+        };
+        assert_eq!(doubled_first(&[]), 0);
+        assert_eq!(doubled_first(&[7, 8]), 14);
+
+        let mut total = 0;
+        for i in 0.. {
+            let step = guard!(| i >= 3 => break, | otherwise => i);
+            total += step;
+        }
+        assert_eq!(total, 3);
+
+        let label = guard_into!(-> String;
+            | n > 5 => unreachable!(),
+            | n == 4 => format!("{n}"),
+            | n < 0 => return,
+            | otherwise => "small",
+        );
+        assert_eq!(label, "small");
+
+        assert_eq!(exit_code("ok"), "0");
+        let unknown = std::panic::catch_unwind(|| exit_code("??"));
+        assert!(unknown.is_err());
+    }
+
+    fn is_blank(s: &str) -> bool {
+        s.trim().is_empty()
+    }
+
+    fn is_comment(s: &str) -> bool {
+        s.starts_with('#')
+    }
+
+    #[derive(Debug, PartialEq)]
+    enum Opcode {
+        Nop,
+        Push(u8),
+        Halt,
+    }
+
+    trait FromCode: Sized {
+        type Error;
+
+        fn from_code(code: u8) -> Self;
+        fn try_from_code(code: u8) -> Result<Self, Self::Error>;
+    }
+
+    impl FromCode for Opcode {
+        type Error = u8;
+
+        fn_guard!(
+            fn from_code(code: u8) -> Self
+            | code == 0xff => Self::Halt,
+            | code >= 0x10 => Self::Push(code - 0x10),
+            | otherwise => Self::Nop,
+        );
+
+        fn_guard!(
+            #[result]
+            fn try_from_code(code: u8) -> Result<Self, Self::Error>
+            | code == 0 => Self::Nop,
+            | code == 0xff => Self::Halt,
+            | (0x10..0x20).contains(&code) => Self::Push(code - 0x10),
+            | otherwise => Err(code),
+        );
+    }
+
+    #[test]
+    fn macros_fn_guard_self_constructor() {
+        assert_eq!(Opcode::from_code(0xff), Opcode::Halt);
+        assert_eq!(Opcode::from_code(0x13), Opcode::Push(3));
+        assert_eq!(Opcode::from_code(0x01), Opcode::Nop);
+
+        assert_eq!(Opcode::try_from_code(0), Ok(Opcode::Nop));
+        assert_eq!(Opcode::try_from_code(0x1f), Ok(Opcode::Push(0xf)));
+        assert_eq!(Opcode::try_from_code(0x20), Err(0x20));
+    }
+
+    const VERBOSE: bool = false;
+    static LOG_LINES: std::sync::atomic::AtomicUsize = std::sync::atomic::AtomicUsize::new(0);
+
+    fn_guard!(
+        fn verbose_flag() -> bool
+        | VERBOSE => true,
+        | otherwise => false,
+    );
+
+    fn_guard!(
+        fn log_banner()
+        | VERBOSE => {
+            LOG_LINES.fetch_add(10, std::sync::atomic::Ordering::Relaxed);
+        },
+        | otherwise => {
+            LOG_LINES.fetch_add(1, std::sync::atomic::Ordering::Relaxed);
+        },
+    );
+
+    fn_guard!(
+        fn default_port() -> u16
+        | VERBOSE => base + 1,
+        | otherwise => base,
+        where base = 8000
+    );
+
+    fn_guard!(
+        #[result]
+        fn required_env() -> Result<&'static str, &'static str>
+        | !VERBOSE => "quiet",
+        | otherwise => Err("verbose mode needs no env"),
+    );
+
+    #[cfg(feature = "std")]
+    fn_guard!(
+        #[memoize]
+        fn startup_cost() -> u64
+        | VERBOSE => 0,
+        | otherwise => (1..=20).product::<u64>() % 1_000_003,
+    );
+
+    fn_guard!(
+        #[equations]
+        fn fib_eq(n: u64) -> u64
+        | 0 => 0,
+        | 1 => 1,
+        | n => fib_eq(n - 1) + fib_eq(n - 2),
+    );
+
+    fn_guard!(
+        #[equations]
+        #[must_use]
+        pub(crate) fn zip_len(xs: &[i32], ys: &[i32]) -> usize
+        | ([], _) | (_, []) => 0,
+        | ([_, xs @ ..], [_, ys @ ..]) => 1 + zip_len(xs, ys),
+    );
+
+    fn_guard!(
+        #[equations]
+        fn describe_pair(pair: (i32, i32)) -> &'static str
+        | (0, 0) => "origin",
+        | (x, y) if x == y => "diagonal",
+        | (x, _) if x.abs() > limit => "far",
+        | _ => "plane",
+        where limit = 100
+    );
+
+    fn_guard!(
+        const fn page_count(items: usize, per_page: usize) -> usize
+        | per_page == 0 => 0,
+        | items.is_multiple_of(per_page) => items / per_page,
+        | otherwise => items / per_page + 1,
+    );
+
+    fn_guard!(
+        pub const fn clamp_shift<const BITS: u32>(shift: u32) -> u32
+        | shift >= BITS => BITS - 1,
+        | otherwise => shift,
+    );
+
+    fn_guard!(
+        #[result]
+        const fn checked_percent(value: u32) -> Result<u32, &'static str>
+        | value <= 100 => value,
+        | otherwise => Err("over 100"),
+    );
+
+    const PAGES: usize = page_count(101, 10);
+    const SHIFT: u32 = clamp_shift::<8>(12);
+    const PERCENT: Result<u32, &str> = checked_percent(40);
+    const LEVEL: u8 = {
+        let load = 70;
+        guard!(| load > high => 2, | load > low => 1, | otherwise => 0, where high = 90, low = 50)
+    };
+
+    fn_guard!(
+        #[result]
+        fn nested_result(n: Option<i32>) -> Result<i32, i32>
+        | case!(n => | Some(v) => v > 0, | None => false) => case!(n => | Some(v) => v, | None => 0) + 1,
+        | otherwise => Err(guard!(| n.is_none() => 0, | otherwise => -1)),
+    );
+
+    fn_guard!(
+        fn padded_width(text: &str) -> usize
+        | width > max => max,
+        | otherwise => width,
+        where width = {
+            let trimmed = text.trim();
+            let chars = trimmed.chars().count();
+            chars + 2
+        }, max = { let base = 8; base * 2 }
+    );
+
+    struct Layers {
+        base: Vec<u8>,
+        overlay: Vec<u8>,
+        label: String,
+        use_overlay: bool,
+    }
+
+    static EMPTY_LAYER: [u8; 0] = [];
+
+    impl Layers {
+        fn active(&self) -> &[u8] {
+            guard_ref!(-> &[u8];
+                | self.use_overlay && !self.overlay.is_empty() => &self.overlay,
+                | self.base.is_empty() => &EMPTY_LAYER,
+                | otherwise => &self.base,
+            )
+        }
+
+        fn active_mut(&mut self) -> &mut Vec<u8> {
+            guard_ref!(-> &mut Vec<u8>;
+                | self.use_overlay => &mut self.overlay,
+                | otherwise => &mut self.base,
+            )
+        }
+
+        fn_guard!(
+            fn title(&self) -> &str
+            | self.label.is_empty() => "untitled",
+            | otherwise => &self.label,
+        );
+    }
+
+    #[test]
+    fn macros_guard_ref() {
+        let mut layers = Layers {
+            base: vec![1, 2],
+            overlay: Vec::new(),
+            label: String::new(),
+            use_overlay: true,
+        };
+        assert_eq!(layers.active(), [1, 2]);
+        layers.active_mut().push(9);
+        assert_eq!(layers.active(), [9]);
+        layers.use_overlay = false;
+        layers.base.clear();
+        assert!(layers.active().is_empty());
+        assert_eq!(layers.title(), "untitled");
+
+        // 默认的 `&_` 标注：引用来自不同的局部变量，结果取两者中较短的生命周期
+        let (first, second) = (String::from("left"), String::from("right"));
+        let longer = guard_ref!(
+            | first.len() >= second.len() => &first,
+            | otherwise => &second,
+        );
+        assert_eq!(longer, "right");
+    }
+
+    fn_guard!(
+        fn ratio_class(num: Option<i32>, den: Option<i32>) -> Result<&'static str, &'static str>
+        | ratio > 1 => Ok("above"),
+        | ratio == 1 => Ok("equal"),
+        | otherwise => Ok("below"),
+        where Some(n) = num else { return Err("missing numerator") },
+              d = den.unwrap_or(1),
+              Some(ratio) = n.checked_div(d) else { return Err("division by zero") }
+    );
+
+    #[test]
+    fn macros_where_let_else() {
+        assert_eq!(ratio_class(Some(6), Some(3)), Ok("above"));
+        assert_eq!(ratio_class(Some(4), None), Ok("above"));
+        assert_eq!(ratio_class(Some(1), Some(3)), Ok("below"));
+        assert_eq!(ratio_class(None, Some(3)), Err("missing numerator"));
+        assert_eq!(ratio_class(Some(1), Some(0)), Err("division by zero"));
+
+        // else 块也可以用 continue/break 跳出外层循环，初始化表达式本身可以含 if-else
+        let mut lengths = Vec::new();
+        for entry in ["a=1", "skip", "bb=22", "c="] {
+            let kind = guard!(
+                | value.len() > 1 => ("long", width),
+                | otherwise => ("short", width),
+                where Some((_, value)) = entry.split_once('=') else { continue },
+                      [_, ..] = value.as_bytes() else { continue },
+                      width = if value.len() > 1 { 2 } else { 1 }
+            );
+            lengths.push(kind);
+        }
+        assert_eq!(lengths, [("short", 1), ("long", 2)]);
+
+        let label = case!(3 =>
+            | 0 => "zero",
+            | n if n > limit => "big",
+            | _ => "small",
+            where Ok(limit) = "2".parse::<i32>() else { panic!("bad limit") }
+        );
+        assert_eq!(label, "big");
+    }
+
+    #[test]
+    fn macros_where_block_initializers() {
+        let text = "  hello world  ";
+        let kind = guard!(
+            | words > 3 => "long",
+            | words > 1 => "short",
+            | otherwise => "single",
+            where words = {
+                let trimmed = text.trim();
+                trimmed.split_whitespace().count()
+            }
+        );
+        assert_eq!(kind, "short");
+
+        // 块初始化器之后仍可以有其他绑定，并引用前面的绑定
+        let bucket = case!(text.len() =>
+            | n if n < small => "small",
+            | n if n < large => "medium",
+            | _ => "large",
+            where small = { let half = 5; half * 2 }, large = { let doubled = small * 2; doubled + 1 }
+        );
+        assert_eq!((bucket, text.len()), ("medium", 15));
+
+        let braced = guard!(
+            | total % parity == 0 => "even",
+            | otherwise => "odd",
+            where {
+                total = {
+                    let digits: Vec<u32> = "1234".chars().filter_map(|c| c.to_digit(10)).collect();
+                    digits.iter().sum::<u32>()
+                };
+                parity = { let base = 1; base + 1 };
+            }
+        );
+        assert_eq!(braced, "even");
+
+        assert_eq!(padded_width("abc"), 5);
+        assert_eq!(padded_width("a very long heading line"), 16);
+
+        let found = guard_opt!(
+            | position.is_some() => position.unwrap_or_default(),
+            where position = { let haystack = text.trim(); haystack.find('w') }
+        );
+        assert_eq!(found, Some(6));
+
+        let shifted = compute_then_case!(
+            where offset = { let raw = -3i32; raw.abs() };
+            text.len() as i32 - offset =>
+            | 12 => "twelve",
+            | _ => "other",
+        );
+        assert_eq!(shifted, "twelve");
+    }
+
+    #[cfg(feature = "profile")]
+    #[test]
+    fn macros_guard_profile() {
+        use crate::profile;
+
+        fn_guard!(
+            fn bucket(n: u32) -> u8
+            | n < 10 => 0,
+            | n < 100 => 1,
+            | let Some(root) = n.checked_ilog10() && root < 4 => 2,
+            | otherwise => 3,
+        );
+
+        profile::reset();
+        for n in 0..20_000 {
+            bucket(n);
+        }
+        let stats = profile::snapshot();
+        let counts: Vec<(&str, u64, u64)> = stats.iter().map(|s| (s.arm, s.evaluated, s.matched)).collect();
+        assert_eq!(
+            counts,
+            [
+                ("n < 10", 20_000, 10),
+                ("n < 100", 19_990, 90),
+                ("let Some(root) = n.checked_ilog10() && root < 4", 19_900, 9_900),
+                ("otherwise", 10_000, 10_000),
+            ]
+        );
+        assert!(stats.iter().all(|s| s.site == stats[0].site));
+        assert!(profile::report().contains("`n < 100`: 90/19990 matched"));
+
+        // 不同的调用位置分别统计
+        profile::reset();
+        let _ = guard!(instrument; | std::hint::black_box(true) => 1, | otherwise => 0);
+        let _ = guard!(instrument; | std::hint::black_box(true) => 1, | otherwise => 0);
+        let stats = profile::snapshot();
+        assert_eq!(stats.len(), 2);
+        assert_ne!(stats[0].site, stats[1].site);
+
+        // 普通的 guard! 不插桩，const fn 中照常使用
+        const fn sign(n: i32) -> i32 {
+            guard!(| n < 0 => -1, | n == 0 => 0, | otherwise => 1)
+        }
+        const NEGATIVE: i32 = sign(-4);
+        profile::reset();
+        assert_eq!((NEGATIVE, sign(std::hint::black_box(3))), (-1, 1));
+        assert!(profile::snapshot().is_empty());
+    }
+
+    #[cfg(feature = "tracing")]
+    #[test]
+    fn macros_guard_tracing() {
+        use std::sync::{Arc, Mutex};
+        use tracing::field::{Field, Visit};
+        use tracing::span::{Attributes, Id, Record};
+        use tracing::{Event, Metadata, Subscriber};
+
+        #[derive(Default, Debug, PartialEq)]
+        struct Matched {
+            site: String,
+            arm: u64,
+            condition: String,
+        }
+
+        impl Visit for Matched {
+            fn record_str(&mut self, field: &Field, value: &str) {
+                match field.name() {
+                    "site" => self.site = value.to_string(),
+                    "condition" => self.condition = value.to_string(),
+                    _ => {}
+                }
+            }
+
+            fn record_u64(&mut self, field: &Field, value: u64) {
+                if field.name() == "arm" {
+                    self.arm = value;
+                }
+            }
+
+            fn record_debug(&mut self, _: &Field, _: &dyn std::fmt::Debug) {}
+        }
+
+        /// 只收集事件的最小 subscriber
+        struct Collect(Arc<Mutex<Vec<Matched>>>);
+
+        impl Subscriber for Collect {
+            fn enabled(&self, _: &Metadata<'_>) -> bool {
+                true
+            }
+
+            fn new_span(&self, _: &Attributes<'_>) -> Id {
+                Id::from_u64(1)
+            }
+
+            fn record(&self, _: &Id, _: &Record<'_>) {}
+
+            fn record_follows_from(&self, _: &Id, _: &Id) {}
+
+            fn event(&self, event: &Event<'_>) {
+                let mut matched = Matched::default();
+                event.record(&mut matched);
+                self.0.lock().unwrap().push(matched);
+            }
+
+            fn enter(&self, _: &Id) {}
+
+            fn exit(&self, _: &Id) {}
+        }
+
+        fn_guard!(
+            fn kind(n: i32) -> &'static str
+            | n < 0 => "negative",
+            | let Some(half) = n.checked_div(2) && half > 10 => "large",
+            | otherwise => "small",
+        );
+
+        let events = Arc::new(Mutex::new(Vec::new()));
+        tracing::subscriber::with_default(Collect(events.clone()), || {
+            assert_eq!(kind(-1), "negative");
+            assert_eq!(kind(40), "large");
+            assert_eq!(kind(3), "small");
+            let n = 3;
+            assert_eq!(guard!(instrument; | n > 5 => 1, | n > 2, | n == 0 => 2, | otherwise => 3), 2);
+            // 普通的 guard! 不发出事件，const fn 中照常使用
+            const fn sign(n: i32) -> i32 {
+                guard!(| n < 0 => -1, | n == 0 => 0, | otherwise => 1)
+            }
+            const NEGATIVE: i32 = sign(-4);
+            assert_eq!((NEGATIVE, sign(std::hint::black_box(3))), (-1, 1));
+        });
+        // subscriber 只在闭包内生效
+        assert_eq!(kind(-5), "negative");
+
+        let events = events.lock().unwrap();
+        let fired: Vec<(u64, &str)> = events.iter().map(|e| (e.arm, e.condition.as_str())).collect();
+        assert_eq!(
+            fired,
+            [
+                (0, "n < 0"),
+                (1, "let Some(half) = n.checked_div(2) && half > 10"),
+                (2, "otherwise"),
+                (1, "n > 2 || n == 0"),
+            ]
+        );
+        assert_eq!(events[0].site, events[2].site);
+        assert_ne!(events[0].site, events[3].site);
+        assert!(events[0].site.starts_with("src/lib.rs:"));
+    }
+
+    #[test]
+    fn macros_guard_in_literal_positions() {
+        #[derive(Debug, PartialEq)]
+        struct Config {
+            mode: &'static str,
+            retries: u8,
+        }
+
+        let verbose = true;
+        let attempts = 4;
+
+        let levels = [
+            guard!(| verbose => "debug", | otherwise => "info"),
+            guard!(| attempts > 3 => "warn", | otherwise => "error"),
+        ];
+        assert_eq!(levels, ["debug", "warn"]);
+
+        let config = Config {
+            mode: guard!(| verbose && attempts > 5 => "trace", | verbose => "debug", | otherwise => "quiet"),
+            retries: guard!(| attempts > limit => limit, | otherwise => attempts, where limit = 3),
+        };
+        assert_eq!(config, Config { mode: "debug", retries: 3 });
+
+        let total = u8::saturating_add(
+            guard!(| verbose => 200, | otherwise => 0),
+            guard!(on attempts; | attempts > 2 => 100, | otherwise => attempts),
+        );
+        assert_eq!(total, u8::MAX);
+
+        let pair = (guard!(| verbose => 'v', | otherwise => '-'), guard!(| true => 1));
+        assert_eq!(pair, ('v', 1));
+        assert_eq!(vec![guard!(| verbose => 1, | otherwise => 0); 3], [1, 1, 1]);
+    }
+
+    #[test]
+    fn macros_nested_composition() {
+        let pair = (3, Some(-1));
+
+        // 条件位置：含逗号的 case! / guard! 调用作为完整的布尔表达式
+        let both = guard!(
+            | case!(pair.1 => | Some(n) if n < 0 => true, | _ => false) => "negative payload",
+            | guard!(| pair.0 > 10 => true, | otherwise => false) => "big",
+            | otherwise => "plain",
+        );
+        assert_eq!(both, "negative payload");
+
+        // 结果位置：分支结果本身是嵌套的宏，后面还可以接运算
+        let score = guard!(
+            | pair.0 > 0 => case!(pair.1 => | Some(n) => n, | None => 0) * 10 + guard!(| pair.0 > 2 => 1, | otherwise => 0),
+            | otherwise => 0,
+        );
+        assert_eq!(score, -9);
+
+        // case! 的 guard 与结果中嵌套 guard!
+        let label = case!(pair =>
+            | (n, Some(m)) if guard!(| n > m => true, | otherwise => false) => guard!(
+                | m < 0 => "above negative",
+                | otherwise => "above",
+            ),
+            | (_, _) => "other",
+        );
+        assert_eq!(label, "above negative");
+
+        // 包装结果的变体同样接受嵌套宏及其后的运算
+        let wrapped: Result<i32, ()> = guard_result!(
+            | case!(pair.0 => | 1..=5 => true, | _ => false) => guard!(| pair.0 == 3 => 30, | otherwise => 0) + 1,
+            err = (),
+        );
+        assert_eq!(wrapped, Ok(31));
+        let wide: i64 = guard_into!(-> i64;
+            | pair.0 == 3 => case!(pair.1 => | Some(n) => n, | None => 0),
+            | otherwise => guard!(| pair.0 < 0 => -1i32, | otherwise => 1) * 2,
+        );
+        assert_eq!(wide, -1);
+        let tiers: Vec<u8> = guard_iter!(
+            | guard!(| pair.0 > 1 => true, | otherwise => false) => case!(pair.0 => | 3 => 3, | _ => 0),
+            | true => guard!(| otherwise => 9),
+        )
+        .collect();
+        assert_eq!(tiers, [3, 9]);
+        assert_eq!(nested_result(Some(4)), Ok(5));
+        assert_eq!(nested_result(Some(-4)), Err(-1));
+        assert_eq!(nested_result(None), Err(0));
+    }
+
+    #[derive(Debug, Clone, Copy, PartialEq)]
+    enum Temperature {
+        Freezing,
+        Cold,
+        Mild,
+        Hot,
+    }
+
+    fn_guard!(
+        #[table(TEMPERATURE_TABLE: -20..=40)]
+        #[must_use]
+        const fn temperature(celsius: i8) -> Temperature
+        | celsius <= 0 => Temperature::Freezing,
+        | celsius < 12 => Temperature::Cold,
+        | celsius < 26 => Temperature::Mild,
+        | otherwise => Temperature::Hot,
+    );
+
+    #[test]
+    fn macros_fn_guard_table() {
+        assert_eq!(TEMPERATURE_TABLE.len(), 61);
+        for (offset, &cached) in TEMPERATURE_TABLE.iter().enumerate() {
+            let celsius = -20 + offset as i8;
+            assert_eq!(cached, temperature(celsius), "mismatch at {celsius}");
+        }
+        assert_eq!(TEMPERATURE_TABLE[0], Temperature::Freezing);
+        assert_eq!(TEMPERATURE_TABLE[60], Temperature::Hot);
+    }
+
+    #[cfg(feature = "std")]
+    const GRADE_SAMPLES: [(u32, u32); 5] = [(0, 10), (5, 10), (9, 10), (10, 10), (3, 4)];
+
+    // 生成的测试 `letter_grade::verify_over` 对每个样本调用 letter_grade 并断言不 panic
+    #[cfg(feature = "std")]
+    fn_guard!(
+        #[verify_over(GRADE_SAMPLES)]
+        fn letter_grade(score: u32, total: u32) -> char
+        | score * 10 >= total * 9 => 'A',
+        | score * 2 >= total => 'B',
+        | score * 2 < total => 'C',
+    );
+
+    #[cfg(feature = "std")]
+    fn_guard!(
+        fn sign_name(n: i32) -> &'static str
+        | n > 0 => "positive",
+        | n < 0 => "negative",
+    );
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn macros_fn_guard_verify_over() {
+        assert_eq!(letter_grade(9, 10), 'A');
+        assert_eq!(letter_grade(3, 4), 'B');
+
+        // 漏掉 0 的分段函数：验证报告出没有分支命中的样本
+        let gaps = crate::__private::verify_samples(&[-2, 0, 3], |&n| {
+            sign_name(n);
+        });
+        assert_eq!(gaps, ["0"]);
+
+        let failed = std::panic::catch_unwind(|| {
+            crate::__fn_guard_verify!(sign_name, [1, 0, -1], |n: &i32| {
+                sign_name(*n);
+            });
+        });
+        assert!(failed.is_err());
+    }
+
+    #[test]
+    fn macros_guard_const_context() {
+        assert_eq!(PAGES, 11);
+        assert_eq!(SHIFT, 7);
+        assert_eq!(LEVEL, 1);
+        assert_eq!(PERCENT, Ok(40));
+        // 同样可以在运行时调用
+        assert_eq!(page_count(std::hint::black_box(20), 10), 2);
+        assert_eq!(page_count(5, 0), 0);
+    }
+
+    fn_guard!(
+        fn build_kind(debug_assertions_only: bool) -> &'static str
+        #[cfg(test)]
+        | debug_assertions_only => "test build",
+        #[cfg(not(test))]
+        | debug_assertions_only => "release build",
+        | otherwise => "any build",
+    );
+
+    fn_guard!(
+        #[result]
+        fn cfg_checked(n: i32) -> Result<i32, &'static str>
+        | n < 0 => -n,
+        #[cfg(test)]
+        #[allow(clippy::manual_range_contains)]
+        | n >= 0 && n < 10 => n * 100,
+        #[cfg(any())]
+        | n >= 0 => unreachable!(),
+        | otherwise => Err("too large"),
+    );
+
+    fn_guard!(
+        #[statements]
+        fn cfg_steps(log: &mut Vec<&'static str>, n: u8)
+        | n > 0 => log.push("positive"),
+        #[cfg(test)]
+        | n > 1 => log.push("test only"),
+        #[cfg(not(test))]
+        | n > 1 => log.push("never in tests"),
+    );
+
+    #[test]
+    fn macros_fn_guard_cfg_arms() {
+        assert_eq!(build_kind(true), "test build");
+        assert_eq!(build_kind(false), "any build");
+
+        assert_eq!(cfg_checked(-4), Ok(4));
+        assert_eq!(cfg_checked(3), Ok(300));
+        assert_eq!(cfg_checked(30), Err("too large"));
+
+        let mut log = Vec::new();
+        cfg_steps(&mut log, 2);
+        assert_eq!(log, ["positive", "test only"]);
+
+        // guard! 本身同样接受带属性的分支，包括 let 条件与 where 子句
+        let value = Some(7);
+        let picked = guard!(
+            | value.is_none() => 0,
+            #[cfg(not(test))]
+            | let Some(n) = value => n,
+            #[cfg(test)]
+            | let Some(n) = value && n > limit => n * 2,
+            | otherwise => -1,
+            where limit = 5
+        );
+        assert_eq!(picked, 14);
+        let first = guard!(
+            #[cfg(any())]
+            | true => "removed",
+            | otherwise => "kept",
+        );
+        assert_eq!(first, "kept");
+    }
+
+    #[test]
+    fn macros_fn_guard_equations() {
+        let fibs: Vec<u64> = (0..10).map(fib_eq).collect();
+        assert_eq!(fibs, [0, 1, 1, 2, 3, 5, 8, 13, 21, 34]);
+
+        assert_eq!(zip_len(&[1, 2, 3], &[4, 5]), 2);
+        assert_eq!(zip_len(&[], &[1]), 0);
+
+        assert_eq!(describe_pair((0, 0)), "origin");
+        assert_eq!(describe_pair((3, 3)), "diagonal");
+        assert_eq!(describe_pair((-500, 1)), "far");
+        assert_eq!(describe_pair((1, 2)), "plane");
+    }
+
+    trait Clampable {
+        fn_guard!(
+            fn clamp_within(self, lo: Self, hi: Self) -> Self
+            where Self: PartialOrd + Sized
+            | lo > hi => panic!("empty range"),
+            | self < lo => lo,
+            | self > hi => hi,
+            | otherwise => self,
+        );
+
+        fn_guard!(
+            fn snap_to<const STEP: u8>(self, origin: Self) -> Self
+            where Self: PartialOrd + Sized + Copy + core::ops::Add<Output = Self> + From<u8>
+            | self < limit => origin,
+            | otherwise => origin + Self::from(STEP),
+            where limit = origin + Self::from(STEP / 2)
+        );
+
+        fn_guard!(
+            fn describe_range(&self, lo: &Self, hi: &Self) -> &'static str
+            where Self: PartialOrd
+            | self < lo => "below",
+            | self > hi => "above",
+            | otherwise => "inside",
+        );
+    }
+
+    impl Clampable for i32 {}
+    impl Clampable for f64 {}
+
+    #[derive(Debug, Clone, Copy, PartialEq, PartialOrd)]
+    struct Meters(u32);
+
+    impl core::ops::Add for Meters {
+        type Output = Meters;
+
+        fn add(self, other: Meters) -> Meters {
+            Meters(self.0 + other.0)
+        }
+    }
+
+    impl From<u8> for Meters {
+        fn from(n: u8) -> Meters {
+            Meters(u32::from(n))
+        }
+    }
+
+    impl Clampable for Meters {
+        // 覆盖默认方法时同样可以使用 fn_guard!
+        fn_guard!(
+            fn describe_range(&self, lo: &Self, hi: &Self) -> &'static str
+            | self.0 == 0 => "zero",
+            | self < lo || self > hi => "outside",
+            | otherwise => "inside",
+        );
+    }
+
+    struct Patient {
+        name: String,
+        weight: f64,
+        height: f64,
+    }
+
+    impl Patient {
+        fn_guard!(
+            fn category(&self) -> &str
+            | bmi < 18.5 => "underweight",
+            | bmi < 25.0 => "normal",
+            | bmi < 30.0 => "overweight",
+            | otherwise => &self.name,
+            where bmi = self.weight / (self.height * self.height)
+        );
+
+        // 绑定从 self 复制出所需的值，之后的分支可以修改 self
+        fn_guard!(
+            fn record_weight(&mut self, kg: f64) -> bool
+            | change.abs() < 0.5 => false,
+            | otherwise => {
+                self.weight = kg;
+                true
+            },
+            where change = kg - self.weight
+        );
+    }
+
+    #[test]
+    fn macros_fn_guard_where_uses_self() {
+        let mut patient = Patient { name: "needs review".to_string(), weight: 70.0, height: 1.75 };
+        assert_eq!(patient.category(), "normal");
+
+        assert!(!patient.record_weight(70.2));
+        assert!(patient.record_weight(85.0));
+        assert_eq!(patient.category(), "overweight");
+        assert!(patient.record_weight(120.0));
+        assert_eq!(patient.category(), "needs review");
+    }
+
+    /// 0..=100 的百分比，运算结果饱和到区间端点
+    #[derive(Debug, Clone, Copy, PartialEq)]
+    struct Percent(u8);
+
+    impl core::ops::Add for Percent {
+        type Output = Self;
+
+        fn_guard!(
+            fn add(self, rhs: Self) -> Self
+            | sum > 100 => Percent(100),
+            | otherwise => Percent(u8::try_from(sum).unwrap()),
+            where sum = u16::from(self.0) + u16::from(rhs.0)
+        );
+    }
+
+    impl core::ops::Sub for Percent {
+        type Output = Percent;
+
+        fn_guard!(
+            fn sub(self, rhs: Percent) -> Self::Output
+            | rhs.0 >= self.0 => Self(0),
+            | otherwise => Self(self.0 - rhs.0),
+        );
+    }
+
+    impl core::ops::AddAssign for Percent {
+        fn_guard!(
+            #[statements]
+            fn add_assign(&mut self, rhs: Self)
+            | rhs.0 == 0 => return,
+            | otherwise => *self = *self + rhs,
+        );
+    }
+
+    impl core::ops::Mul<u8> for Percent {
+        type Output = Percent;
+
+        fn_guard!(
+            fn mul(self, factor: u8) -> Percent
+            | let Some(product) = self.0.checked_mul(factor) && product <= 100 => Percent(product),
+            | otherwise => Percent(100),
+        );
+    }
+
+    #[cfg(feature = "alloc")]
+    trait Shape {
+        fn area(&self) -> f64;
+    }
+
+    #[cfg(feature = "alloc")]
+    struct Circle(f64);
+    #[cfg(feature = "alloc")]
+    struct Rect(f64, f64);
+
+    #[cfg(feature = "alloc")]
+    impl Shape for Circle {
+        fn area(&self) -> f64 {
+            std::f64::consts::PI * self.0 * self.0
+        }
+    }
+
+    #[cfg(feature = "alloc")]
+    impl Shape for Rect {
+        fn area(&self) -> f64 {
+            self.0 * self.1
+        }
+    }
+
+    #[cfg(feature = "alloc")]
+    fn_guard!(
+        #[boxed]
+        fn parse_shape(spec: &str) -> Box<dyn Shape>
+        | let Some(r) = spec.strip_prefix("circle:") => Circle(r.parse().unwrap_or(0.0)),
+        | let Some((w, h)) = spec.split_once('x') => Rect(w.parse().unwrap_or(0.0), h.parse().unwrap_or(0.0)),
+        | spec.is_empty() => panic!("empty shape spec"),
+        | otherwise => Rect(side, side),
+        where side = spec.parse().unwrap_or(1.0)
+    );
+
+    // `impl` 之后的多个约束（含生命周期约束）
+    #[cfg(feature = "alloc")]
+    fn_guard!(
+        #[boxed]
+        fn shown(text: &'static str, n: usize) -> impl std::fmt::Display + Send + 'static
+        | n == 0 => text,
+        | otherwise => format!("{text} x{n}"),
+    );
+
+    #[cfg(feature = "alloc")]
     #[test]
-    fn test_case() {
-        let opt = Some(42);
-        let result = case!(opt =>
-            | None => 0,
-            | Some(x) if x > 50 => 1,
-            | Some(_) => 2,
+    fn macros_fn_guard_boxed() {
+        assert!((parse_shape("circle:1").area() - std::f64::consts::PI).abs() < 1e-9);
+        assert!((parse_shape("2x3").area() - 6.0).abs() < 1e-9);
+        assert!((parse_shape("4").area() - 16.0).abs() < 1e-9);
+        assert!(std::panic::catch_unwind(|| parse_shape("")).is_err());
+        assert_eq!(shown("hi", 0).to_string(), "hi");
+        assert_eq!(shown("hi", 3).to_string(), "hi x3");
+    }
+
+    #[test]
+    fn macros_fn_guard_operator_impls() {
+        assert_eq!(Percent(40) + Percent(35), Percent(75));
+        assert_eq!(Percent(80) + Percent(90), Percent(100));
+        assert_eq!(Percent(30) - Percent(50), Percent(0));
+        assert_eq!(Percent(30) - Percent(5), Percent(25));
+        assert_eq!(Percent(30) * 3, Percent(90));
+        assert_eq!(Percent(60) * 5, Percent(100));
+
+        let mut total = Percent(10);
+        total += Percent(0);
+        total += Percent(95);
+        assert_eq!(total, Percent(100));
+    }
+
+    #[test]
+    fn macros_fn_guard_trait_defaults() {
+        assert_eq!(15.clamp_within(0, 10), 10);
+        assert_eq!((-3).clamp_within(0, 10), 0);
+        assert_eq!(2.5f64.clamp_within(0.0, 1.0), 1.0);
+        assert_eq!(Meters(7).clamp_within(Meters(1), Meters(5)), Meters(5));
+
+        assert_eq!(12.snap_to::<10>(10), 10);
+        assert_eq!(16.snap_to::<10>(10), 20);
+        assert_eq!(Meters(9).snap_to::<4>(Meters(4)), Meters(8));
+
+        assert_eq!(3.describe_range(&5, &9), "below");
+        assert_eq!(7.0f64.describe_range(&5.0, &9.0), "inside");
+        assert_eq!(Meters(0).describe_range(&Meters(1), &Meters(2)), "zero");
+        assert_eq!(Meters(3).describe_range(&Meters(1), &Meters(2)), "outside");
+    }
+
+    #[derive(Debug, PartialEq)]
+    enum Shipping {
+        Free,
+        Express,
+        Standard,
+    }
+
+    named_guard!(
+        fn shipping(total_cents: u32, express: bool) -> Shipping {
+            express: express => Shipping::Express,
+            free: total_cents >= FREE_SHIPPING_CENTS => Shipping::Free,
+            _ => Shipping::Standard,
+        }
+    );
+
+    const FREE_SHIPPING_CENTS: u32 = 5_000;
+
+    named_guard!(
+        pub(crate) fn quadrant(x: i32, y: i32) -> u8 {
+            first: x > 0 && y > 0 => 1,
+            second: x < 0 && y > 0 => 2,
+            third: x < 0 && y < 0 => 3,
+            fourth: x > 0 && y < 0 => 4,
+        }
+    );
+
+    #[test]
+    fn macros_named_guard() {
+        assert_eq!(shipping(1_000, true), Shipping::Express);
+        assert_eq!(shipping(9_000, false), Shipping::Free);
+        assert_eq!(shipping(1_000, false), Shipping::Standard);
+
+        // 每个条件可以单独测试，不必关心分支顺序
+        assert!(shipping::free(9_000, true));
+        assert!(!shipping::free(4_999, false));
+        assert!(shipping::express(0, true));
+
+        assert_eq!(quadrant(3, -2), 4);
+        assert!(quadrant::second(-1, 1));
+        assert!(!quadrant::first(0, 5));
+        assert!(std::panic::catch_unwind(|| quadrant(0, 0)).is_err());
+    }
+
+    struct Inventory {
+        shelves: Vec<Vec<u32>>,
+    }
+
+    impl Inventory {
+        fn_guard!(
+            fn heaviest_on(&self, shelf: usize) -> Option<u32>
+            | items.is_empty() => None,
+            | items.len() == 1 => Some(items[0]),
+            | *items.iter().max()? > limit => Some(limit),
+            | otherwise => items.iter().copied().max(),
+            where items = self.shelves.get(shelf)?, limit = 100
+        );
+
+        // 没有 otherwise：兜底的 panic! 与 Option 返回类型统一
+        fn_guard!(
+            fn first_on(&self, shelf: usize) -> Option<u32>
+            | shelf < self.shelves.len() => Some(*self.shelves[shelf].first()?),
         );
-        assert_eq!(result, 2);
     }
-}
 
-#[cfg(test)]
-mod hgm {
-    use super::*;
+    fn_guard!(
+        #[statements]
+        fn parse_pair(text: &str) -> Result<(i32, i32), std::num::ParseIntError>
+        | text.is_empty() => return Ok((0, 0)),
+        | otherwise => {
+            let (a, b) = text.split_once(',').unwrap_or((text, "0"));
+            return Ok((a.trim().parse()?, b.trim().parse()?));
+        },
+    );
 
     #[test]
-    fn macros_guard() {
-        let weight = 70.0;
-        let height = 1.75;
+    fn macros_fn_guard_question_mark() {
+        let inventory = Inventory { shelves: vec![vec![], vec![7], vec![3, 250, 9], vec![4, 8]] };
+        assert_eq!(inventory.heaviest_on(0), None);
+        assert_eq!(inventory.heaviest_on(1), Some(7));
+        assert_eq!(inventory.heaviest_on(2), Some(100));
+        assert_eq!(inventory.heaviest_on(3), Some(8));
+        assert_eq!(inventory.heaviest_on(9), None);
+
+        assert_eq!(inventory.first_on(3), Some(4));
+        assert_eq!(inventory.first_on(0), None);
+        assert!(std::panic::catch_unwind(|| inventory.first_on(9)).is_err());
+
+        assert_eq!(parse_pair(""), Ok((0, 0)));
+        assert_eq!(parse_pair("3, -4"), Ok((3, -4)));
+        assert_eq!(parse_pair("5"), Ok((5, 0)));
+        assert!(parse_pair("x,1").is_err());
+    }
+
+    fn_guard!(
+        fn normalized_name(name: &str, width: usize) -> String
+        | name.is_empty() => "-".repeat(width),
+        | name.len() > width => name[..width].to_string(),
+        | otherwise => format!("{name:<width$}"),
+        where name = name.trim(),
+              width = width.max(1),
+              name = name.to_lowercase()
+    );
+
+    fn_guard!(
+        #[result]
+        fn checked_offset(offset: i64) -> Result<u32, String>
+        | let Ok(small) = u32::try_from(offset) => small,
+        | otherwise => Err(format!("{offset} is too large")),
+        where offset = offset.abs()
+    );
+
+    #[test]
+    fn macros_fn_guard_where_shadows_parameters() {
+        // 后续的绑定与所有分支都看到遮蔽后的值
+        assert_eq!(normalized_name("  Alice ", 8), "alice   ");
+        assert_eq!(normalized_name("   ", 3), "---");
+        assert_eq!(normalized_name("BARTHOLOMEW", 4), "bart");
+        assert_eq!(normalized_name("", 0), "-");
+
+        assert_eq!(checked_offset(-7), Ok(7));
+        assert_eq!(checked_offset(-5_000_000_000), Err("5000000000 is too large".to_string()));
+    }
+
+    fn_guard!(
+        fn split(n: i32) -> (i32, &'static str)
+        | n < 0 => (-n, "was negative"),
+        | otherwise => (n, "ok")
+    );
+
+    fn_guard!(
+        fn div_rem(n: u32, d: u32) -> Option<(u32, u32)>
+        | d == 0 => None,
+        | otherwise => Some((q, r)),
+        where (q, r) = (n / d.max(1), n % d.max(1))
+    );
+
+    fn_guard!(
+        fn bounds(values: &[i32]) -> (Option<i32>, Option<i32>, usize)
+        | values.is_empty() => (None, None, 0),
+        | values.len() == 1 => (Some(values[0]), Some(values[0]), 1),
+        | otherwise => (values.iter().copied().min(), values.iter().copied().max(), values.len()),
+    );
+
+    #[test]
+    fn macros_fn_guard_tuple_results() {
+        assert_eq!(split(-4), (4, "was negative"));
+        assert_eq!(split(9), (9, "ok"));
+
+        assert_eq!(div_rem(17, 5), Some((3, 2)));
+        assert_eq!(div_rem(1, 0), None);
+
+        assert_eq!(bounds(&[]), (None, None, 0));
+        assert_eq!(bounds(&[7]), (Some(7), Some(7), 1));
+        assert_eq!(bounds(&[3, -1, 8]), (Some(-1), Some(8), 3));
+
+        // guard! 的分支结果同样可以是元组，括号内的逗号不会被当作分支分隔符
+        let n = 12;
+        let (half, parity) = guard!(| n % 2 == 0 => (n / 2, "even"), | otherwise => (n, "odd"));
+        assert_eq!((half, parity), (6, "even"));
+    }
+
+    #[test]
+    fn macros_fn_guard_nullary() {
+        assert!(!verbose_flag());
+        log_banner();
+        log_banner();
+        assert_eq!(LOG_LINES.load(std::sync::atomic::Ordering::Relaxed), 2);
+        assert_eq!(default_port(), 8000);
+        assert_eq!(required_env(), Ok("quiet"));
+        #[cfg(feature = "std")]
+        assert_eq!(startup_cost(), startup_cost());
+    }
+
+    #[test]
+    fn macros_guard_likely_hints() {
+        let plain = |n: i32| guard!(| n < 0 => -1, | n == 0 => 0, | otherwise => 1);
+        let hinted = |n: i32| guard!(
+            | [unlikely] n < 0 => -1,
+            | [unlikely] n == 0 => 0,
+            | otherwise => 1,
+        );
+        for n in -3..=3 {
+            assert_eq!(plain(n), hinted(n));
+        }
+
+        // 条件仍然按顺序求值，先命中的分支优先
+        let evaluated = std::cell::Cell::new(0);
+        let check = |hit: bool| {
+            evaluated.set(evaluated.get() + 1);
+            hit
+        };
+        let first = guard!(| [unlikely] check(false) => 'a', | [likely] check(true) => 'b', | check(true) => 'c');
+        assert_eq!((first, evaluated.get()), ('b', 2));
+
+        // 其他以 guard! 为基础的宏同样接受这些标记
+        let wide: i64 = guard_into!(-> i64; | [likely] first == 'b' => 1i32, | otherwise => 0i32);
+        assert_eq!(wide, 1);
+        let opt = guard_opt!(| [unlikely] wide > 5 => "big");
+        assert_eq!(opt, None);
+        let even = guard!(apply 4; | [likely] |n: i32| n % 2 == 0 => "even", | otherwise => "odd");
+        assert_eq!(even, "even");
+        let chained = guard_chain!(| [unlikely] wide == 0 => 0; | [likely] wide == 1 => 10, | otherwise => 20);
+        assert_eq!(chained, 10);
+        assert_eq!(guard_iter!(| [likely] true => 1, | [unlikely] false => 2).collect::<Vec<_>>(), [1]);
+    }
+
+    #[test]
+    fn macros_first_ok() {
+        let attempts = std::cell::Cell::new(0);
+        let attempt = |result: Result<u8, &'static str>| {
+            attempts.set(attempts.get() + 1);
+            result
+        };
+
+        // 中间的尝试成功：之后的尝试不会执行
+        let middle = first_ok!(attempt(Err("a")), attempt(Ok(2)), attempt(Ok(3)));
+        assert_eq!((middle, attempts.get()), (Ok(2), 2));
+
+        // 全部失败：返回最后一个错误，每个尝试恰好执行一次
+        attempts.set(0);
+        let all_err = first_ok!(attempt(Err("a")), attempt(Err("b")), attempt(Err("c")),);
+        assert_eq!((all_err, attempts.get()), (Err("c"), 3));
+
+        // 也适用于 Option，以及只有一个尝试的情况
+        let env: Option<&str> = None;
+        let fallback: Option<&str> = Some("default");
+        assert_eq!(first_ok!(env, fallback), Some("default"));
+        assert_eq!(first_ok!(env), None);
+    }
+
+    #[test]
+    fn macros_guard_chain() {
+        // 站点规则优先，全局规则兜底
+        let site_rules = |path: &str| guard_opt!(
+            | path == "/admin" => 403,
+            | let Some(rest) = path.strip_prefix("/old/") => 300 + rest.len() as u16,
+        );
+        assert_eq!(site_rules("/admin"), Some(403));
+        assert_eq!(site_rules("/old/abc"), Some(303));
+        assert_eq!(site_rules("/"), None);
+
+        let status = |path: &str, logged_in: bool| guard_chain!(
+            | path == "/admin" && !logged_in => 403,
+            | let Some(rest) = path.strip_prefix("/old/") => 300 + rest.len() as u16;
+            | path.ends_with(".php") => 410;
+            | path.len() > limit => 414,
+            | otherwise => 200,
+            where limit = 16
+        );
+        assert_eq!(status("/admin", false), 403);
+        assert_eq!(status("/admin", true), 200);
+        assert_eq!(status("/old/x", true), 301);
+        assert_eq!(status("/index.php", true), 410);
+        assert_eq!(status("/a/very/long/path/here", true), 414);
+
+        // 后面的组只有在前面都未命中时才会求值
+        let calls = std::cell::Cell::new(0);
+        let probe = |hit: bool| {
+            calls.set(calls.get() + 1);
+            hit
+        };
+        let first = guard_chain!(| probe(true) => 'a'; | probe(true) => 'b', | otherwise => 'c');
+        assert_eq!((first, calls.get()), ('a', 1));
+        let with_default: Option<u8> = guard_opt!(| probe(false) => 1, | otherwise => 2);
+        assert_eq!(with_default, Some(2));
+    }
+
+    #[test]
+    fn macros_guard_apply() {
+        let evaluated = std::cell::Cell::new(0);
+        let next_line = || {
+            evaluated.set(evaluated.get() + 1);
+            "# heading"
+        };
+        let kind = guard!(
+            apply next_line();
+            | is_blank => "blank",
+            | is_comment => "comment",
+            | otherwise => "text",
+        );
+        assert_eq!(kind, "comment");
+        assert_eq!(evaluated.get(), 1);
+
+        let owned = String::from("  ");
+        let kind = guard!(apply owned.as_str(); | is_comment => 1, | is_blank => 2, | otherwise => 3);
+        assert_eq!(kind, 2);
+
+        let shifted = guard!(
+            apply 5;
+            | |n: i32| n > limit => "above",
+            | otherwise => "below",
+            where limit = 3
+        );
+        assert_eq!(shifted, "above");
+    }
+
+    #[test]
+    fn macros_guard_debug() {
+        let (weight, height) = (50.0_f64, 1.8_f64);
+        let outcome = guard_debug!(
+            | bmi < 18.5 => "underweight",
+            | bmi < 25.0 => "normal",
+            | otherwise => "overweight",
+            where squared = height * height, bmi = weight / squared
+        );
+        let plain = guard_debug!(| weight > 60.0 => 'h', | otherwise => 'l');
+
+        #[cfg(feature = "debug")]
+        {
+            let (category, (squared, bmi)) = outcome;
+            assert_eq!(category, "underweight");
+            assert!((squared - 3.24).abs() < 1e-9);
+            assert!((bmi - 15.43).abs() < 0.01);
+            assert_eq!(plain, ('l', ()));
+
+            let (name, (len,)) = guard_debug!(
+                | len > 3 => String::from("long"),
+                | otherwise => String::from("short"),
+                where { len = "hgm".len(); }
+            );
+            assert_eq!((name.as_str(), len), ("short", 3));
+        }
+        #[cfg(not(feature = "debug"))]
+        {
+            assert_eq!(outcome, "underweight");
+            assert_eq!(plain, 'l');
+        }
+    }
+
+    fn_guard!(
+        fn keyword_kind(r#type: &str, r#ref: bool) -> &'static str
+        | r#type == "fn" && r#ref => "function reference",
+        | r#type == "fn" => "function",
+        | otherwise => r#match,
+        where r#match = if r#ref { "reference" } else { "value" }
+    );
+
+    named_guard!(
+        fn keyword_flags(r#in: u8) -> u8 {
+            r#if: r#in > 10 => 1,
+            r#else: r#in == 0 => 2,
+            _ => 0,
+        }
+    );
+
+    #[test]
+    fn macros_raw_identifiers() {
+        let r#type = 3;
+        let r#match = guard!(
+            | r#type < r#loop => r#type,
+            | otherwise => r#await,
+            where r#loop = 5, r#await = r#loop
+        );
+        assert_eq!(r#match, 3);
+
+        let r#struct = guard!(on r#enum = r#type * 2; | r#enum > 5 => r#enum, | otherwise => 0);
+        assert_eq!(r#struct, 6);
+
+        let r#yield = guard!(
+            default 0;
+            where? r#move = Some(r#type);
+            | r#move > 2 => r#move * 10,
+        );
+        assert_eq!(r#yield, 30);
+
+        let r#use = case!(Some(r#type) =>
+            | Some(r#as) if r#as > r#where => r#as,
+            | _ => 0,
+            where r#where = 1
+        );
+        assert_eq!(r#use, 3);
+
+        let r#const = guard_opt!(| let Some(r#mut) = Some(r#type) => r#mut + 1);
+        assert_eq!(r#const, Some(4));
+
+        assert_eq!(keyword_kind("fn", true), "function reference");
+        assert_eq!(keyword_kind("let", false), "value");
+        assert_eq!(keyword_flags(11), 1);
+        assert!(keyword_flags::r#else(0));
+
+        assert_eq!(guard_debug_arms!(| r#type > 1 => 1, | otherwise => 0), ["r#type > 1", "otherwise"]);
+    }
+
+    #[test]
+    fn macros_guard_literal_true_fallback() {
+        let evaluated = std::cell::Cell::new(0);
+        let probe = |result: bool| {
+            evaluated.set(evaluated.get() + 1);
+            result
+        };
+
+        let fallback = guard!(| probe(false) => 1, | true => 2);
+        assert_eq!(fallback, 2);
+        assert_eq!(evaluated.get(), 1);
+
+        let with_where = guard!(| n > 10 => "big", | true => "small", where n = 4);
+        assert_eq!(with_where, "small");
+
+        // 常量 false 的分支永远不会命中，结果表达式也不会求值
+        let skipped = guard!(
+            | false => { evaluated.set(100); 0 },
+            | probe(true) => 1,
+            | otherwise => 2,
+        );
+        assert_eq!(skipped, 1);
+        assert_eq!(evaluated.get(), 2);
+
+        assert_eq!(guard_debug_arms!(| n > 1 => 1, | true => 0), ["n > 1", "true"]);
+    }
+
+    // `| true =>` 之后的分支永远不会被检查，调用处产生 unused_must_use 警告
+    #[allow(unused_must_use)]
+    #[test]
+    fn macros_guard_arms_after_literal_true() {
+        let evaluated = std::cell::Cell::new(0);
+        let probe = |result: bool| {
+            evaluated.set(evaluated.get() + 1);
+            result
+        };
+
+        let first = guard!(
+            | probe(false) => 'a',
+            | true => 'b',
+            | probe(true) => 'c',
+            | otherwise => 'd',
+            where _unused = probe(false)
+        );
+        assert_eq!(first, 'b');
+        // where 绑定与 true 之前的条件各求值一次，被丢弃的分支从不求值
+        assert_eq!(evaluated.get(), 2);
+
+        // 兜底分支落在 8 个一批的累积范围内时同样被识别，其后的分支全部丢弃
+        evaluated.set(0);
+        let second = guard!(
+            | probe(false) => 0,
+            | true => 1,
+            | probe(true) => 2, | probe(true) => 3, | probe(true) => 4, | probe(true) => 5,
+            | probe(true) => 6, | probe(true) => 7, | probe(true) => 8, | probe(true) => 9,
+        );
+        assert_eq!(second, 1);
+        assert_eq!(evaluated.get(), 1);
+
+        evaluated.set(0);
+        let third = guard!(
+            | probe(false) => 0, | probe(false) => 1, | probe(false) => 2,
+            | otherwise => 3,
+            | probe(true) => 4, | probe(true) => 5, | probe(true) => 6, | probe(true) => 7,
+            | probe(true) => 8, | let 9 = 9 => 9,
+        );
+        assert_eq!(third, 3);
+        assert_eq!(evaluated.get(), 3);
+    }
+
+    /// 固定种子的 xorshift 生成器，保证测试结果确定
+    struct XorShift(u64);
+
+    impl XorShift {
+        fn below(&mut self, total: u64) -> u64 {
+            self.0 ^= self.0 << 13;
+            self.0 ^= self.0 >> 7;
+            self.0 ^= self.0 << 17;
+            self.0 % total
+        }
+    }
+
+    #[test]
+    fn macros_guard_weighted() {
+        let mut rng = XorShift(0x9e37_79b9_7f4a_7c15);
+        let evaluated = std::cell::Cell::new(0);
+        let mut counts = [0u32; 3];
+        let level = 4;
+        for _ in 0..3000 {
+            let pick = guard_weighted!(|total| rng.below(total);
+                | level > 2 => (1, 'a'),
+                | level > 3 => (2, { evaluated.set(evaluated.get() + 1); 'b' }),
+                | level > 9 => (100, 'x'),
+                | otherwise => (3, 'c'),
+            );
+            counts[match pick { Some('a') => 0, Some('b') => 1, _ => 2 }] += 1;
+        }
+        // 条件不成立的分支永远不会被选中；比例大致为 1:2:3
+        assert_eq!(counts.iter().sum::<u32>(), 3000);
+        assert!((400..600).contains(&counts[0]), "{counts:?}");
+        assert!((900..1100).contains(&counts[1]), "{counts:?}");
+        assert!((1400..1600).contains(&counts[2]), "{counts:?}");
+        // 只求值被选中分支的结果
+        assert_eq!(evaluated.get(), counts[1]);
+
+        // 同一种子得到同样的序列
+        let mut first = XorShift(7);
+        let mut second = XorShift(7);
+        for _ in 0..20 {
+            let a = guard_weighted!(|t| first.below(t); | level > 0 => (1, 1), | otherwise => (1, 2));
+            let b = guard_weighted!(|t| second.below(t); | level > 0 => (1, 1), | otherwise => (1, 2));
+            assert_eq!(a, b);
+        }
+
+        let none: Option<()> = guard_weighted!(|t| rng.below(t); | level < 0 => (5, ()));
+        assert_eq!(none, None);
+        let bad_source = std::panic::catch_unwind(|| guard_weighted!(|total| total; | true => (1, ())));
+        assert!(bad_source.is_err());
+    }
+
+    #[cfg(feature = "rand")]
+    #[test]
+    fn macros_guard_weighted_rand() {
+        use rand::SeedableRng;
+        use rand::rngs::StdRng;
+
+        let level = 4;
+        let mut rng = StdRng::seed_from_u64(42);
+        let mut counts = [0u32; 3];
+        for _ in 0..3000 {
+            let pick = guard_weighted!(rng = &mut rng;
+                | level > 2 => (1, 0),
+                | level > 3 => (2, 1),
+                | level > 9 => (100, 3),
+                | otherwise => (3, 2),
+            );
+            counts[pick.unwrap()] += 1;
+        }
+        assert!((400..600).contains(&counts[0]), "{counts:?}");
+        assert!((900..1100).contains(&counts[1]), "{counts:?}");
+        assert!((1400..1600).contains(&counts[2]), "{counts:?}");
+
+        // 同一种子得到同样的序列
+        let mut first = StdRng::seed_from_u64(7);
+        let mut second = StdRng::seed_from_u64(7);
+        for _ in 0..20 {
+            let a = guard_weighted!(rng = &mut first; | level > 0 => (1, 1), | otherwise => (1, 2));
+            let b = guard_weighted!(rng = &mut second; | level > 0 => (1, 1), | otherwise => (1, 2));
+            assert_eq!(a, b);
+        }
+        let none: Option<()> = guard_weighted!(rng = &mut rng; | level < 0 => (5, ()));
+        assert_eq!(none, None);
+    }
+
+    #[test]
+    fn macros_guard_grouped_conditions() {
+        let order = std::cell::RefCell::new(Vec::new());
+        let check = |name: &'static str, result: bool| {
+            order.borrow_mut().push(name);
+            result
+        };
+
+        // 分组是“或”：按顺序短路，第一个成立的条件之后不再求值
+        let grouped = guard!(
+            | check("a", false),
+            | check("b", true),
+            | check("c", true) => "any",
+            | otherwise => "none",
+        );
+        assert_eq!(grouped, "any");
+        assert_eq!(*order.borrow(), ["a", "b"]);
+
+        // 同一分支中的 && 是“与”：一个条件不成立即整体不成立
+        order.borrow_mut().clear();
+        let conjunction = guard!(
+            | check("a", false) && check("b", true) => "all",
+            | otherwise => "none",
+        );
+        assert_eq!(conjunction, "none");
+        assert_eq!(*order.borrow(), ["a"]);
+
+        let classify = |c: char| guard!(
+            | c == 'a', | c == 'e', | c == 'i', | c == 'o', | c == 'u' => "vowel",
+            | c.is_ascii_digit() => "digit",
+            | c.is_alphabetic() && c.is_ascii() => "consonant",
+            | otherwise => "other",
+        );
+        assert_eq!(classify('o'), "vowel");
+        assert_eq!(classify('7'), "digit");
+        assert_eq!(classify('z'), "consonant");
+        assert_eq!(classify('é'), "other");
+
+        // 与 where 子句以及其它分支形式组合；guard_debug_arms! 记录合并后的条件
+        let n = 12;
+        let kind = guard!(
+            | n < lo, | n > hi => "out of range",
+            | otherwise => "in range",
+            where lo = 0, hi = 10
+        );
+        assert_eq!(kind, "out of range");
+        assert_eq!(
+            guard_debug_arms!(| n < 0, | n > 10 => 1, | otherwise => 0),
+            ["n < 0 || n > 10", "otherwise"]
+        );
+    }
+
+    #[test]
+    fn macros_guard_default_when_absent() {
+        let checked = std::cell::Cell::new(0);
+        let probe = |result: bool| {
+            checked.set(checked.get() + 1);
+            result
+        };
+        let settings = [("width", "80"), ("depth", "deep")];
+        let lookup = |key: &str| settings.iter().find(|(k, _)| *k == key).map(|(_, v)| *v);
+
+        let width = guard!(
+            default -1;
+            where? raw = lookup("width");
+            where? width = raw.parse::<i32>().ok();
+            | probe(width > limit) => limit,
+            | otherwise => width,
+            where limit = 100
+        );
+        assert_eq!(width, 80);
+        assert_eq!(checked.get(), 1);
+
+        // 缺失的绑定直接取默认值，后续的可选绑定与所有分支都不会求值
+        let height = guard!(
+            default -1;
+            where? raw = lookup("height");
+            where? height = { checked.set(checked.get() + 10); raw.parse::<i32>().ok() };
+            | probe(height > 0) => height,
+            | otherwise => 0,
+        );
+        assert_eq!(height, -1);
+        assert_eq!(checked.get(), 1);
+
+        let depth = guard!(
+            default 0;
+            where? raw = lookup("depth");
+            where? depth = raw.parse::<i32>().ok();
+            | probe(depth > 0) => depth,
+        );
+        assert_eq!(depth, 0);
+        assert_eq!(checked.get(), 1);
+    }
+
+    #[test]
+    fn macros_guard_type_annotation() {
+        let level = 7;
+        // 不标注时这些字面量会被推断为 i32，位移后溢出
+        let mask = guard!(: u64;
+            | level > 5 => 1 << 40,
+            | level > 2 => 1 << 20,
+            | otherwise => 0,
+        );
+        assert_eq!(mask, 1_099_511_627_776);
+        assert_eq!(std::mem::size_of_val(&mask), 8);
+
+        let ratio = guard!(: f32; on level; | level > 5 => 0.5, | otherwise => 1.0);
+        assert!((ratio - 0.5).abs() < f32::EPSILON);
+
+        let wrapped = guard!(: Option<u8>; map = Some; | level > 100 => 1, | otherwise => 200);
+        assert_eq!(wrapped, Some(200));
+    }
+
+    #[test]
+    fn macros_guard_map() {
+        let lookup = |key: &str| {
+            guard!(
+                map = Some;
+                | key == "one" => 1,
+                | key.len() > limit => 0,
+                | otherwise => key.len(),
+                where limit = 4,
+            )
+        };
+        assert_eq!(lookup("one"), Some(1));
+        assert_eq!(lookup("longer"), Some(0));
+        assert_eq!(lookup("ab"), Some(2));
+
+        let calls = std::cell::Cell::new(0);
+        let wrap = |r: i32| {
+            calls.set(calls.get() + 1);
+            r * 10
+        };
+        let v = 3;
+        let result = guard!(map = wrap; on v; | v > 2 => v, | otherwise => 0);
+        assert_eq!(result, 30);
+        assert_eq!(calls.get(), 1);
+    }
+
+    #[test]
+    fn macros_guard_into() {
+        let describe = |n: i32| {
+            guard_into!(-> String;
+                | n == 0 => "zero",
+                | n < 0 => format!("{} below zero", -n),
+                | otherwise => unit,
+                where unit = if n == 1 { "one" } else { "many" },
+            )
+        };
+        assert_eq!(describe(0), "zero");
+        assert_eq!(describe(-3), "3 below zero");
+        assert_eq!(describe(1), "one");
+
+        let path: std::path::PathBuf = guard_into!(-> std::path::PathBuf; | describe(5) == "many" => "/tmp");
+        assert_eq!(path, std::path::Path::new("/tmp"));
+    }
+
+    #[test]
+    fn macros_guard_debug_arms() {
+        let arms: &'static [&'static str] = guard_debug_arms!(
+            | score >= 90 => 'A',
+            | otherwise => 'B',
+            | score.is_positive() => 'C',
+        );
+        assert_eq!(arms, ["score >= 90", "otherwise", "score.is_positive()"]);
+
+        // 没有 otherwise 时不会额外添加条目
+        assert_eq!(guard_debug_arms!(| flag => 1), ["flag"]);
+    }
+
+    #[test]
+    fn macros_guard_with_reason() {
+        let route = |path: &str, admin: bool| {
+            guard_with_reason!(
+                | path.is_empty() => 400,
+                | path.starts_with("/admin"), | path.starts_with("/ops") => if admin { 200 } else { 403 },
+                | let Some(id) = path.strip_prefix("/users/") && !id.is_empty() => 200,
+                | len > 64 => 414,
+                | otherwise => 404,
+                where len = path.len()
+            )
+        };
+        assert_eq!(route("", false), ("path.is_empty()", 400));
+        assert_eq!(
+            route("/ops/deploy", true),
+            ("path.starts_with(\"/admin\") || path.starts_with(\"/ops\")", 200)
+        );
+        assert_eq!(route("/users/7", false).1, 200);
+        assert!(route("/users/7", false).0.starts_with("let Some(id)"));
+        assert_eq!(route(&"x".repeat(80), false), ("len > 64", 414));
+        assert_eq!(route("/nope", false), ("otherwise", 404));
+
+        // 返回的文本就是 guard_debug_arms! 中对应的条目
+        let n = 3;
+        let (reason, _) = guard_with_reason!(| n < 0 => "neg", | n % 2 == 1 => "odd", | otherwise => "even");
+        let arms = guard_debug_arms!(| n < 0 => "neg", | n % 2 == 1 => "odd", | otherwise => "even");
+        assert_eq!(reason, arms[1]);
+
+        // 没有 otherwise 且无分支命中时与 guard! 一样 panic
+        let missing = std::panic::catch_unwind(|| guard_with_reason!(| n > 10 => ()));
+        assert!(missing.is_err());
+    }
+
+    #[test]
+    fn macros_guard_do_runs_matched_arm_once() {
+        use std::cell::RefCell;
+
+        let effects = RefCell::new(Vec::new());
+        let checks = RefCell::new(Vec::new());
+        let check = |name: &'static str, value: bool| {
+            checks.borrow_mut().push(name);
+            value
+        };
+        for _ in 0..3 {
+            let value = guard_do!(
+                | check("a", false) => { effects.borrow_mut().push("a"); 1 },
+                | check("b", true) => { effects.borrow_mut().push("b"); 2 },
+                | check("c", true) => { effects.borrow_mut().push("c"); 3 },
+                | otherwise => { effects.borrow_mut().push("otherwise"); 4 },
+            );
+            assert_eq!(value, 2);
+        }
+        assert_eq!(*effects.borrow(), ["b", "b", "b"]);
+        assert_eq!(*checks.borrow(), ["a", "b", "a", "b", "a", "b"]);
+    }
+
+    #[test]
+    fn macros_guard_where_hygiene() {
+        let bmi = 0.0;
         let result = guard! {
             | bmi <= 18.5 => "Underweight",
             | otherwise => "Unknown",
-            where bmi = weight / height
+            where bmi = 70.0 / (1.75 * 1.75)
         };
         assert_eq!(result, "Unknown");
+        // where 绑定只在 guard! 内部可见，外部同名变量保持不变
+        assert_eq!(bmi, 0.0);
     }
-    fn_guard! {
-        #[inline]
-        pub fn bmi_tell(weight:f64,height:f64) -> &'static str
-        | bmi <= 18.5 => "Underweight",
-        | otherwise => "Unknown",
-        where bmi = weight / height
+
+    fn_guard!(
+        fn area_class(w: u32, h: u32) -> &'static str
+        | area == 0 => "empty",
+        | area > limit => "large",
+        | otherwise => "normal",
+        where {
+            area = w * h;
+            limit = 100;
+        }
+    );
+
+    #[test]
+    fn macros_where_brace_form() {
+        let x = 6;
+        let comma = guard!(| z > 10 => "big", | otherwise => "small", where y = x * 2, z = y + 1);
+        let brace = guard!(| z > 10 => "big", | otherwise => "small", where { y = x * 2; z = y + 1 });
+        assert_eq!(comma, brace);
+        assert!(guard!(| z == 13 => true, | otherwise => false, where { y = x * 2; z = y + 1; }));
+
+        let kind = case!(x % modulus =>
+            | 0 => "multiple",
+            | r if r == half => "half",
+            | _ => "other",
+            where { modulus = 4; half = modulus / 2; }
+        );
+        assert_eq!(kind, "half");
+
+        assert_eq!(area_class(0, 5), "empty");
+        assert_eq!(area_class(20, 6), "large");
+        assert_eq!(area_class(3, 3), "normal");
+    }
+}
+
+// 常见用法在最严格的 lint 设置下展开后不应产生任何警告
+#[cfg(test)]
+#[deny(unreachable_patterns, unreachable_code, unused, clippy::all, clippy::pedantic)]
+mod strict_lints {
+    #[derive(Debug, Clone, Copy, PartialEq)]
+    enum Signal {
+        Stop,
+        Slow,
+        Go,
     }
+
+    fn_guard!(
+        fn signal(speed: u32) -> Signal
+        | speed == 0 => Signal::Stop,
+        | speed < limit => Signal::Slow,
+        | otherwise => Signal::Go,
+        where limit = 30
+    );
+
+    fn_guard!(
+        #[result]
+        fn checked_half(n: u32) -> Result<u32, u32>
+        | n.is_multiple_of(2) => n / 2,
+        | otherwise => Err(n),
+    );
+
+    fn_guard!(
+        fn must_be_known(n: u8) -> &'static str
+        | n == 0 => "zero",
+        | n == 1 => "one",
+        | otherwise => unreachable!("unknown value {n}"),
+    );
+
     #[test]
-    fn macros_fn_guard() {
-        let result = bmi_tell(70.0, 1.75);
-        assert_eq!(result, "Unknown");
+    fn guard_forms() {
+        let n = 7;
+        let sign = guard!(| n < 0 => -1, | n == 0 => 0, | otherwise => 1);
+        assert_eq!(sign, 1);
+
+        let exhaustive: i32 = guard!(| n > 5 => 1, | n <= 5 => 0);
+        assert_eq!(exhaustive, 1);
+
+        let wide: i64 = guard_into!(-> i64; | n > 0 => n, | otherwise => 0);
+        assert_eq!(wide, 7);
+
+        let checked: Result<i32, &str> = guard_result!(| n > 0 => n, err = "negative");
+        assert_eq!(checked, Ok(7));
+
+        let mut hits = 0;
+        guard_each!(| n > 0 => hits += 1, | n > 5 => hits += 1, | otherwise => hits += 10);
+        assert_eq!(hits, 12);
+
+        assert_eq!(signal(0), Signal::Stop);
+        assert_eq!(signal(45), Signal::Go);
+        assert_eq!(checked_half(6), Ok(3));
+        assert_eq!(must_be_known(1), "one");
+    }
+
+    #[test]
+    fn case_forms() {
+        let value = Some(3);
+        let name = case!(value =>
+            | Some(0) => "zero",
+            | Some(n) if n < limit => "small",
+            | Some(_) => "large",
+            | None => "none",
+            where limit = 10
+        );
+        assert_eq!(name, "small");
+
+        let wait = case!(#[over Signal] signal(10) =>
+            | Signal::Stop => 30,
+            | Signal::Slow => 5,
+            | Signal::Go => 0,
+        );
+        assert_eq!(wait, 5);
+
+        let fallback = case!(value => | Some(1 | 2) => 'a', | otherwise => 'b');
+        assert_eq!(fallback, 'b');
+
+        let both = case_all!(
+            value => [| Some(n) if n > 0 => n, | _ => 0],
+            Signal::Go => [| Signal::Go => "go", | otherwise => "wait"],
+        );
+        assert_eq!(both, (3, "go"));
+
+        let class = lex_case!(b'7' => | b'0'..=b'9' => "digit", | _ => "other");
+        assert_eq!(class, "digit");
+
+        let mut steps = 0;
+        let zero = case!(5u32 => | 0 => steps, | n => redispatch { steps += 1; n - 1 });
+        assert_eq!(zero, 5);
+
+        assert!(case_matches!(value => | Some(0), | Some(n) if n > 2));
+        assert!(case_matches!(signal(10) => | Signal::Stop | Signal::Slow, | Signal::Go));
     }
 }
@@ -0,0 +1,25 @@
+//! 编译失败用例：每个 `tests/ui/**/*.rs` 都必须编译失败，且错误输出与同名 `.stderr` 一致
+//!
+//! 更新快照：`TRYBUILD=overwrite cargo test --test ui --features strict`
+
+#[test]
+// 快照对应稳定版编译器的诊断输出，nightly 的措辞可能不同
+#[cfg_attr(feature = "nightly", ignore = "snapshots track the stable compiler's diagnostics")]
+fn ui() {
+    let t = trybuild::TestCases::new();
+    t.compile_fail("tests/ui/*.rs");
+    // strict 特性下的检查只在启用该特性时编译失败
+    if cfg!(feature = "strict") {
+        t.compile_fail("tests/ui/strict/*.rs");
+    }
+    // #[boxed] 在没有 alloc 时整体报错，不再检查返回类型
+    if cfg!(feature = "alloc") {
+        t.compile_fail("tests/ui/alloc/*.rs");
+    } else {
+        t.compile_fail("tests/ui/no_alloc/*.rs");
+    }
+    // profile / tracing 插桩后 if / else 链的 span 不同，这些快照只对应不插桩的展开
+    if !cfg!(any(feature = "profile", feature = "tracing")) {
+        t.compile_fail("tests/ui/uninstrumented/*.rs");
+    }
+}
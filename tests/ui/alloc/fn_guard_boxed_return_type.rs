@@ -0,0 +1,10 @@
+use hgm::fn_guard;
+
+fn_guard!(
+    #[boxed]
+    fn count(n: u8) -> u8
+    | n > 3 => 3,
+    | otherwise => n,
+);
+
+fn main() {}
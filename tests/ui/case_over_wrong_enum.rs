@@ -0,0 +1,14 @@
+use hgm::case;
+
+enum Light {
+    Red,
+    Green,
+}
+
+fn main() {
+    let _ = (Light::Red, Light::Green);
+    let _wait = case!(#[over Light] Some(3) =>
+        | Some(_) => 1,
+        | None => 0,
+    );
+}
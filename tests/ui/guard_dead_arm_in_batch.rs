@@ -0,0 +1,18 @@
+#![deny(unused_must_use)]
+
+use hgm::guard;
+
+fn main() {
+    let n = 3;
+    let _early = guard!(
+        | n == 0 => 0,
+        | true => 1,
+        | n == 2 => 2, | n == 3 => 3, | n == 4 => 4, | n == 5 => 5,
+        | n == 6 => 6, | n == 7 => 7, | n == 8 => 8, | n == 9 => 9,
+    );
+    let _middle = guard!(
+        | n == 0 => 0,
+        | otherwise => 1,
+        | n == 2 => 2,
+    );
+}
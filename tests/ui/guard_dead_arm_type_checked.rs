@@ -0,0 +1,17 @@
+#![allow(unused_must_use)]
+
+use hgm::guard;
+
+fn main() {
+    let n = 3;
+    let _ = guard!(
+        | n < 0 => -1,
+        | otherwise => 1,
+        | this_does_not_exist(n) => 2,
+    );
+    let _ = guard!(
+        | n < 0 => -1,
+        | true => 1,
+        | n > 5 => "wrong type",
+    );
+}
@@ -0,0 +1,11 @@
+use hgm::fn_guard;
+
+trait Shape {}
+
+fn_guard!(
+    #[boxed]
+    fn shape(n: u8) -> Box<dyn Shape>
+    | otherwise => panic!("{n}"),
+);
+
+fn main() {}
@@ -0,0 +1,9 @@
+use hgm::case;
+
+fn main() {
+    let n = 4;
+    let _parity = case!(n =>
+        | otherwise => "any",
+        | 0 => "zero",
+    );
+}
@@ -0,0 +1,9 @@
+use hgm::case;
+
+fn main() {
+    let opt = Some(1);
+    let _name = case!(opt =>
+        | _ => "x",
+        | Some(_) => "y",
+    );
+}
@@ -0,0 +1,11 @@
+use hgm::case;
+
+fn main() {
+    let n = 3;
+    let _name = case!(n =>
+        | 1 => "one",
+        | 3 => "three",
+        | 3 => "three again",
+        | _ => "other",
+    );
+}
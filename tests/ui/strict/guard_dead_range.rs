@@ -0,0 +1,10 @@
+use hgm::guard;
+
+fn main() {
+    let x = 3;
+    let _size = guard!(
+        | x < 10 => "small",
+        | x < 5 => "tiny",
+        | otherwise => "large",
+    );
+}
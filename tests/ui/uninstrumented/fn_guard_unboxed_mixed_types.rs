@@ -0,0 +1,13 @@
+use std::fmt::Display;
+
+use hgm::fn_guard;
+
+fn_guard!(
+    fn label(n: i32) -> impl Display
+    | n < 0 => "negative",
+    | otherwise => n,
+);
+
+fn main() {
+    let _ = label(1);
+}